@@ -1,6 +1,66 @@
-use crate::{token::Token, typechecker::TypeKind};
+use crate::{span::Span, token::Token, typechecker::{IntType, TypeKind}};
 
 
+// program => ( function | struct_decl | enum_decl )* expression?
+// when there is no trailing expression, the program starts at the function named main
+#[derive(Debug)]
+pub struct Program<'t> {
+    pub functions: Vec<Function<'t>>,
+    pub structs: Vec<StructDecl<'t>>,
+    pub enums: Vec<EnumDecl<'t>>,
+    pub main: Option<Expr<'t>>,
+}
+
+// struct_decl -> "struct" IDENTIFIER type_params? "{" field_decl ( "," field_decl )* ","? "}"
+// field_decl -> IDENTIFIER ":" type
+#[derive(Debug)]
+pub struct StructDecl<'t> {
+    pub name: &'t Token<'t>,
+    pub type_params: Vec<&'t Token<'t>>,
+    pub fields: Vec<FieldDecl<'t>>,
+    // set by the typechecker when a struct contains itself, its values are kept on the heap
+    pub recursive: bool,
+}
+
+#[derive(Debug)]
+pub struct FieldDecl<'t> {
+    pub name: &'t Token<'t>,
+    pub type_kind: TypeKind,
+}
+
+// generic functions are checked once, with their type parameters standing for any type
+#[derive(Debug)]
+pub struct Function<'t> {
+    pub name: &'t Token<'t>,
+    pub type_params: Vec<&'t Token<'t>>,
+    pub params: Vec<Param<'t>>,
+    pub return_type: TypeKind,
+    pub body: Expr<'t>,
+}
+
+#[derive(Debug)]
+pub struct Param<'t> {
+    pub name: &'t Token<'t>,
+    pub type_kind: TypeKind,
+}
+
+// enum_decl -> "enum" IDENTIFIER type_params? "{" variant ( "," variant )* ","? "}"
+// variant -> IDENTIFIER ( "(" type ( "," type )* ")" )?
+#[derive(Debug)]
+pub struct EnumDecl<'t> {
+    pub name: &'t Token<'t>,
+    pub type_params: Vec<&'t Token<'t>>,
+    pub variants: Vec<VariantDecl<'t>>,
+    // like StructDecl::recursive, List in enum List { Nil, Cons(int, List) }
+    pub recursive: bool,
+}
+
+#[derive(Debug)]
+pub struct VariantDecl<'t> {
+    pub name: &'t Token<'t>,
+    pub payload: Vec<TypeKind>,
+}
+
 #[derive(Debug)]
 pub enum Expr<'t> {
     Binary(Binary<'t>),
@@ -8,6 +68,72 @@ pub enum Expr<'t> {
     Literal(Literal<'t>),
     Grouping(Grouping<'t>),
     If(If<'t>),
+    Variable(Variable<'t>),
+    Block(Block<'t>),
+    Call(Call<'t>),
+    Logical(Logical<'t>),
+    While(While<'t>),
+    Assign(Assign<'t>),
+    Array(Array<'t>),
+    Index(Index<'t>),
+    StructLiteral(StructLiteral<'t>),
+    Field(Field<'t>),
+    Path(Path<'t>),
+    Match(Match<'t>),
+    Lambda(Lambda<'t>),
+    Cast(Cast<'t>),
+}
+
+impl Expr<'_> {
+    // from the expression's first token to its last
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary(n) => n.span,
+            Expr::Unary(n) => n.span,
+            Expr::Literal(n) => n.span,
+            Expr::Grouping(n) => n.span,
+            Expr::If(n) => n.span,
+            Expr::Variable(n) => n.span,
+            Expr::Block(n) => n.span,
+            Expr::Call(n) => n.span,
+            Expr::Logical(n) => n.span,
+            Expr::While(n) => n.span,
+            Expr::Assign(n) => n.span,
+            Expr::Array(n) => n.span,
+            Expr::Index(n) => n.span,
+            Expr::StructLiteral(n) => n.span,
+            Expr::Field(n) => n.span,
+            Expr::Path(n) => n.span,
+            Expr::Match(n) => n.span,
+            Expr::Lambda(n) => n.span,
+            Expr::Cast(n) => n.span,
+        }
+    }
+
+    // the type recorded by the typechecker, None before typechecking
+    pub fn type_kind(&self) -> Option<TypeKind> {
+        match self {
+            Expr::Binary(n) => n.type_kind.clone(),
+            Expr::Unary(n) => n.type_kind.clone(),
+            Expr::Literal(n) => n.type_kind.clone(),
+            Expr::Grouping(n) => n.type_kind.clone(),
+            Expr::If(n) => n.type_kind.clone(),
+            Expr::Variable(n) => n.type_kind.clone(),
+            Expr::Block(n) => n.type_kind.clone(),
+            Expr::Call(n) => n.type_kind.clone(),
+            Expr::Logical(n) => n.type_kind.clone(),
+            Expr::While(n) => n.type_kind.clone(),
+            Expr::Assign(n) => n.type_kind.clone(),
+            Expr::Array(n) => n.type_kind.clone(),
+            Expr::Index(n) => n.type_kind.clone(),
+            Expr::StructLiteral(n) => n.type_kind.clone(),
+            Expr::Field(n) => n.type_kind.clone(),
+            Expr::Path(n) => n.type_kind.clone(),
+            Expr::Match(n) => n.type_kind.clone(),
+            Expr::Lambda(n) => n.type_kind.clone(),
+            Expr::Cast(n) => n.type_kind.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -15,6 +141,12 @@ pub enum BinaryOp {
     Add,
     Minus,
     Times,
+    // like Add, Minus and Times, but never checked for overflow
+    WrappingAdd,
+    WrappingMinus,
+    WrappingTimes,
+    Divide,
+    Modulo,
     BangEqual,
     EqualEqual,
     Greater,
@@ -25,63 +157,280 @@ pub enum BinaryOp {
 
 #[derive(Debug)]
 pub enum UnaryOp {
-    Minus
+    Minus,
+    WrappingMinus,
+    Not,
+}
+
+// kept apart from BinaryOp because the right operand is not always evaluated
+#[derive(Debug)]
+pub enum LogicalOp {
+    And,
+    Or,
 }
 
 #[derive(Debug)]
 pub enum LiteralType {
-    Int
+    // the value and the type the suffix names, like the u8 of 0xffu8. The scanner works them out
+    Int(u64, Option<IntType>),
+    Float,
+    Bool,
+    Str,
+    // stands in for an Error token, programs with one are never typechecked
+    Error,
 }
 
 #[derive(Debug)]
 pub struct Binary<'t> {
-    pub token: &'t Token,
+    pub token: &'t Token<'t>,
     pub operation: BinaryOp,
     pub left: Box<Expr<'t>>,
     pub right: Box<Expr<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub struct Logical<'t> {
+    pub token: &'t Token<'t>,
+    pub operation: LogicalOp,
+    pub left: Box<Expr<'t>>,
+    pub right: Box<Expr<'t>>,
+    pub span: Span,
     pub type_kind: Option<TypeKind>,
 }
 
 #[derive(Debug)]
 pub struct Unary<'t> {
-    pub token: &'t Token,
+    pub token: &'t Token<'t>,
     pub operation: UnaryOp,
     pub right: Box<Expr<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// cast -> unary ( "as" type )*
+// converts between integer types, between integers and float, and from bool to an integer
+#[derive(Debug)]
+pub struct Cast<'t> {
+    pub token: &'t Token<'t>,
+    pub expr: Box<Expr<'t>>,
+    pub target: TypeKind,
+    pub span: Span,
     pub type_kind: Option<TypeKind>,
 }
 
 #[derive(Debug)]
 pub struct Literal<'t> {
-    pub token: &'t Token,
+    pub token: &'t Token<'t>,
     pub literal_type: LiteralType,
+    pub span: Span,
     pub type_kind: Option<TypeKind>,
 }
 
+// the span includes the parentheses
 #[derive(Debug)]
 pub struct Grouping<'t> {
     pub expr: Box<Expr<'t>>,
+    pub span: Span,
     pub type_kind: Option<TypeKind>,
 }
 
 #[derive(Debug)]
 pub struct If<'t> {
-    pub token: &'t Token,
+    pub token: &'t Token<'t>,
     pub condition: Box<Expr<'t>>,
     pub then_branch: Box<Expr<'t>>,
     pub else_branch: Option<Box<Expr<'t>>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub struct Variable<'t> {
+    pub token: &'t Token<'t>,
+    // the types a use of a let-polymorphic lambda picked for its type variables, in the
+    // order of the let's quantified variables, or the type arguments a use of a generic
+    // function picked, in the order of its type parameters. Empty for every other variable
+    pub instance: Vec<TypeKind>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// block -> "{" statement* expression? "}"
+// without a trailing expression the block is Unit typed
+#[derive(Debug)]
+pub struct Block<'t> {
+    pub statements: Vec<Stmt<'t>>,
+    pub expr: Option<Box<Expr<'t>>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub enum Stmt<'t> {
+    Let(Let<'t>),
+    Expr(Expr<'t>),
+}
+
+// the name is in scope from the next statement to the end of the enclosing block
+#[derive(Debug)]
+pub struct Let<'t> {
+    pub name: &'t Token<'t>,
+    pub mutable: bool,
+    // the annotated type, inferred from the initializer when there is none
+    pub type_kind: Option<TypeKind>,
+    pub initializer: Expr<'t>,
+    // type variables of a lambda's type that every use of the name can instantiate
+    // differently. Filled in by the typechecker
+    pub quantified: Vec<u32>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct While<'t> {
+    pub token: &'t Token<'t>,
+    pub condition: Box<Expr<'t>>,
+    pub body: Box<Expr<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub struct Assign<'t> {
+    pub token: &'t Token<'t>,
+    pub target: Box<Expr<'t>>,
+    pub value: Box<Expr<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub struct Call<'t> {
+    pub token: &'t Token<'t>,
+    pub callee: Box<Expr<'t>>,
+    pub arguments: Vec<Expr<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// array -> "[" expression ( "," expression )* ","? "]"
+#[derive(Debug)]
+pub struct Array<'t> {
+    pub token: &'t Token<'t>,
+    pub elements: Vec<Expr<'t>>,
+    pub span: Span,
     pub type_kind: Option<TypeKind>,
 }
 
+#[derive(Debug)]
+pub struct Index<'t> {
+    pub token: &'t Token<'t>,
+    pub array: Box<Expr<'t>>,
+    pub index: Box<Expr<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// struct_literal -> IDENTIFIER "{" IDENTIFIER ":" expression ( "," IDENTIFIER ":" expression )* ","? "}"
+// fields can be given in any order, they are evaluated in the order written
+#[derive(Debug)]
+pub struct StructLiteral<'t> {
+    pub name: &'t Token<'t>,
+    pub fields: Vec<(&'t Token<'t>, Expr<'t>)>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub struct Field<'t> {
+    pub token: &'t Token<'t>,
+    pub object: Box<Expr<'t>>,
+    pub name: &'t Token<'t>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// path -> IDENTIFIER "::" IDENTIFIER
+// names an enum variant, called like a function when it has a payload
+#[derive(Debug)]
+pub struct Path<'t> {
+    pub qualifier: &'t Token<'t>,
+    pub name: &'t Token<'t>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// match_expr -> "match" expression "{" arm ( "," arm )* ","? "}"
+// arm -> pattern "=>" expression
+// the "," after an arm whose body is a block can be left out
+#[derive(Debug)]
+pub struct Match<'t> {
+    pub token: &'t Token<'t>,
+    pub scrutinee: Box<Expr<'t>>,
+    pub arms: Vec<Arm<'t>>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+#[derive(Debug)]
+pub struct Arm<'t> {
+    pub pattern: Pattern<'t>,
+    pub body: Expr<'t>,
+}
+
+// lambda -> ( "||" | "|" lambda_param ( "," lambda_param )* "|" ) expression
+// lambda_param -> IDENTIFIER ( ":" type )?
+#[derive(Debug)]
+pub struct Lambda<'t> {
+    pub token: &'t Token<'t>,
+    pub params: Vec<LambdaParam<'t>>,
+    pub body: Box<Expr<'t>>,
+    // variables of enclosing scopes the body uses, in the order it first uses them. Filled in
+    // by the typechecker, their values are copied into the closure when it is created
+    pub captures: Vec<(String, TypeKind)>,
+    pub span: Span,
+    pub type_kind: Option<TypeKind>,
+}
+
+// without an annotation the type is inferred from how the lambda is used
+#[derive(Debug)]
+pub struct LambdaParam<'t> {
+    pub name: &'t Token<'t>,
+    pub type_kind: Option<TypeKind>,
+}
+
+// pattern -> "_" | IDENTIFIER | NUMBER | "true" | "false" | path ( "(" pattern ( "," pattern )* ")" )?
+#[derive(Debug)]
+pub enum Pattern<'t> {
+    Wildcard(&'t Token<'t>),
+    // matches anything and binds it to the name for the arm's body
+    Binding(&'t Token<'t>),
+    Literal(&'t Token<'t>, LiteralType),
+    Variant(VariantPattern<'t>),
+}
+
+#[derive(Debug)]
+pub struct VariantPattern<'t> {
+    pub enum_name: &'t Token<'t>,
+    pub variant: &'t Token<'t>,
+    pub fields: Vec<Pattern<'t>>,
+}
+
 // print string representation of AST for debugging
 #[allow(dead_code)]
 pub fn tree_repr(root: &Expr, indent: usize) -> String {
-    let repr;
     match root {
         Expr::Binary(n) => {
             let op = match n.operation {
                 BinaryOp::Add => "+",
                 BinaryOp::Minus => "-",
                 BinaryOp::Times => "*",
+                BinaryOp::WrappingAdd => "+%",
+                BinaryOp::WrappingMinus => "-%",
+                BinaryOp::WrappingTimes => "*%",
+                BinaryOp::Divide => "/",
+                BinaryOp::Modulo => "%",
                 BinaryOp::BangEqual => "!=",
                 BinaryOp::EqualEqual => "==",
                 BinaryOp::Greater => ">",
@@ -89,30 +438,181 @@ pub fn tree_repr(root: &Expr, indent: usize) -> String {
                 BinaryOp::Less => "<",
                 BinaryOp::LessEqual => "<=",
             };
-            repr = format!("{:>width$}\n{left}\n{right}",
+            format!("{op:>width$}\n{left}\n{right}",
                 op=op,
                 left=tree_repr(n.left.as_ref(), indent + 1),
                 right=tree_repr(n.right.as_ref(), indent + 1),
                 width = indent
-            );
+            )
+        }
+        Expr::Logical(n) => {
+            let op = match n.operation {
+                LogicalOp::And => "&&",
+                LogicalOp::Or => "||",
+            };
+            format!("{op:>width$}\n{left}\n{right}",
+                op=op,
+                left=tree_repr(n.left.as_ref(), indent + 1),
+                right=tree_repr(n.right.as_ref(), indent + 1),
+                width = indent
+            )
         }
         Expr::Unary(n) => {
             let op = match n.operation {
-                UnaryOp::Minus => "-"
+                UnaryOp::Minus => "-",
+                UnaryOp::WrappingMinus => "-%",
+                UnaryOp::Not => "!",
             };
-            repr = format!("{:>width$}\n{right}",
+            format!("{op:>width$}\n{right}",
                 op=op,
                 right=tree_repr(n.right.as_ref(), indent + 1),
                 width = indent
-            );
+            )
         }
         Expr::Literal(n) => {
-            repr = format!("{:>width$}", lit=n.token.lexeme.clone(), width = indent);
+            format!("{lit:>width$}", lit=n.token.lexeme.to_string(), width = indent)
         }
         Expr::Grouping(n) => {
-            repr = tree_repr(n.expr.as_ref(), indent);
+            tree_repr(n.expr.as_ref(), indent)
+        }
+        Expr::If(n) => {
+            let repr = format!("{op:>width$}\n{condition}\n{then}",
+                op="if",
+                condition=tree_repr(n.condition.as_ref(), indent + 1),
+                then=tree_repr(n.then_branch.as_ref(), indent + 1),
+                width = indent
+            );
+            match &n.else_branch {
+                Some(else_branch) => format!("{repr}\n{op:>width$}\n{otherwise}",
+                    op="else",
+                    otherwise=tree_repr(else_branch.as_ref(), indent + 1),
+                    width = indent
+                ),
+                None => repr,
+            }
+        }
+        Expr::Variable(n) => {
+            format!("{name:>width$}", name=n.token.lexeme.to_string(), width = indent)
+        }
+        Expr::Block(n) => {
+            let mut lines: Vec<String> = n.statements.iter().map(|statement| match statement {
+                Stmt::Let(let_stmt) => format!("{name:>width$} =\n{init}",
+                    name=format!("let {}", let_stmt.name.lexeme),
+                    init=tree_repr(&let_stmt.initializer, indent + 1),
+                    width = indent
+                ),
+                Stmt::Expr(expr) => tree_repr(expr, indent),
+            }).collect();
+            if let Some(expr) = &n.expr {
+                lines.push(tree_repr(expr.as_ref(), indent));
+            }
+            lines.join("\n")
+        }
+        Expr::While(n) => {
+            format!("{op:>width$}\n{condition}\n{body}",
+                op="while",
+                condition=tree_repr(n.condition.as_ref(), indent + 1),
+                body=tree_repr(n.body.as_ref(), indent + 1),
+                width = indent
+            )
+        }
+        Expr::Assign(n) => {
+            format!("{op:>width$}\n{target}\n{value}",
+                op="=",
+                target=tree_repr(n.target.as_ref(), indent + 1),
+                value=tree_repr(n.value.as_ref(), indent + 1),
+                width = indent
+            )
+        }
+        Expr::Array(n) => {
+            let elements: Vec<String> = n.elements.iter().map(|element| tree_repr(element, indent + 1)).collect();
+            format!("{op:>width$}\n{elements}",
+                op="[]",
+                elements=elements.join("\n"),
+                width = indent
+            )
+        }
+        Expr::Index(n) => {
+            format!("{op:>width$}\n{array}\n{index}",
+                op="[i]",
+                array=tree_repr(n.array.as_ref(), indent + 1),
+                index=tree_repr(n.index.as_ref(), indent + 1),
+                width = indent
+            )
+        }
+        Expr::StructLiteral(n) => {
+            let fields: Vec<String> = n.fields.iter().map(|(name, value)| format!("{name:>width$}:\n{value}",
+                name=name.lexeme,
+                value=tree_repr(value, indent + 2),
+                width = indent + 1
+            )).collect();
+            format!("{name:>width$}\n{fields}",
+                name=n.name.lexeme,
+                fields=fields.join("\n"),
+                width = indent
+            )
+        }
+        Expr::Field(n) => {
+            format!("{name:>width$}\n{object}",
+                name=format!(".{}", n.name.lexeme),
+                object=tree_repr(n.object.as_ref(), indent + 1),
+                width = indent
+            )
+        }
+        Expr::Path(n) => {
+            format!("{path:>width$}", path=format!("{}::{}", n.qualifier.lexeme, n.name.lexeme), width = indent)
+        }
+        Expr::Match(n) => {
+            let arms: Vec<String> = n.arms.iter().map(|arm| format!("{pattern:>width$} =>\n{body}",
+                pattern=pattern_repr(&arm.pattern),
+                body=tree_repr(&arm.body, indent + 2),
+                width = indent + 1
+            )).collect();
+            format!("{op:>width$}\n{scrutinee}\n{arms}",
+                op="match",
+                scrutinee=tree_repr(n.scrutinee.as_ref(), indent + 1),
+                arms=arms.join("\n"),
+                width = indent
+            )
+        }
+        Expr::Lambda(n) => {
+            let params: Vec<&str> = n.params.iter().map(|param| param.name.lexeme).collect();
+            format!("{op:>width$}\n{body}",
+                op=format!("|{}|", params.join(", ")),
+                body=tree_repr(n.body.as_ref(), indent + 1),
+                width = indent
+            )
+        }
+        Expr::Cast(n) => {
+            format!("{op:>width$}\n{expr}",
+                op=format!("as {}", n.target),
+                expr=tree_repr(n.expr.as_ref(), indent + 1),
+                width = indent
+            )
+        }
+        Expr::Call(n) => {
+            let arguments: Vec<String> = n.arguments.iter().map(|arg| tree_repr(arg, indent + 1)).collect();
+            format!("{callee}()\n{args}",
+                callee=tree_repr(n.callee.as_ref(), indent),
+                args=arguments.join("\n"),
+            )
+        }
+    }
+}
+
+pub fn pattern_repr(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard(_) => String::from("_"),
+        Pattern::Binding(name) => name.lexeme.to_string(),
+        Pattern::Literal(token, _) => token.lexeme.to_string(),
+        Pattern::Variant(variant) => {
+            let path = format!("{}::{}", variant.enum_name.lexeme, variant.variant.lexeme);
+            if variant.fields.is_empty() {
+                path
+            } else {
+                let fields: Vec<String> = variant.fields.iter().map(pattern_repr).collect();
+                format!("{}({})", path, fields.join(", "))
+            }
         }
-        Expr::If(_) => todo!(),
     }
-    repr
 }
@@ -1,28 +1,244 @@
-use crate::ast::{Binary, BinaryOp, Expr, If, Literal, Unary, UnaryOp};
+use std::collections::{HashMap, HashSet};
 
-pub struct CodeGenerator {
-    bb_label_counter: i32
+use crate::{Options, runtime, scan, token::Token, typechecker::{BUILTINS, IntType, TypeKind, Warning, param_mapping}};
+use crate::ast::{Array, Assign, Binary, BinaryOp, Block, Call, Cast, Expr, Field, Function, If, Index, Lambda, Literal, LiteralType, Logical, LogicalOp, Match, Path, Pattern, Program, Stmt, StructLiteral, Unary, UnaryOp, Variable, While};
+
+// System V integer argument registers, in order
+const ARGUMENT_REGISTERS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+// user functions get a prefix so they can't clash with main or anything linked in from libc
+fn function_symbol(name: &str) -> String {
+    format!("cheer_{}", symbol_name(name))
+}
+
+// names from other modules, like geometry::area, with the :: spelled as a character symbols can have
+fn symbol_name(name: &str) -> String {
+    name.replace("::", ".")
+}
+
+// a generic function's code for some type arguments, like cheer_swap.4PairIisE for swap<Pair<int, str>>
+fn instance_symbol(function_symbol: &str, arguments: &[TypeKind]) -> String {
+    let arguments: Vec<String> = arguments.iter().map(mangle).collect();
+    format!("{}.{}", function_symbol, arguments.concat())
+}
+
+// a type spelled with symbol characters. Every spelling ends where it can be told to, so a
+// list of them can be read back: names have their length in front, type arguments are
+// between I and E, and function parameters end at the _ before the return type
+fn mangle(type_kind: &TypeKind) -> String {
+    match type_kind {
+        TypeKind::Int(int_type) => String::from(match int_type {
+            IntType::I8 => "a",
+            IntType::I16 => "w",
+            IntType::I32 => "l",
+            IntType::I64 => "i",
+            IntType::U8 => "h",
+            IntType::U16 => "t",
+            IntType::U32 => "j",
+            IntType::U64 => "m",
+        }),
+        TypeKind::Float => String::from("d"),
+        TypeKind::Bool => String::from("b"),
+        TypeKind::Str => String::from("s"),
+        TypeKind::Unit => String::from("u"),
+        TypeKind::Array(element_type, length) => format!("A{}_{}", length, mangle(element_type)),
+        TypeKind::Named(name, arguments) if arguments.is_empty() => {
+            let name = symbol_name(name);
+            format!("{}{}", name.len(), name)
+        }
+        TypeKind::Named(name, arguments) => {
+            let name = symbol_name(name);
+            let arguments: Vec<String> = arguments.iter().map(mangle).collect();
+            format!("{}{}I{}E", name.len(), name, arguments.concat())
+        }
+        TypeKind::Function(params, return_type) => {
+            let params: Vec<String> = params.iter().map(mangle).collect();
+            format!("F{}_{}", params.concat(), mangle(return_type))
+        }
+        TypeKind::Var(_) | TypeKind::Param(_) | TypeKind::Error => unreachable!("instances are generated for concrete types"),
+    }
+}
+
+// the instruction suffix for operating on integers of a type
+fn suffix(bits: u32) -> &'static str {
+    match bits {
+        8 => "b",
+        16 => "w",
+        32 => "l",
+        _ => "q",
+    }
+}
+
+// the part of %rax, %rcx or %rdx (given as a, c or d) holding the low bits of a value
+fn register(name: char, bits: u32) -> String {
+    match bits {
+        8 => format!("%{}l", name),
+        16 => format!("%{}x", name),
+        32 => format!("%e{}x", name),
+        _ => format!("%r{}x", name),
+    }
+}
+
+// the instruction that sign or zero extends the low bits of %rax to the whole register, so
+// every integer is held in 64 bits the same way whatever width it was computed in.
+// Writing a 32 bit register clears the upper half, which is why movl zero extends
+fn extend(int_type: IntType) -> Option<&'static str> {
+    match int_type {
+        IntType::I8 => Some("movsbq %al, %rax"),
+        IntType::I16 => Some("movswq %ax, %rax"),
+        IntType::I32 => Some("movslq %eax, %rax"),
+        IntType::U8 => Some("movzbl %al, %eax"),
+        IntType::U16 => Some("movzwl %ax, %eax"),
+        IntType::U32 => Some("movl %eax, %eax"),
+        IntType::I64 | IntType::U64 => None,
+    }
+}
+
+// the 64 bit word holding an integer literal, a literal of a type too small for it was rejected
+fn literal_value(value: u64, int_type: IntType) -> i64 {
+    int_type.wrap(value as i64)
+}
+
+// instructions take at most 32 bit immediates, sign extended to 64 bits
+fn fits_immediate(value: i64) -> bool {
+    value >= i32::MIN as i64 && value <= i32::MAX as i64
+}
+
+// a function used as a value is a closure record holding nothing but the code pointer
+fn closure_symbol(function_symbol: &str) -> String {
+    format!("CLOSURE_{}", function_symbol)
+}
+
+// code that calls a function for closure calls, when they don't reserve all the stack
+// argument space its tail calls need
+fn closure_entry_symbol(function_symbol: &str) -> String {
+    format!("CLOSURE_ENTRY_{}", function_symbol)
+}
+
+// what a call jumps to, a closure's code is found through the record it points to
+enum Callee<'a, 't> {
+    Symbol(String),
+    Closure(&'a Expr<'t>),
+}
+
+// the function or lambda whose body is being generated. Calls in tail position in it jump to
+// their callee, which takes over the frame and returns straight to its caller
+struct Frame<'a> {
+    // how reports name the body, like function loop
+    name: String,
+    // words of stack argument space its callers reserve above the return address, a tail call
+    // puts its stack arguments there so it can't pass more
+    argument_area: usize,
+    tail_calls: Vec<&'a Call<'a>>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(name: String) -> Frame<'a> {
+        Frame {name, argument_area: 0, tail_calls: Vec::new()}
+    }
+}
+
+// where a variable lives in the frame
+#[derive(Clone)]
+struct Local<'a> {
+    offset: i32,
+    // set for let-polymorphic lambdas, whose slot holds a closure record without a code pointer
+    generic: Option<Generic<'a>>,
+}
+
+// a lambda whose code is generated separately for every combination of types it is used at
+#[derive(Clone)]
+struct Generic<'a> {
+    lambda: &'a Lambda<'a>,
+    quantified: Vec<u32>,
+    // the same for each of the lambda's captures, which can be generic lambdas themselves
+    captures: Vec<Option<Generic<'a>>>,
 }
 
-impl CodeGenerator {
-    pub fn new() -> CodeGenerator {
-        CodeGenerator {bb_label_counter: 0}
+pub struct CodeGenerator<'a> {
+    bb_label_counter: i32,
+    // number of 8 byte words pushed since the frame was set up,
+    // the word pushed at depth d lives at -8*d(%rbp)
+    stack_depth: i32,
+    // whether the word at each depth can point to a heap object, the word at depth d is pointers[d - 1].
+    // Flags above the current depth are left over from values that are gone
+    pointers: Vec<bool>,
+    // the offsets from %rbp of the words that can point to heap objects at every call, see stack_map.
+    // Emitted at the end of the code as STACKMAP<index>, calls with the same offsets share one
+    stack_maps: Vec<Vec<i32>>,
+    // maps variable name to where it lives, innermost scope last
+    scopes: Vec<HashMap<String, Local<'a>>>,
+    // string literal values, emitted into .rodata as STRING<index>
+    strings: Vec<String>,
+    // field names and types of every struct, in declaration order
+    structs: HashMap<String, Vec<(String, TypeKind)>>,
+    // variant names and payload types of every enum, in declaration order
+    enums: HashMap<String, Vec<(String, Vec<TypeKind>)>>,
+    // type parameter names of every struct and enum, their declared types refer to them
+    type_params: HashMap<String, Vec<String>>,
+    // structs and enums that contain themselves. Their values are pointers to heap objects
+    // holding the words the value would otherwise take up on the stack
+    boxed: HashSet<String>,
+    functions: HashMap<String, &'a Function<'a>>,
+    // words of stack argument space callers of each non-generic function reserve, by symbol. It
+    // can be more than its own stack arguments take up, see argument_areas
+    argument_areas: HashMap<String, usize>,
+    // types for the type parameters of the generic function instance being generated
+    type_arguments: HashMap<String, TypeKind>,
+    // generic function instances that are used but not generated yet, and every one used so far
+    pending: Vec<(String, &'a Function<'a>, Vec<TypeKind>)>,
+    function_instances: HashSet<String>,
+    // code for every lambda, generated apart from the function it appears in
+    lambda_code: String,
+    lambda_count: i32,
+    // overflowing arithmetic jumps to a stub that passes the operator and its line to the
+    // runtime's panic, they are kept out of the way at the end of the code
+    overflow_checks: bool,
+    overflow_code: String,
+    // types for the type variables of the generic lambdas whose code is being generated
+    substitution: HashMap<u32, TypeKind>,
+    // the symbol of every generic lambda instance generated so far, keyed by lambda and types
+    instances: HashMap<String, String>,
+    frame: Option<Frame<'a>>,
+    // what became of every call to a user function or closure, when asked for
+    report_tail_calls: bool,
+    pub notes: Vec<String>,
+    // calls in tail position that stay ordinary calls
+    pub warnings: Vec<Warning>,
+    gc_stats: bool,
+}
+
+impl<'a> CodeGenerator<'a> {
+    pub fn new(options: &Options) -> CodeGenerator<'a> {
+        CodeGenerator {bb_label_counter: 0, stack_depth: 0, pointers: Vec::new(), stack_maps: Vec::new(), scopes: Vec::new(), strings: Vec::new(),
+            structs: HashMap::new(), enums: HashMap::new(), type_params: HashMap::new(), boxed: HashSet::new(), functions: HashMap::new(), argument_areas: HashMap::new(),
+            type_arguments: HashMap::new(), pending: Vec::new(), function_instances: HashSet::new(),
+            lambda_code: String::new(), lambda_count: 0, overflow_checks: options.overflow_checks,
+            overflow_code: String::new(), substitution: HashMap::new(), instances: HashMap::new(), frame: None,
+            report_tail_calls: options.report_tail_calls, notes: Vec::new(), warnings: Vec::new(), gc_stats: options.gc_stats}
     }
 
-    pub fn gen_code(&mut self, ast: Expr) -> String {
+    pub fn gen_code(&mut self, ast: &'a Program<'a>) -> String {
         let mut program = String::new();
         
         let preamble = "\t.file	\"test.c\"\n\
-        \t.text\n\
+        \t.text\n";
+
+        let main_prologue = "\
         \t.globl\tmain\n\
         \t.type\tmain, @function\n\
         \tmain:\n\
-        \t.LFB0:\n";
+        \t.LFB0:\n\
+        \t\tpushq %rbp\n\
+        \t\tmovq %rsp, %rbp\n";
 
-        let postamble = "\t\tpopq %rax\n\
+        let main_epilogue = "\t\tpopq %rax\n\
+        \t\tleave\n\
         \t\tret\n\
         \t.LFE0:\n\
-        \t    .size	main, .-main\n\
+        \t    .size	main, .-main\n";
+
+        let postamble = "\
         \t    .ident	\"GCC: (Ubuntu 9.3.0-17ubuntu1~20.04) 9.3.0\"\n\
         \t    .section	.note.GNU-stack,\"\",@progbits\n\
         \t    .section	.note.gnu.property,\"a\"\n\
@@ -43,51 +259,620 @@ impl CodeGenerator {
         \t4:\n\
         ";
 
-        self.visit_expr(&ast, &mut program);
+        for struct_decl in &ast.structs {
+            let fields = struct_decl.fields.iter()
+                .map(|field| (field.name.lexeme.to_string(), field.type_kind.clone()))
+                .collect();
+            self.structs.insert(struct_decl.name.lexeme.to_string(), fields);
+            let type_params = struct_decl.type_params.iter().map(|token| token.lexeme.to_string()).collect();
+            self.type_params.insert(struct_decl.name.lexeme.to_string(), type_params);
+            if struct_decl.recursive {
+                self.boxed.insert(struct_decl.name.lexeme.to_string());
+            }
+        }
+        for enum_decl in &ast.enums {
+            let variants = enum_decl.variants.iter()
+                .map(|variant| (variant.name.lexeme.to_string(), variant.payload.clone()))
+                .collect();
+            self.enums.insert(enum_decl.name.lexeme.to_string(), variants);
+            let type_params = enum_decl.type_params.iter().map(|token| token.lexeme.to_string()).collect();
+            self.type_params.insert(enum_decl.name.lexeme.to_string(), type_params);
+            if enum_decl.recursive {
+                self.boxed.insert(enum_decl.name.lexeme.to_string());
+            }
+        }
+
+        for function in &ast.functions {
+            self.functions.insert(function.name.lexeme.to_string(), function);
+        }
+        self.argument_areas(ast);
+        // generic functions are generated once they are used, for the types they are used at
+        for function in ast.functions.iter().filter(|function| function.type_params.is_empty()) {
+            self.gen_function(function, &function_symbol(function.name.lexeme), &[], &mut program);
+        }
+
+        program.push_str(main_prologue);
+        program.push_str(format!("\t\tmovq %rbp, {}(%rip)\n", runtime::STACK_BASE).as_str());
+        self.stack_depth = 0;
+        match &ast.main {
+            Some(expr) => self.visit_expr(expr, &mut program),
+            None => {
+                let main = ast.functions.iter()
+                    .find(|function| function.name.lexeme == "main")
+                    .expect("typechecker should have required a main function");
+                let return_words = self.words(&main.return_type);
+                self.gen_call(Callee::Symbol(function_symbol("main")), &[], return_words, &mut program);
+                if return_words == 1 {
+                    program.push_str("\t\tpushq %rax\n");
+                }
+            }
+        }
+        if self.gc_stats {
+            program.push_str(format!("\t\tcall {}\n", runtime::GC_STATS).as_str());
+        }
+        program.push_str(main_epilogue);
+        // generating an instance can use more of them
+        while !self.pending.is_empty() {
+            let (symbol, function, arguments) = self.pending.remove(0);
+            self.gen_function(function, &symbol, &arguments, &mut program);
+        }
+        program.push_str(self.lambda_code.as_str());
+        program.push_str(self.overflow_code.as_str());
+        self.gen_stack_maps(&mut program);
+        program.push_str(runtime::routines().as_str());
+        self.gen_strings(&mut program);
 
         format!("{}{}{}", preamble, program, postamble) 
     }
 
-    fn visit_expr(&mut self, node: &Expr, program: &mut String) {
+    // number of 8 byte stack words a value of this type takes up. Aggregates are pushed
+    // component by component, so the first component ends up at the highest address
+    fn words(&self, type_kind: &TypeKind) -> i32 {
+        match type_kind {
+            TypeKind::Array(element_type, length) => self.words(element_type) * *length as i32,
+            TypeKind::Named(name, _) if self.boxed.contains(name) => 1,
+            TypeKind::Named(..) => self.layout_words(type_kind),
+            TypeKind::Var(variable) => {
+                self.words(self.substitution.get(variable).expect("generic lambdas are generated with types for their variables"))
+            }
+            TypeKind::Param(name) => {
+                self.words(self.type_arguments.get(name).expect("generic functions are generated with types for their type parameters"))
+            }
+            _ => 1,
+        }
+    }
+
+    // the words of a struct or enum value, on the stack or in the heap object of a boxed one
+    fn layout_words(&self, type_kind: &TypeKind) -> i32 {
+        match self.concrete(type_kind) {
+            TypeKind::Named(name, arguments) if self.structs.contains_key(&name) => self.struct_fields(&name, &arguments).iter()
+                .map(|(_, field_type)| self.words(field_type))
+                .sum(),
+            // the tag, then room for the largest payload
+            TypeKind::Named(name, arguments) => 1 + (0..self.enums[&name].len())
+                .map(|variant| self.variant_payload(&name, &arguments, variant).iter().map(|type_kind| self.words(type_kind)).sum::<i32>())
+                .max()
+                .unwrap_or(0),
+            _ => unreachable!("only structs and enums have a layout"),
+        }
+    }
+
+    fn is_boxed(&self, type_kind: &TypeKind) -> bool {
+        matches!(self.concrete(type_kind), TypeKind::Named(name, _) if self.boxed.contains(&name))
+    }
+
+    // a function's tail calls put their stack arguments where its own came in, so its callers
+    // reserve enough space for the stack arguments of every call it makes in tail position, and
+    // for the space the functions it calls there need in turn. Generic functions only get the
+    // space their own arguments take up
+    fn argument_areas(&mut self, ast: &'a Program<'a>) {
+        let mut needs: HashMap<String, Vec<(usize, Option<String>)>> = HashMap::new();
+        for function in ast.functions.iter().filter(|function| function.type_params.is_empty()) {
+            let symbol = function_symbol(function.name.lexeme);
+            let param_words: i32 = function.params.iter().map(|param| self.words(&param.type_kind)).sum();
+            self.argument_areas.insert(symbol.clone(), (param_words as usize).saturating_sub(ARGUMENT_REGISTERS.len()));
+            let mut tail_calls = Vec::new();
+            find_tail_calls(&function.body, &mut tail_calls);
+            let calls = tail_calls.iter()
+                .filter(|call| match call.callee.as_ref() {
+                    Expr::Path(_) => false,
+                    Expr::Variable(variable) => !BUILTINS.contains(&variable.token.lexeme),
+                    _ => true,
+                })
+                .map(|call| (self.stack_argument_words(&call.arguments), self.named_callee(call)))
+                .collect();
+            needs.insert(symbol, calls);
+        }
+        // the space only grows, up to the most any call passes
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (symbol, calls) in &needs {
+                for (words, callee) in calls {
+                    let callee_area = callee.as_ref().map_or(0, |callee| self.argument_areas[callee]);
+                    if (*words).max(callee_area) > self.argument_areas[symbol] {
+                        self.argument_areas.insert(symbol.clone(), (*words).max(callee_area));
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // words of a call's arguments that go on the stack
+    fn stack_argument_words(&self, arguments: &[Expr]) -> usize {
+        let argument_words: i32 = arguments.iter()
+            .map(|argument| self.words(argument.type_kind().as_ref().unwrap()))
+            .sum();
+        (argument_words as usize).saturating_sub(ARGUMENT_REGISTERS.len())
+    }
+
+    // the symbol of the non-generic function a call is to, when that's what it calls. A local
+    // with the same name is taken for the function, which only makes its space bigger
+    fn named_callee(&self, call: &Call) -> Option<String> {
+        match call.callee.as_ref() {
+            Expr::Variable(variable) if self.local_offset(variable.token.lexeme).is_none() => self.functions
+                .get(variable.token.lexeme)
+                .filter(|function| function.type_params.is_empty())
+                .map(|function| function_symbol(function.name.lexeme)),
+            _ => None,
+        }
+    }
+
+    // the type with the types of the generic code being generated filled in
+    fn concrete(&self, type_kind: &TypeKind) -> TypeKind {
+        type_kind.substitute(&self.substitution).substitute_params(&self.type_arguments)
+    }
+
+    // a struct's fields with the given type arguments in place of its type parameters
+    fn struct_fields(&self, struct_name: &str, arguments: &[TypeKind]) -> Vec<(String, TypeKind)> {
+        let mapping = param_mapping(&self.type_params[struct_name], arguments);
+        self.structs[struct_name].iter()
+            .map(|(name, field_type)| (name.clone(), field_type.substitute_params(&mapping)))
+            .collect()
+    }
+
+    fn variant_payload(&self, enum_name: &str, arguments: &[TypeKind], variant: usize) -> Vec<TypeKind> {
+        let mapping = param_mapping(&self.type_params[enum_name], arguments);
+        self.enums[enum_name][variant].1.iter().map(|type_kind| type_kind.substitute_params(&mapping)).collect()
+    }
+
+    // byte offsets of the tag and of each payload value's lowest word from an enum value's lowest word.
+    // The tag is pushed first and unused payload words are pushed last
+    fn variant_layout(&self, enum_name: &str, arguments: &[TypeKind], variant: usize) -> (i32, Vec<i32>) {
+        let total_words = self.layout_words(&TypeKind::Named(String::from(enum_name), arguments.to_vec()));
+        let mut start = 1;
+        let mut offsets = Vec::new();
+        for type_kind in &self.variant_payload(enum_name, arguments, variant) {
+            let value_words = self.words(type_kind);
+            offsets.push(8 * (total_words - start - value_words));
+            start += value_words;
+        }
+        (8 * (total_words - 1), offsets)
+    }
+
+    fn variant_index(&self, enum_name: &str, variant_name: &str) -> usize {
+        self.enums[enum_name].iter()
+            .position(|(name, _)| name == variant_name)
+            .expect("typechecker should have rejected unknown variants")
+    }
+
+    fn expr_words(&self, expr: &Expr) -> i32 {
+        self.words(&expr.type_kind().expect("typechecker should have set every type"))
+    }
+
+    // byte offset of a field's lowest word from the struct's lowest word, and the field's size in words
+    fn field_layout(&self, struct_name: &str, arguments: &[TypeKind], field_name: &str) -> (i32, i32) {
+        let fields = self.struct_fields(struct_name, arguments);
+        let total_words: i32 = fields.iter().map(|(_, field_type)| self.words(field_type)).sum();
+        let mut start = 0;
+        for (name, field_type) in &fields {
+            let field_words = self.words(field_type);
+            if name == field_name {
+                return (8 * (total_words - start - field_words), field_words);
+            }
+            start += field_words;
+        }
+        unreachable!("typechecker only allows accessing declared fields")
+    }
+
+    // a string is a pointer to its length as a quad, followed by its UTF-8 bytes
+    fn gen_strings(&self, program: &mut String) {
+        program.push_str("\t.section\t.rodata\n");
+        for (index, value) in self.strings.iter().enumerate() {
+            program.push_str(
+                format!("\
+                \t.align 8\n\
+                \tSTRING{index}:\n\
+                \t\t.quad {length}\n\
+                ", index=index, length=value.len()).as_str()
+            );
+            if !value.is_empty() {
+                let bytes: Vec<String> = value.bytes().map(|byte| byte.to_string()).collect();
+                program.push_str(format!("\t\t.byte {}\n", bytes.join(", ")).as_str());
+            }
+        }
+        program.push_str("\t.text\n");
+    }
+
+    fn gen_stack_maps(&self, program: &mut String) {
+        for (index, offsets) in self.stack_maps.iter().enumerate() {
+            program.push_str(format!("\tSTACKMAP{}:\n\t\t.quad {}\n", index, offsets.len()).as_str());
+            for offset in offsets {
+                program.push_str(format!("\t\t.quad {}\n", offset).as_str());
+            }
+        }
+    }
+
+    // arguments gives the types for a generic function's type parameters
+    fn gen_function(&mut self, function: &'a Function<'a>, symbol: &str, arguments: &[TypeKind], program: &mut String) {
+        program.push_str(format!("\t.globl\t{}\n", symbol).as_str());
+        let params: Vec<(String, TypeKind)> = function.params.iter()
+            .map(|param| (param.name.lexeme.to_string(), param.type_kind.clone()))
+            .collect();
+        let type_params: Vec<String> = function.type_params.iter().map(|token| token.lexeme.to_string()).collect();
+        self.type_arguments = param_mapping(&type_params, arguments);
+        self.frame = Some(Frame::new(format!("function {}", function.name.lexeme)));
+        self.gen_function_body(symbol, &params, &[], &function.return_type, &function.body, program);
+        self.frame = None;
+        self.type_arguments.clear();
+
+        // generic function instances only get the space their own arguments take up
+        let entry = match self.argument_areas.get(symbol) {
+            Some(area) => {
+                let param_words: i32 = function.params.iter().map(|param| self.words(&param.type_kind)).sum();
+                let stack_arguments = (param_words as usize).saturating_sub(ARGUMENT_REGISTERS.len());
+                if *area > stack_arguments {
+                    self.gen_closure_entry(symbol, stack_arguments, *area, program);
+                    closure_entry_symbol(symbol)
+                } else {
+                    String::from(symbol)
+                }
+            }
+            None => String::from(symbol),
+        };
+        program.push_str(
+            format!("\
+            \t.section\t.data.rel.ro,\"aw\"\n\
+            \t.align 8\n\
+            \t{closure}:\n\
+            \t\t.quad {entry}\n\
+            \t.text\n\
+            ", closure=closure_symbol(symbol), entry=entry).as_str()
+        );
+    }
+
+    // closure calls only pass the stack arguments, so this copies them into the space the
+    // function's callers reserve and calls it. Registers, and %rax with the address of a
+    // wide result, are passed on as they are
+    fn gen_closure_entry(&mut self, symbol: &str, stack_arguments: usize, area: usize, program: &mut String) {
+        program.push_str(
+            format!("\
+            \t{entry}:\n\
+            \t\tpushq %rbp\n\
+            \t\tmovq %rsp, %rbp\n\
+            \t\tsubq ${bytes}, %rsp\n\
+            ", entry=closure_entry_symbol(symbol), bytes=8 * (area + area % 2)).as_str()
+        );
+        for word in 0..area {
+            if word < stack_arguments {
+                program.push_str(
+                    format!("\
+                    \t\tmovq {from}(%rbp), %r11\n\
+                    \t\tmovq %r11, {to}(%rsp)\n\
+                    ", from=16 + 8 * word, to=8 * word).as_str()
+                );
+            } else {
+                program.push_str(format!("\t\tmovq $0, {}(%rsp)\n", 8 * word).as_str());
+            }
+        }
+        // the copies are the callee's to look after
+        let stack_map = self.stack_map(0);
+        program.push_str(
+            format!("\
+            \t\tcall {}\n\
+            {}\
+            \t\tleave\n\
+            \t\tret\n\
+            ", symbol, stack_map).as_str()
+        );
+    }
+
+    // lambdas are called with their closure record in %r10, the values it captured
+    // are copied into the frame after the parameters. self.frame names the body
+    fn gen_function_body(&mut self, symbol: &str, params: &[(String, TypeKind)], captures: &[(String, TypeKind, Option<Generic<'a>>)],
+        return_type: &TypeKind, body: &'a Expr<'a>, program: &mut String) {
+        program.push_str(
+            format!("\
+            \t.type\t{symbol}, @function\n\
+            \t{symbol}:\n\
+            \t\tpushq %rbp\n\
+            \t\tmovq %rsp, %rbp\n\
+            ", symbol=symbol).as_str()
+        );
+
+        self.stack_depth = 0;
+        let return_words = self.words(return_type);
+        if return_words > 1 {
+            // the caller passes the address to copy a wide result to in %rax
+            self.stack_depth += 1;
+            program.push_str("\t\tpushq %rax\n");
+        }
+
+        // argument words are copied into the frame in order, so every parameter is laid out
+        // like a local. The first six come in registers, the rest sit above the return address
+        let mut scope = HashMap::new();
+        let mut word = 0;
+        for (name, type_kind) in params {
+            for _ in 0..self.words(type_kind) {
+                let source = match ARGUMENT_REGISTERS.get(word) {
+                    Some(register) => String::from(*register),
+                    None => format!("{}(%rbp)", 16 + 8 * (word - ARGUMENT_REGISTERS.len())),
+                };
+                self.stack_depth += 1;
+                program.push_str(format!("\t\tpushq {}\n", source).as_str());
+                word += 1;
+            }
+            self.set_pointers(&self.pointer_words(type_kind));
+            scope.insert(name.clone(), Local {offset: -8 * self.stack_depth, generic: None});
+        }
+        let mut start = 0;
+        for (name, type_kind, generic) in captures {
+            let value_words = self.words(type_kind);
+            self.push_words("%r10", 8 + 8 * start, value_words, program);
+            self.set_pointers(&self.pointer_words(type_kind));
+            scope.insert(name.clone(), Local {offset: -8 * self.stack_depth, generic: generic.clone()});
+            start += value_words;
+        }
+        self.scopes.push(scope);
+
+        if let Some(frame) = self.frame.as_mut() {
+            frame.argument_area = match self.argument_areas.get(symbol) {
+                Some(area) => *area,
+                None => word.saturating_sub(ARGUMENT_REGISTERS.len()),
+            };
+            find_tail_calls(body, &mut frame.tail_calls);
+        }
+        self.visit_expr(body, program);
+        self.scopes.pop();
+
+        if return_words > 1 {
+            program.push_str("\t\tmovq -8(%rbp), %rdi\n");
+            for word in 0..return_words {
+                program.push_str(
+                    format!("\
+                    \t\tmovq {offset}(%rsp), %rax\n\
+                    \t\tmovq %rax, {offset}(%rdi)\n\
+                    ", offset=8 * word).as_str()
+                );
+            }
+            program.push_str("\t\tmovq %rdi, %rax\n");
+        } else {
+            program.push_str("\t\tpopq %rax\n");
+        }
+        program.push_str(
+            format!("\
+            \t\tleave\n\
+            \t\tret\n\
+            \t.size\t{symbol}, .-{symbol}\n\
+            ", symbol=symbol).as_str()
+        );
+    }
+
+    fn visit_expr(&mut self, node: &'a Expr<'a>, program: &mut String) {
         match node {
             Expr::Binary(binary) => {self.visit_binary(binary, program)}
             Expr::Literal(literal) => {self.visit_literal(literal, program)}
             Expr::Unary(unary) => {self.visit_unary(unary, program)}
             Expr::Grouping(grouping) => {self.visit_expr(grouping.expr.as_ref(), program)}
             Expr::If(if_expr) => {self.visit_if_expr(if_expr, program)},
+            Expr::Variable(variable) => {self.visit_variable(variable, program)}
+            Expr::Block(block) => {self.visit_block(block, program)}
+            Expr::Call(call) => {self.visit_call(call, program)}
+            Expr::Logical(logical) => {self.visit_logical(logical, program)}
+            Expr::While(while_expr) => {self.visit_while(while_expr, program)}
+            Expr::Assign(assign) => {self.visit_assign(assign, program)}
+            Expr::Array(array) => {self.visit_array(array, program)}
+            Expr::Index(index) => {self.visit_index(index, program)}
+            Expr::StructLiteral(struct_literal) => {self.visit_struct_literal(struct_literal, program)}
+            Expr::Field(field) => {self.visit_field(field, program)}
+            Expr::Path(path) => {self.visit_variant(path, &[], program)}
+            Expr::Match(match_expr) => {self.visit_match(match_expr, program)}
+            Expr::Lambda(lambda) => {self.visit_lambda(lambda, program)}
+            Expr::Cast(cast) => {self.visit_cast(cast, program)}
+        }
+        if let Some(type_kind) = node.type_kind() {
+            self.set_pointers(&self.pointer_words(&type_kind));
+        }
+    }
+
+    // flag the words of the value on top of the stack, given in the order they were pushed
+    fn set_pointers(&mut self, pointers: &[bool]) {
+        let depth = self.stack_depth as usize;
+        if self.pointers.len() < depth {
+            self.pointers.resize(depth, false);
         }
+        self.pointers[depth - pointers.len()..depth].copy_from_slice(pointers);
     }
 
-    fn visit_binary(&mut self, node: &Binary, program: &mut String) {
+    fn visit_binary(&mut self, node: &'a Binary<'a>, program: &mut String) {
         self.visit_expr(node.left.as_ref(), program);
         self.visit_expr(node.right.as_ref(), program);
+        if let Some(TypeKind::Str) = node.left.type_kind() {
+            self.string_equality(node, program);
+            return;
+        }
+        if let Some(TypeKind::Float) = node.left.type_kind().map(|type_kind| self.concrete(&type_kind)) {
+            self.float_binary(node, program);
+            return;
+        }
+        let int_type = self.int_type(node.left.type_kind());
+        // unsigned values compare with the below and above jumps
+        let (less, less_equal, greater, greater_equal) = if int_type.signed() {
+            ("jl", "jle", "jg", "jge")
+        } else {
+            ("jb", "jbe", "ja", "jae")
+        };
         match node.operation {
-            BinaryOp::Add => self.binary_arithmetic("addq", program),
-            BinaryOp::Minus => self.binary_arithmetic("subq", program),
-            BinaryOp::Times => self.binary_arithmetic("imulq", program),
-            BinaryOp::Less => self.binary_compare("jl", program),
+            BinaryOp::Add => self.binary_arithmetic("add", int_type, self.checked(node.token), program),
+            BinaryOp::Minus => self.binary_arithmetic("sub", int_type, self.checked(node.token), program),
+            BinaryOp::Times => self.binary_multiply(int_type, self.checked(node.token), program),
+            BinaryOp::WrappingAdd => self.binary_arithmetic("add", int_type, None, program),
+            BinaryOp::WrappingMinus => self.binary_arithmetic("sub", int_type, None, program),
+            BinaryOp::WrappingTimes => self.binary_multiply(int_type, None, program),
+            BinaryOp::Divide => self.binary_divide('a', int_type, program),
+            BinaryOp::Modulo => self.binary_divide('d', int_type, program),
+            BinaryOp::Less => self.binary_compare(less, program),
             BinaryOp::BangEqual => self.binary_compare("jne", program),
             BinaryOp::EqualEqual => self.binary_compare("je", program),
-            BinaryOp::Greater => self.binary_compare("jg", program),
-            BinaryOp::GreaterEqual => self.binary_compare("jge", program),
-            BinaryOp::LessEqual => self.binary_compare("jle", program),
+            BinaryOp::Greater => self.binary_compare(greater, program),
+            BinaryOp::GreaterEqual => self.binary_compare(greater_equal, program),
+            BinaryOp::LessEqual => self.binary_compare(less_equal, program),
         };   
     }
 
-    fn binary_arithmetic(&mut self, op_instr: &str, program: &mut String) {
+    // the integer type of a value typed as one, bools count as int
+    fn int_type(&self, type_kind: Option<TypeKind>) -> IntType {
+        match type_kind.map(|type_kind| self.concrete(&type_kind)) {
+            Some(TypeKind::Int(int_type)) => int_type,
+            _ => IntType::I64,
+        }
+    }
+
+    // the operator to report when an operation overflows, None when overflow isn't checked
+    fn checked(&self, token: &'a Token<'a>) -> Option<&'a Token<'a>> {
+        if self.overflow_checks { Some(token) } else { None }
+    }
+
+    // the low bits of a sum or difference don't depend on signedness, so one instruction
+    // at the operands' width does for both
+    fn binary_arithmetic(&mut self, op_instr: &str, int_type: IntType, checked: Option<&Token>, program: &mut String) {
+        self.stack_depth -= 1;
+        let bits = int_type.bits();
         program.push_str(
             format!("\
             \t\tpopq %rdx\n\
             \t\tpopq %rax\n\
-            \t\t{} %rdx, %rax\n\
-            \t\tpushq %rax\n\
-            ", op_instr).as_str()
-        )
+            \t\t{}{} {}, {}\n\
+            ", op_instr, suffix(bits), register('d', bits), register('a', bits)).as_str()
+        );
+        self.check_overflow(int_type, checked, program);
+        self.push_extended(int_type, program);
+    }
+
+    // mul and imul both leave the product's low bits in %rax, but only set the overflow
+    // flags for products that don't fit their kind of operands. Bytes need the one operand
+    // imul, there is no two operand form for them
+    fn binary_multiply(&mut self, int_type: IntType, checked: Option<&Token>, program: &mut String) {
+        self.stack_depth -= 1;
+        let bits = int_type.bits();
+        let multiply = match (int_type.signed(), bits) {
+            (false, _) => format!("mul{} {}", suffix(bits), register('d', bits)),
+            (true, 8) => String::from("imulb %dl"),
+            (true, _) => format!("imul{} {}, {}", suffix(bits), register('d', bits), register('a', bits)),
+        };
+        program.push_str(
+            format!("\
+            \t\tpopq %rdx\n\
+            \t\tpopq %rax\n\
+            \t\t{}\n\
+            ", multiply).as_str()
+        );
+        self.check_overflow(int_type, checked, program);
+        self.push_extended(int_type, program);
+    }
+
+    // after an instruction at int_type's width, panic if its result didn't fit. Signed
+    // results set the overflow flag and unsigned ones the carry flag
+    fn check_overflow(&mut self, int_type: IntType, checked: Option<&Token>, program: &mut String) {
+        let token = match checked {
+            Some(token) => token,
+            None => return,
+        };
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        self.strings.push(token.lexeme.to_string());
+        program.push_str(
+            format!("\t\t{} OVERFLOW{}\n", if int_type.signed() { "jo" } else { "jc" }, label).as_str()
+        );
+        self.overflow_code.push_str(
+            format!("\
+            \tOVERFLOW{label}:\n\
+            \t\tleaq STRING{string}(%rip), %rdi\n\
+            \t\tmovq ${line}, %rsi\n\
+            \t\tjmp {overflow}\n\
+            ",
+            label=label,
+            string=self.strings.len() - 1,
+            line=token.line,
+            overflow=runtime::OVERFLOW).as_str()
+        );
+    }
+
+    // idiv and div leave the quotient in %rax and the remainder in %rdx. Bytes and 16 bit values
+    // are divided as 32 bit ones, which they are already extended to
+    fn binary_divide(&mut self, result_register: char, int_type: IntType, program: &mut String) {
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        self.stack_depth -= 1;
+        let bits = int_type.bits().max(32);
+        program.push_str(
+            format!("\
+            \t\tpopq %rcx\n\
+            \t\tpopq %rax\n\
+            \t\tcmpq $0, %rcx\n\
+            \t\tje {divide_by_zero}\n\
+            ",
+            divide_by_zero=runtime::DIVIDE_BY_ZERO).as_str()
+        );
+        if int_type.signed() {
+            // idiv raises SIGFPE for a zero divisor and for the smallest value / -1, so check both first
+            program.push_str(
+                format!("\
+                \t\tcmpq $-1, %rcx\n\
+                \t\tjne DIVIDE{label}\n\
+                \t\tmovabsq ${min}, %rdx\n\
+                \t\tcmpq %rdx, %rax\n\
+                \t\tje {divide_overflow}\n\
+                \tDIVIDE{label}:\n\
+                \t\t{extend_dividend}\n\
+                \t\tidiv{suffix} {divisor}\n\
+                ",
+                divide_overflow=runtime::DIVIDE_OVERFLOW,
+                min=int_type.min(),
+                label=label,
+                extend_dividend=if bits == 64 { "cqto" } else { "cltd" },
+                suffix=suffix(bits),
+                divisor=register('c', bits)).as_str()
+            );
+        } else {
+            program.push_str(
+                format!("\
+                \t\txorl %edx, %edx\n\
+                \t\tdiv{suffix} {divisor}\n\
+                ",
+                suffix=suffix(bits),
+                divisor=register('c', bits)).as_str()
+            );
+        }
+        if result_register == 'd' {
+            program.push_str("\t\tmovq %rdx, %rax\n");
+        }
+        self.push_extended(int_type, program);
+    }
+
+    // push %rax after extending the low bits of a result of the given type to all 64
+    fn push_extended(&mut self, int_type: IntType, program: &mut String) {
+        if let Some(extend) = extend(int_type) {
+            program.push_str(format!("\t\t{}\n", extend).as_str());
+        }
+        program.push_str("\t\tpushq %rax\n");
     }
 
     fn binary_compare(&mut self, jump_instr: &str, program: &mut String) {
         let label = self.bb_label_counter;
         self.bb_label_counter += 1;
+        self.stack_depth -= 1;
         program.push_str(
             format!("\
             \t\tpopq %rdx\n\
@@ -104,64 +889,1407 @@ impl CodeGenerator {
         );
     }
 
-    fn visit_unary(&mut self, node: &Unary, program: &mut String) {
-        self.visit_expr(node.right.as_ref(), program);
+    // the operands go through %xmm0 and %xmm1. ucomisd sets the flags like an unsigned compare,
+    // and sets the parity flag as well when either operand is NaN, which equals nothing
+    fn float_binary(&mut self, node: &Binary, program: &mut String) {
+        self.stack_depth -= 1;
+        program.push_str("\
+            \t\tpopq %rdx\n\
+            \t\tpopq %rax\n\
+            \t\tmovq %rax, %xmm0\n\
+            \t\tmovq %rdx, %xmm1\n\
+            ");
+        let arithmetic = match node.operation {
+            BinaryOp::Add => "addsd",
+            BinaryOp::Minus => "subsd",
+            BinaryOp::Times => "mulsd",
+            BinaryOp::Divide => "divsd",
+            _ => "",
+        };
+        if !arithmetic.is_empty() {
+            program.push_str(format!("\t\t{} %xmm1, %xmm0\n\t\tmovq %xmm0, %rax\n\t\tpushq %rax\n", arithmetic).as_str());
+            return;
+        }
+        // a < b is b > a, so NaN operands make every ordering false
+        let (compare, jumps) = match node.operation {
+            BinaryOp::Greater => ("ucomisd %xmm1, %xmm0", "ja CMP_TRUE"),
+            BinaryOp::GreaterEqual => ("ucomisd %xmm1, %xmm0", "jae CMP_TRUE"),
+            BinaryOp::Less => ("ucomisd %xmm0, %xmm1", "ja CMP_TRUE"),
+            BinaryOp::LessEqual => ("ucomisd %xmm0, %xmm1", "jae CMP_TRUE"),
+            BinaryOp::EqualEqual => ("ucomisd %xmm1, %xmm0", "jp CMP_FALSE"),
+            BinaryOp::BangEqual => ("ucomisd %xmm1, %xmm0", "jp CMP_TRUE"),
+            _ => unreachable!("typechecker only allows arithmetic and comparisons on floats"),
+        };
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        program.push_str(format!("\t\t{}\n\t\t{}{}\n", compare, jumps, label).as_str());
+        match node.operation {
+            BinaryOp::EqualEqual => program.push_str(format!("\t\tje CMP_TRUE{}\n", label).as_str()),
+            BinaryOp::BangEqual => program.push_str(format!("\t\tjne CMP_TRUE{}\n", label).as_str()),
+            _ => {}
+        }
         program.push_str(
             format!("\
-            \t\tpopq %rax\n\
-            \t\t{}\n\
-            \t\tpushq %rax\n\
-            ",
-            match node.operation {
-                UnaryOp::Minus => "imulq $-1, %rax"
-            }
-            ).as_str()
+            \tCMP_FALSE{label}:\n\
+            \t\tpushq $0\n\
+            \t\tjmp CMP_DONE{label}\n\
+            \tCMP_TRUE{label}:\n\
+            \t\tpushq $1\n\
+            \tCMP_DONE{label}:\n\
+            ", label=label).as_str()
         );
-        
     }
 
-    fn visit_literal(&mut self, node: &Literal, program: &mut String) {
-        program.push_str(
-            format!("\t\tpushq ${}\n", node.token.lexeme).as_str()
-        )
+    // strings compare by contents, the typechecker only allows == and != on them
+    fn string_equality(&mut self, node: &Binary, program: &mut String) {
+        let base_depth = self.stack_depth - 2;
+        self.emit_call(runtime::STR_EQ, base_depth, None, program);
+        if let BinaryOp::BangEqual = node.operation {
+            program.push_str("\t\txorq $1, %rax\n");
+        }
+        self.stack_depth += 1;
+        program.push_str("\t\tpushq %rax\n");
     }
 
-    fn visit_if_expr(&mut self, node: &If, program: &mut String) {
-        self.visit_expr(node.condition.as_ref(), program);
-        
+    fn visit_unary(&mut self, node: &'a Unary<'a>, program: &mut String) {
+        // a negative literal is a constant, -128i8 would overflow if 128i8 was negated at runtime
+        if let (UnaryOp::Minus | UnaryOp::WrappingMinus, Expr::Literal(literal)) = (&node.operation, node.right.as_ref()) {
+            if let LiteralType::Int(value, _) = literal.literal_type {
+                let int_type = self.int_type(literal.type_kind.clone());
+                let value = int_type.wrap(literal_value(value, int_type).wrapping_neg());
+                self.stack_depth += 1;
+                self.push_immediate(value, program);
+                return;
+            }
+        }
+        self.visit_expr(node.right.as_ref(), program);
+        program.push_str("\t\tpopq %rax\n");
+        match node.operation {
+            // flipping the sign bit negates every float, NaN and 0 too
+            UnaryOp::Minus if node.right.type_kind().map(|type_kind| self.concrete(&type_kind)) == Some(TypeKind::Float) => {
+                program.push_str("\t\tbtcq $63, %rax\n\t\tpushq %rax\n");
+            }
+            UnaryOp::Minus | UnaryOp::WrappingMinus => {
+                let int_type = self.int_type(node.right.type_kind());
+                program.push_str(format!("\t\tneg{} {}\n", suffix(int_type.bits()), register('a', int_type.bits())).as_str());
+                if let UnaryOp::Minus = node.operation {
+                    self.check_overflow(int_type, self.checked(node.token), program);
+                }
+                self.push_extended(int_type, program);
+            }
+            UnaryOp::Not => program.push_str("\t\txorq $1, %rax\n\t\tpushq %rax\n"),
+        }
+    }
+
+    // between integers only the target's width and signedness matter, extending its low bits
+    // gives the value
+    fn visit_cast(&mut self, node: &'a Cast<'a>, program: &mut String) {
+        self.visit_expr(node.expr.as_ref(), program);
+        let source = node.expr.type_kind().map(|type_kind| self.concrete(&type_kind));
+        match (source, &node.target) {
+            (Some(TypeKind::Float), TypeKind::Float) => {}
+            (Some(TypeKind::Float), TypeKind::Int(int_type)) => self.float_to_int(*int_type, program),
+            (source, TypeKind::Float) => {
+                program.push_str("\t\tpopq %rax\n");
+                if self.int_type(source) == IntType::U64 {
+                    self.u64_to_float(program);
+                } else {
+                    // narrower values are already extended to 64 bits
+                    program.push_str("\t\tcvtsi2sdq %rax, %xmm0\n");
+                }
+                program.push_str("\t\tmovq %xmm0, %rax\n\t\tpushq %rax\n");
+            }
+            (_, TypeKind::Int(int_type)) => {
+                if let Some(extend) = extend(*int_type) {
+                    program.push_str(format!("\t\tpopq %rax\n\t\t{}\n\t\tpushq %rax\n", extend).as_str());
+                }
+            }
+            _ => unreachable!("typechecker only allows casts to integer types and float"),
+        }
+    }
+
+    // cvtsi2sdq only converts signed values, so a u64 with its top bit set is halved first,
+    // keeping the lowest bit so it still rounds the same way, and doubled after
+    fn u64_to_float(&mut self, program: &mut String) {
         let label = self.bb_label_counter;
         self.bb_label_counter += 1;
-        
-        // order of operands in cmpq matters below
         program.push_str(
             format!("\
-            \t\tpopq %rax\n\
-            \t\tcmpq $1, %rax\n\
-            \t\tje THEN{label}\n\
-            \tELSE{label}:\n\
+            \t\ttestq %rax, %rax\n\
+            \t\tjs CAST_HALVE{label}\n\
+            \t\tcvtsi2sdq %rax, %xmm0\n\
+            \t\tjmp CAST_DONE{label}\n\
+            \tCAST_HALVE{label}:\n\
+            \t\tmovq %rax, %rdx\n\
+            \t\tshrq $1, %rdx\n\
+            \t\tandl $1, %eax\n\
+            \t\torq %rax, %rdx\n\
+            \t\tcvtsi2sdq %rdx, %xmm0\n\
+            \t\taddsd %xmm0, %xmm0\n\
+            \tCAST_DONE{label}:\n\
             ", label=label).as_str()
         );
+    }
 
-        if let Some(else_branch) = &node.else_branch {
-            self.visit_expr(else_branch.as_ref(), program)
-            // TODO possible optimization
-            // If there is no else branch, we can chane je to jne
-            // and fall through to THEN block, and jump over THEN block to IF_DONE
-        }
-
+    // truncates toward zero. Floats out of the target's range become its smallest or biggest
+    // value and NaN becomes 0, cvttsd2siq alone only handles values that fit an i64
+    fn float_to_int(&mut self, int_type: IntType, program: &mut String) {
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        let above = ((int_type.max() + 1) as f64).to_bits() as i64;
+        let below = ((int_type.min() - 1) as f64).to_bits() as i64;
         program.push_str(
             format!("\
-            \t\tjmp IF_ELSE_DONE{label}\n\
-            \tTHEN{label}:\n\
-            ", label=label).as_str()
+            \t\tpopq %rax\n\
+            \t\tmovq %rax, %xmm0\n\
+            \t\txorl %eax, %eax\n\
+            \t\tucomisd %xmm0, %xmm0\n\
+            \t\tjp CAST_DONE{label}\n\
+            \t\tmovabsq ${above}, %rdx\n\
+            \t\tmovq %rdx, %xmm1\n\
+            \t\tmovabsq ${max}, %rax\n\
+            \t\tucomisd %xmm1, %xmm0\n\
+            \t\tjae CAST_DONE{label}\n\
+            \t\tmovabsq ${below}, %rdx\n\
+            \t\tmovq %rdx, %xmm1\n\
+            \t\tmovabsq ${min}, %rax\n\
+            \t\tucomisd %xmm1, %xmm0\n\
+            \t\tjbe CAST_DONE{label}\n\
+            ",
+            label=label,
+            above=above,
+            below=below,
+            max=int_type.max() as i64,
+            min=int_type.min() as i64).as_str()
         );
-
-        self.visit_expr(node.then_branch.as_ref(), program);
-
+        if int_type == IntType::U64 {
+            // values from 2^63 up convert after taking 2^63 off, which sets the top bit back
+            program.push_str(
+                format!("\
+                \t\tmovabsq ${half}, %rdx\n\
+                \t\tmovq %rdx, %xmm1\n\
+                \t\tucomisd %xmm1, %xmm0\n\
+                \t\tjb CAST_CONVERT{label}\n\
+                \t\tsubsd %xmm1, %xmm0\n\
+                \t\tcvttsd2siq %xmm0, %rax\n\
+                \t\tbtsq $63, %rax\n\
+                \t\tjmp CAST_DONE{label}\n\
+                \tCAST_CONVERT{label}:\n\
+                ",
+                label=label,
+                half=((1u64 << 63) as f64).to_bits() as i64).as_str()
+            );
+        }
         program.push_str(
             format!("\
-            \tIF_ELSE_DONE{label}:\n\
+            \t\tcvttsd2siq %xmm0, %rax\n\
+            \tCAST_DONE{label}:\n\
+            \t\tpushq %rax\n\
             ", label=label).as_str()
         );
     }
-}
\ No newline at end of file
+
+    fn visit_literal(&mut self, node: &Literal, program: &mut String) {
+        self.stack_depth += 1;
+        let value = match node.literal_type {
+            LiteralType::Int(value, _) => {
+                let value = literal_value(value, self.int_type(node.type_kind.clone()));
+                self.push_immediate(value, program);
+                return;
+            }
+            // the bits of the value, like a float stored in memory
+            LiteralType::Float => {
+                let value: f64 = node.token.lexeme.replace('_', "").parse().expect("scanner should have rejected invalid float literals");
+                self.push_immediate(value.to_bits() as i64, program);
+                return;
+            }
+            LiteralType::Bool => if node.token.lexeme == "true" { "1" } else { "0" },
+            LiteralType::Error => unreachable!("programs with scan errors aren't compiled"),
+            LiteralType::Str => {
+                let value = scan::unescape(node.token.lexeme)
+                    .expect("scanner should have rejected invalid escapes");
+                self.strings.push(value);
+                program.push_str(
+                    format!("\
+                    \t\tleaq STRING{}(%rip), %rax\n\
+                    \t\tpushq %rax\n\
+                    ", self.strings.len() - 1).as_str()
+                );
+                return;
+            }
+        };
+        program.push_str(
+            format!("\t\tpushq ${}\n", value).as_str()
+        )
+    }
+
+    // push only takes 32 bit immediates, bigger values go through %rax
+    fn push_immediate(&mut self, value: i64, program: &mut String) {
+        if fits_immediate(value) {
+            program.push_str(format!("\t\tpushq ${}\n", value).as_str());
+        } else {
+            program.push_str(format!("\t\tmovabsq ${}, %rax\n\t\tpushq %rax\n", value).as_str());
+        }
+    }
+
+    fn visit_logical(&mut self, node: &'a Logical<'a>, program: &mut String) {
+        self.visit_expr(node.left.as_ref(), program);
+
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        self.stack_depth -= 1;
+
+        // && skips the right operand when the left is false, || when it is true,
+        // and in both cases the left operand is the result
+        let short_circuit = match node.operation {
+            LogicalOp::And => "je",
+            LogicalOp::Or => "jne",
+        };
+        program.push_str(
+            format!("\
+            \t\tpopq %rax\n\
+            \t\tcmpq $0, %rax\n\
+            \t\t{short_circuit} SHORT_CIRCUIT{label}\n\
+            ", short_circuit=short_circuit, label=label).as_str()
+        );
+
+        let branch_depth = self.stack_depth;
+        self.visit_expr(node.right.as_ref(), program);
+        self.stack_depth = branch_depth + 1;
+
+        program.push_str(
+            format!("\
+            \t\tjmp LOGICAL_DONE{label}\n\
+            \tSHORT_CIRCUIT{label}:\n\
+            \t\tpushq %rax\n\
+            \tLOGICAL_DONE{label}:\n\
+            ", label=label).as_str()
+        );
+    }
+
+    fn visit_if_expr(&mut self, node: &'a If<'a>, program: &mut String) {
+        self.visit_expr(node.condition.as_ref(), program);
+        
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        self.stack_depth -= 1;
+
+        // order of operands in cmpq matters below
+        program.push_str(
+            format!("\
+            \t\tpopq %rax\n\
+            \t\tcmpq $1, %rax\n\
+            \t\tje THEN{label}\n\
+            \tELSE{label}:\n\
+            ", label=label).as_str()
+        );
+
+        // only one of the branches runs, so each starts from the same depth
+        let branch_depth = self.stack_depth;
+        if let Some(else_branch) = &node.else_branch {
+            self.visit_expr(else_branch.as_ref(), program)
+            // TODO possible optimization
+            // If there is no else branch, we can chane je to jne
+            // and fall through to THEN block, and jump over THEN block to IF_DONE
+        } else {
+            // keep the stack balanced when the condition is false
+            self.stack_depth += 1;
+            program.push_str("\t\tpushq $0\n");
+        }
+        self.stack_depth = branch_depth;
+
+        program.push_str(
+            format!("\
+            \t\tjmp IF_ELSE_DONE{label}\n\
+            \tTHEN{label}:\n\
+            ", label=label).as_str()
+        );
+
+        self.visit_expr(node.then_branch.as_ref(), program);
+
+        program.push_str(
+            format!("\
+            \tIF_ELSE_DONE{label}:\n\
+            ", label=label).as_str()
+        );
+    }
+
+    fn local(&self, name: &str) -> Option<&Local<'a>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn local_offset(&self, name: &str) -> Option<i32> {
+        self.local(name).map(|local| local.offset)
+    }
+
+    fn variable_offset(&self, name: &str) -> i32 {
+        self.local_offset(name).expect("typechecker should have rejected undefined variables")
+    }
+
+    fn visit_variable(&mut self, node: &'a Variable<'a>, program: &mut String) {
+        match self.local(node.token.lexeme).cloned() {
+            Some(Local {offset, generic: Some(generic)}) => self.visit_generic_use(&generic, &node.instance, offset, program),
+            Some(Local {offset, generic: None}) => {
+                let value_words = self.words(node.type_kind.as_ref().unwrap());
+                self.push_words("%rbp", offset, value_words, program);
+            }
+            // any other name is a function used as a value
+            None => {
+                let symbol = self.function_instance(node);
+                self.stack_depth += 1;
+                program.push_str(
+                    format!("\
+                    \t\tleaq {}(%rip), %rax\n\
+                    \t\tpushq %rax\n\
+                    ", closure_symbol(&symbol)).as_str()
+                );
+            }
+        }
+    }
+
+    // the symbol of the function a variable names. A generic function's instance for the
+    // types this use picked is queued to be generated, unless it already has been
+    fn function_instance(&mut self, node: &Variable) -> String {
+        let function = self.functions[node.token.lexeme];
+        let symbol = function_symbol(node.token.lexeme);
+        if function.type_params.is_empty() {
+            return symbol;
+        }
+        let arguments: Vec<TypeKind> = node.instance.iter().map(|type_kind| self.concrete(type_kind)).collect();
+        let symbol = instance_symbol(&symbol, &arguments);
+        if self.function_instances.insert(symbol.clone()) {
+            self.pending.push((symbol.clone(), function, arguments));
+        }
+        symbol
+    }
+
+    // push a copy of the value whose lowest word is at offset(base)
+    fn push_words(&mut self, base: &str, offset: i32, value_words: i32, program: &mut String) {
+        for word in (0..value_words).rev() {
+            program.push_str(format!("\t\tpushq {}({})\n", offset + 8 * word, base).as_str());
+        }
+        self.stack_depth += value_words;
+    }
+
+    fn visit_block(&mut self, node: &'a Block<'a>, program: &mut String) {
+        let base_depth = self.stack_depth;
+        self.scopes.push(HashMap::new());
+        for statement in &node.statements {
+            match statement {
+                Stmt::Let(let_stmt) => {
+                    // the initializer's value stays on the stack and becomes the variable's slot
+                    let generic = match &let_stmt.initializer {
+                        Expr::Lambda(lambda) if !let_stmt.quantified.is_empty() => {
+                            let captures = self.capture_generics(lambda);
+                            self.closure_record(None, &lambda.captures, program);
+                            Some(Generic {lambda, quantified: let_stmt.quantified.clone(), captures})
+                        }
+                        initializer => {
+                            self.visit_expr(initializer, program);
+                            None
+                        }
+                    };
+                    let local = Local {offset: -8 * self.stack_depth, generic};
+                    self.scopes.last_mut().unwrap().insert(let_stmt.name.lexeme.to_string(), local);
+                }
+                Stmt::Expr(expr) => {
+                    self.visit_expr(expr, program);
+                    self.discard(self.expr_words(expr), program);
+                }
+            }
+        }
+        let value_words = match &node.expr {
+            Some(expr) => {
+                self.visit_expr(expr.as_ref(), program);
+                self.expr_words(expr)
+            }
+            None => {
+                self.stack_depth += 1;
+                program.push_str("\t\tpushq $0\n");
+                1
+            }
+        };
+        self.scopes.pop();
+
+        // drop the block's variables from under its value
+        let locals = self.stack_depth - base_depth - value_words;
+        if locals > 0 {
+            self.move_up(value_words, locals, program);
+        }
+        self.stack_depth = base_depth + value_words;
+    }
+
+    fn discard(&mut self, value_words: i32, program: &mut String) {
+        self.stack_depth -= value_words;
+        program.push_str(format!("\t\taddq ${}, %rsp\n", 8 * value_words).as_str());
+    }
+
+    // move the value on top of the stack up by distance words, dropping whatever was there.
+    // Words are copied from the highest down, so overlapping source and destination are fine
+    fn move_up(&mut self, value_words: i32, distance: i32, program: &mut String) {
+        for word in (0..value_words).rev() {
+            program.push_str(
+                format!("\
+                \t\tmovq {from}(%rsp), %rax\n\
+                \t\tmovq %rax, {to}(%rsp)\n\
+                ", from=8 * word, to=8 * (word + distance)).as_str()
+            );
+        }
+        self.discard(distance, program);
+    }
+
+    fn visit_while(&mut self, node: &'a While<'a>, program: &mut String) {
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+
+        program.push_str(format!("\tWHILE_HEADER{}:\n", label).as_str());
+        self.visit_expr(node.condition.as_ref(), program);
+        self.stack_depth -= 1;
+        program.push_str(
+            format!("\
+            \t\tpopq %rax\n\
+            \t\tcmpq $1, %rax\n\
+            \t\tjne WHILE_DONE{label}\n\
+            ", label=label).as_str()
+        );
+
+        // discard the body's value so every iteration starts from the same depth
+        self.visit_expr(node.body.as_ref(), program);
+        self.discard(self.expr_words(&node.body), program);
+        program.push_str(
+            format!("\
+            \t\tjmp WHILE_HEADER{label}\n\
+            \tWHILE_DONE{label}:\n\
+            ", label=label).as_str()
+        );
+
+        // loops are unit typed, push a placeholder like every other expression
+        self.stack_depth += 1;
+        program.push_str("\t\tpushq $0\n");
+    }
+
+    fn visit_assign(&mut self, node: &'a Assign<'a>, program: &mut String) {
+        let value_words = self.expr_words(&node.value);
+        match node.target.as_ref() {
+            Expr::Variable(variable) => {
+                let offset = self.variable_offset(variable.token.lexeme);
+                self.visit_expr(node.value.as_ref(), program);
+                for word in 0..value_words {
+                    program.push_str(
+                        format!("\
+                        \t\tmovq {from}(%rsp), %rax\n\
+                        \t\tmovq %rax, {to}(%rbp)\n\
+                        ", from=8 * word, to=offset + 8 * word).as_str()
+                    );
+                }
+            }
+            target => {
+                self.visit_place(target, true, program);
+                self.visit_expr(node.value.as_ref(), program);
+                // the target's address sits under the value
+                program.push_str(format!("\t\tmovq {}(%rsp), %rdi\n", 8 * value_words).as_str());
+                for word in 0..value_words {
+                    program.push_str(
+                        format!("\
+                        \t\tmovq {offset}(%rsp), %rax\n\
+                        \t\tmovq %rax, {offset}(%rdi)\n\
+                        ", offset=8 * word).as_str()
+                    );
+                }
+                self.discard(1, program);
+            }
+        }
+        // the value is replaced by the unit placeholder
+        self.discard(value_words, program);
+        self.stack_depth += 1;
+        program.push_str("\t\tpushq $0\n");
+    }
+
+    fn visit_array(&mut self, node: &'a Array<'a>, program: &mut String) {
+        for element in &node.elements {
+            self.visit_expr(element, program);
+        }
+    }
+
+    fn visit_index(&mut self, node: &'a Index<'a>, program: &mut String) {
+        let element_words = self.words(node.type_kind.as_ref().unwrap());
+        if is_place(&node.array) {
+            // read straight out of the variable instead of copying the whole array first
+            self.element_address(node, false, program);
+            self.load_place(element_words, program);
+            return;
+        }
+
+        let array_words = self.expr_words(&node.array);
+        self.visit_expr(node.array.as_ref(), program);
+        self.visit_expr(node.index.as_ref(), program);
+        self.stack_depth -= 1;
+        program.push_str("\t\tpopq %rcx\n");
+        self.element_offset(node, program);
+        self.extract_component(array_words, element_words, program);
+    }
+
+    fn visit_struct_literal(&mut self, node: &'a StructLiteral<'a>, program: &mut String) {
+        let arguments = named_arguments(node.type_kind.as_ref().unwrap());
+        let fields = self.structs[node.name.lexeme].clone();
+        let in_order = fields.iter().zip(node.fields.iter())
+            .all(|((declared, _), (given, _))| *declared == given.lexeme);
+        if in_order {
+            for (_, value) in &node.fields {
+                self.visit_expr(value, program);
+            }
+        } else {
+            // fields are evaluated in the order written, then stored to where the declaration puts them.
+            // The space starts out zeroed, so fields that aren't stored yet don't point anywhere
+            let struct_words = self.layout_words(node.type_kind.as_ref().unwrap());
+            self.stack_depth += struct_words;
+            for _ in 0..struct_words {
+                program.push_str("\t\tpushq $0\n");
+            }
+            self.set_pointers(&self.layout_pointer_words(node.type_kind.as_ref().unwrap()));
+            let struct_offset = -8 * self.stack_depth;
+            for (field, value) in &node.fields {
+                let (field_offset, field_words) = self.field_layout(node.name.lexeme, arguments, field.lexeme);
+                self.visit_expr(value, program);
+                for word in 0..field_words {
+                    program.push_str(
+                        format!("\
+                        \t\tmovq {from}(%rsp), %rax\n\
+                        \t\tmovq %rax, {to}(%rbp)\n\
+                        ", from=8 * word, to=struct_offset + field_offset + 8 * word).as_str()
+                    );
+                }
+                self.discard(field_words, program);
+            }
+        }
+        if self.is_boxed(node.type_kind.as_ref().unwrap()) {
+            self.box_value(node.type_kind.as_ref().unwrap(), program);
+        }
+    }
+
+    fn visit_field(&mut self, node: &'a Field<'a>, program: &mut String) {
+        let (field_offset, field_words) = self.field_offset(node);
+        if is_place(&node.object) {
+            self.field_address(node, false, program);
+            self.load_place(field_words, program);
+            return;
+        }
+
+        let object_type = node.object.type_kind().unwrap();
+        let struct_words = self.layout_words(&object_type);
+        self.visit_expr(node.object.as_ref(), program);
+        if self.is_boxed(&object_type) {
+            self.load_place(struct_words, program);
+        }
+        program.push_str(format!("\t\tmovq ${}, %rcx\n", field_offset).as_str());
+        self.extract_component(struct_words, field_words, program);
+    }
+
+    fn field_offset(&self, node: &Field) -> (i32, i32) {
+        match node.object.type_kind() {
+            Some(TypeKind::Named(name, arguments)) => self.field_layout(&name, &arguments, node.name.lexeme),
+            _ => unreachable!("typechecker only allows accessing fields of structs"),
+        }
+    }
+
+    // replace the address on top of the stack with a copy of the value it points to
+    fn load_place(&mut self, value_words: i32, program: &mut String) {
+        self.stack_depth -= 1;
+        program.push_str("\t\tpopq %rax\n");
+        self.push_words("%rax", 0, value_words, program);
+    }
+
+    // keep only the component at byte offset %rcx of the value on top of the stack. The component
+    // is copied from the highest word down, since it can overlap where it ends up
+    fn extract_component(&mut self, value_words: i32, component_words: i32, program: &mut String) {
+        program.push_str("\t\tleaq (%rsp,%rcx), %rax\n");
+        for word in (0..component_words).rev() {
+            program.push_str(
+                format!("\
+                \t\tmovq {from}(%rax), %rdx\n\
+                \t\tmovq %rdx, {to}(%rsp)\n\
+                ", from=8 * word, to=8 * (value_words - component_words + word)).as_str()
+            );
+        }
+        if value_words > component_words {
+            self.discard(value_words - component_words, program);
+        }
+    }
+
+    // push the address of the lowest word of a variable or an element of one. Other values can
+    // point to the same heap object as a boxed value, so one is copied before it is written to
+    fn visit_place(&mut self, node: &'a Expr<'a>, write: bool, program: &mut String) {
+        match node {
+            Expr::Variable(variable) => {
+                let offset = self.variable_offset(variable.token.lexeme);
+                self.stack_depth += 1;
+                program.push_str(
+                    format!("\
+                    \t\tleaq {}(%rbp), %rax\n\
+                    \t\tpushq %rax\n\
+                    ", offset).as_str()
+                );
+            }
+            Expr::Index(index) => self.element_address(index, write, program),
+            Expr::Field(field) => self.field_address(field, write, program),
+            _ => unreachable!("parser only allows variables and their elements and fields as places"),
+        }
+    }
+
+    fn element_address(&mut self, node: &'a Index<'a>, write: bool, program: &mut String) {
+        self.visit_place(node.array.as_ref(), write, program);
+        self.visit_expr(node.index.as_ref(), program);
+        self.stack_depth -= 1;
+        program.push_str("\t\tpopq %rcx\n");
+        self.element_offset(node, program);
+        program.push_str("\
+            \t\tpopq %rax\n\
+            \t\taddq %rcx, %rax\n\
+            \t\tpushq %rax\n\
+            "
+        );
+    }
+
+    fn field_address(&mut self, node: &'a Field<'a>, write: bool, program: &mut String) {
+        let (field_offset, _) = self.field_offset(node);
+        self.visit_place(node.object.as_ref(), write, program);
+        let object_type = node.object.type_kind().unwrap();
+        if self.is_boxed(&object_type) {
+            if write {
+                // the copy's address replaces the old one in the place
+                program.push_str("\
+                    \t\tmovq (%rsp), %rax\n\
+                    \t\tpushq (%rax)\n\
+                    "
+                );
+                self.stack_depth += 1;
+                self.load_place(self.layout_words(&object_type), program);
+                self.box_value(&object_type, program);
+                self.stack_depth -= 1;
+                program.push_str("\
+                    \t\tpopq %rax\n\
+                    \t\tmovq (%rsp), %rdx\n\
+                    \t\tmovq %rax, (%rdx)\n\
+                    \t\tmovq %rax, (%rsp)\n\
+                    "
+                );
+            } else {
+                program.push_str("\
+                    \t\tmovq (%rsp), %rax\n\
+                    \t\tmovq (%rax), %rax\n\
+                    \t\tmovq %rax, (%rsp)\n\
+                    "
+                );
+            }
+        }
+        program.push_str(format!("\t\taddq ${}, (%rsp)\n", field_offset).as_str());
+    }
+
+    // bounds check the index in %rcx, then turn it into the byte offset of the element's
+    // lowest word from the array's lowest word. Element 0 was pushed first, so it is highest
+    fn element_offset(&mut self, node: &Index, program: &mut String) {
+        let (element_words, length) = match node.array.type_kind() {
+            Some(TypeKind::Array(element_type, length)) => (self.words(&element_type), length),
+            _ => unreachable!("typechecker only allows indexing arrays"),
+        };
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        // negative indexes are huge when compared unsigned, so one check covers both ends
+        program.push_str(
+            format!("\
+            \t\tcmpq ${length}, %rcx\n\
+            \t\tjb INDEX_OK{label}\n\
+            \t\tmovq %rcx, %rdi\n\
+            \t\tmovq ${length}, %rsi\n\
+            \t\tjmp {out_of_bounds}\n\
+            \tINDEX_OK{label}:\n\
+            \t\tnegq %rcx\n\
+            \t\taddq ${last}, %rcx\n\
+            \t\timulq ${element_bytes}, %rcx\n\
+            ",
+            length=length,
+            label=label,
+            out_of_bounds=runtime::INDEX_OUT_OF_BOUNDS,
+            last=length - 1,
+            element_bytes=8 * element_words).as_str()
+        );
+    }
+
+    fn visit_call(&mut self, node: &'a Call<'a>, program: &mut String) {
+        let return_words = self.words(node.type_kind.as_ref().unwrap());
+        let variable = match node.callee.as_ref() {
+            Expr::Variable(variable) if BUILTINS.contains(&variable.token.lexeme)
+                || self.local_offset(variable.token.lexeme).is_none() => variable,
+            Expr::Path(path) => {
+                self.visit_variant(path, &node.arguments, program);
+                return;
+            }
+            callee => {
+                let description = format!("call through a closure at line {}, column {}", node.token.line, node.token.column);
+                self.gen_user_call(Callee::Closure(callee), node, description, return_words, program);
+                return;
+            }
+        };
+        let name = variable.token.lexeme;
+        if name == "len" {
+            self.visit_len(&node.arguments[0], program);
+            return;
+        }
+        let symbol = match name {
+            // builtins are runtime routines picked by the argument's type
+            "print" => match node.arguments[0].type_kind() {
+                Some(TypeKind::Float) => String::from(runtime::PRINT_FLOAT),
+                Some(TypeKind::Bool) => String::from(runtime::PRINT_BOOL),
+                Some(TypeKind::Str) => String::from(runtime::PRINT_STR),
+                Some(TypeKind::Int(IntType::U64)) => String::from(runtime::PRINT_UINT),
+                _ => String::from(runtime::PRINT_INT),
+            },
+            _ => {
+                let symbol = self.function_instance(variable);
+                let description = format!("call to {} at line {}, column {}", name, variable.token.line, variable.token.column);
+                self.gen_user_call(Callee::Symbol(symbol), node, description, return_words, program);
+                return;
+            }
+        };
+
+        self.gen_call(Callee::Symbol(symbol), &node.arguments, return_words, program);
+        self.push_result(node, return_words, program);
+    }
+
+    fn gen_user_call(&mut self, callee: Callee<'a, 'a>, node: &'a Call<'a>, description: String, return_words: i32, program: &mut String) {
+        let problem = self.tail_call_problem(node);
+        match problem {
+            None => self.gen_tail_call(callee, &node.arguments, return_words, program),
+            Some(_) => {
+                self.gen_call(callee, &node.arguments, return_words, program);
+                self.push_result(node, return_words, program);
+            }
+        }
+        let context = match &self.frame {
+            Some(frame) => frame.name.clone(),
+            None => String::from("the top level"),
+        };
+        // recursion through it uses up the stack, where a jump wouldn't
+        if let (Some(reason), true) = (&problem, self.in_tail_position(node)) {
+            let message = format!("{} in {} is in tail position but stays an ordinary call: {}", description, context, reason);
+            if !self.warnings.iter().any(|warning| warning.message == message) {
+                self.warnings.push(Warning {message});
+            }
+        }
+        if self.report_tail_calls {
+            let note = match problem {
+                None => format!("{} in {} is a tail call", description, context),
+                Some(reason) => format!("{} in {} is not a tail call: {}", description, context, reason),
+            };
+            if !self.notes.contains(&note) {
+                self.notes.push(note);
+            }
+        }
+    }
+
+    // why a call can't reuse the frame of the body it is in, if it can't
+    fn tail_call_problem(&self, node: &Call) -> Option<String> {
+        let frame = match &self.frame {
+            Some(frame) => frame,
+            None => return Some(String::from("the top level has no frame to reuse")),
+        };
+        if !self.in_tail_position(node) {
+            return Some(String::from("it isn't in tail position"));
+        }
+        let stack_argument_words = self.stack_argument_words(&node.arguments);
+        let callee_area = self.named_callee(node).map_or(0, |callee| self.argument_areas[&callee]);
+        let needed = stack_argument_words.max(callee_area);
+        if needed > frame.argument_area {
+            return Some(format!("it needs {} word{} of stack argument space but callers of {} only reserve {}",
+                needed, if needed == 1 { "" } else { "s" }, frame.name, frame.argument_area));
+        }
+        None
+    }
+
+    fn in_tail_position(&self, node: &Call) -> bool {
+        self.frame.as_ref().is_some_and(|frame| frame.tail_calls.iter().any(|call| std::ptr::eq(*call, node)))
+    }
+
+    // the arguments are evaluated like for a call, then put where the callee looks for them:
+    // registers, and the stack slots this body's own arguments came in. Leaving the frame
+    // before jumping makes the callee return straight to this body's caller
+    fn gen_tail_call(&mut self, callee: Callee<'a, 'a>, arguments: &'a [Expr<'a>], return_words: i32, program: &mut String) {
+        let depth = self.stack_depth;
+        let (symbol, closure_offset) = match callee {
+            Callee::Symbol(symbol) => (symbol, None),
+            Callee::Closure(expr) => {
+                self.visit_expr(expr, program);
+                (String::from("*(%r10)"), Some(-8 * self.stack_depth))
+            }
+        };
+
+        let base_depth = self.stack_depth;
+        for argument in arguments {
+            self.visit_expr(argument, program);
+        }
+        let argument_offset = |index: usize| -8 * (base_depth + index as i32 + 1);
+        let argument_count = (self.stack_depth - base_depth) as usize;
+        for index in ARGUMENT_REGISTERS.len()..argument_count {
+            program.push_str(
+                format!("\
+                \t\tmovq {}(%rbp), %rax\n\
+                \t\tmovq %rax, {}(%rbp)\n\
+                ", argument_offset(index), 16 + 8 * (index - ARGUMENT_REGISTERS.len())).as_str()
+            );
+        }
+        for (index, register) in ARGUMENT_REGISTERS.iter().enumerate().take(argument_count) {
+            program.push_str(format!("\t\tmovq {}(%rbp), {}\n", argument_offset(index), register).as_str());
+        }
+        if let Some(offset) = closure_offset {
+            program.push_str(format!("\t\tmovq {}(%rbp), %r10\n", offset).as_str());
+        }
+        // a wide result goes to the address this body's caller passed
+        if return_words > 1 {
+            program.push_str("\t\tmovq -8(%rbp), %rax\n");
+        }
+        program.push_str(format!("\t\tleave\n\t\tjmp {}\n", symbol).as_str());
+
+        // nothing after the jump runs, but the code that follows expects the call's result
+        self.stack_depth = depth + return_words;
+    }
+
+    fn push_result(&mut self, node: &Call, return_words: i32, program: &mut String) {
+        match node.type_kind.as_ref().map(|type_kind| self.concrete(type_kind)) {
+            // runtime routines don't leave anything meaningful in %rax
+            Some(TypeKind::Unit) => program.push_str("\t\tpushq $0\n"),
+            _ if return_words > 1 => {}
+            _ => program.push_str("\t\tpushq %rax\n"),
+        }
+    }
+
+    // the lambda's code is generated on its own. Its value is a pointer to a closure record
+    // holding a pointer to that code followed by the captured values
+    fn visit_lambda(&mut self, node: &'a Lambda<'a>, program: &mut String) {
+        let symbol = format!("LAMBDA{}", self.lambda_count);
+        self.lambda_count += 1;
+        let capture_generics = self.capture_generics(node);
+        self.gen_lambda_code(&symbol, node, &capture_generics);
+        self.closure_record(Some(&symbol), &node.captures, program);
+    }
+
+    fn capture_generics(&self, node: &Lambda) -> Vec<Option<Generic<'a>>> {
+        node.captures.iter()
+            .map(|(name, _)| self.local(name).and_then(|local| local.generic.clone()))
+            .collect()
+    }
+
+    fn gen_lambda_code(&mut self, symbol: &str, node: &'a Lambda<'a>, capture_generics: &[Option<Generic<'a>>]) {
+        let (param_types, return_type) = match node.type_kind.as_ref().unwrap() {
+            TypeKind::Function(params, return_type) => (params.clone(), return_type.as_ref().clone()),
+            _ => unreachable!("typechecker gives lambdas function types"),
+        };
+        let params: Vec<(String, TypeKind)> = node.params.iter().zip(param_types)
+            .map(|(param, type_kind)| (param.name.lexeme.to_string(), type_kind))
+            .collect();
+        let captures: Vec<(String, TypeKind, Option<Generic<'a>>)> = node.captures.iter().zip(capture_generics)
+            .map(|((name, type_kind), generic)| (name.clone(), type_kind.clone(), generic.clone()))
+            .collect();
+
+        // the enclosing function's frame is set aside while the lambda's body is generated
+        let stack_depth = self.stack_depth;
+        let pointers = std::mem::take(&mut self.pointers);
+        let scopes = std::mem::take(&mut self.scopes);
+        let frame = self.frame.replace(Frame::new(format!("lambda at line {}, column {}", node.token.line, node.token.column)));
+        let mut code = String::new();
+        self.gen_function_body(symbol, &params, &captures, &return_type, node.body.as_ref(), &mut code);
+        self.lambda_code.push_str(code.as_str());
+        self.stack_depth = stack_depth;
+        self.pointers = pointers;
+        self.scopes = scopes;
+        self.frame = frame;
+    }
+
+    // allocate a closure record for the captured variables and push a pointer to it. A generic
+    // lambda's record has no code, every use copies it and fills in the code for its types
+    fn closure_record(&mut self, code: Option<&str>, captures: &[(String, TypeKind)], program: &mut String) {
+        let capture_words: i32 = captures.iter().map(|(_, type_kind)| self.words(type_kind)).sum();
+        self.stack_depth += 2;
+        program.push_str(format!("\t\tpushq ${}\n", 8 * (1 + capture_words)).as_str());
+        self.push_immediate(self.record_mask(captures), program);
+        self.emit_call(runtime::ALLOC, self.stack_depth - 2, None, program);
+        match code {
+            Some(symbol) => program.push_str(
+                format!("\
+                \t\tleaq {}(%rip), %rdx\n\
+                \t\tmovq %rdx, (%rax)\n\
+                ", symbol).as_str()
+            ),
+            None => program.push_str("\t\tmovq $0, (%rax)\n"),
+        }
+        let mut start = 0;
+        for (name, type_kind) in captures {
+            let offset = self.variable_offset(name);
+            let value_words = self.words(type_kind);
+            for word in 0..value_words {
+                program.push_str(
+                    format!("\
+                    \t\tmovq {from}(%rbp), %rdx\n\
+                    \t\tmovq %rdx, {to}(%rax)\n\
+                    ", from=offset + 8 * word, to=8 + 8 * (start + word)).as_str()
+                );
+            }
+            start += value_words;
+        }
+        self.stack_depth += 1;
+        program.push_str("\t\tpushq %rax\n");
+        self.set_pointers(&[true]);
+    }
+
+    // which words of a closure record with these captures the collector has to look at.
+    // The first word points to code
+    fn record_mask(&self, captures: &[(String, TypeKind)]) -> i64 {
+        let mut pointers = vec![false];
+        for (_, type_kind) in captures {
+            let mut value = self.pointer_words(type_kind);
+            // the words of a value go from its last component at the lowest address to its first
+            value.reverse();
+            pointers.extend(value);
+        }
+        pointer_mask(&pointers)
+    }
+
+    // move the words of a boxed value on top of the stack to a new heap object, leaving a pointer to it
+    fn box_value(&mut self, type_kind: &TypeKind, program: &mut String) {
+        let value_words = self.layout_words(type_kind);
+        let mut pointers = self.layout_pointer_words(type_kind);
+        pointers.reverse();
+        // the words stay on the stack while the allocator runs, so the collector sees what they point to
+        self.stack_depth += 2;
+        program.push_str(format!("\t\tpushq ${}\n", 8 * value_words).as_str());
+        self.push_immediate(pointer_mask(&pointers), program);
+        self.emit_call(runtime::ALLOC, self.stack_depth - 2, None, program);
+        for word in 0..value_words {
+            program.push_str(
+                format!("\
+                \t\tmovq {offset}(%rsp), %rdx\n\
+                \t\tmovq %rdx, {offset}(%rax)\n\
+                ", offset=8 * word).as_str()
+            );
+        }
+        self.discard(value_words, program);
+        self.stack_depth += 1;
+        program.push_str("\t\tpushq %rax\n");
+    }
+
+    // for each word of a value in the order they are pushed, whether it can point to a heap
+    // object. The payload words of an enum can when they do for any variant
+    fn pointer_words(&self, type_kind: &TypeKind) -> Vec<bool> {
+        match type_kind {
+            TypeKind::Function(_, _) => vec![true],
+            TypeKind::Array(element_type, length) => self.pointer_words(element_type).repeat(*length),
+            TypeKind::Named(name, _) if self.boxed.contains(name) => vec![true],
+            TypeKind::Named(..) => self.layout_pointer_words(type_kind),
+            TypeKind::Var(_) | TypeKind::Param(_) => self.pointer_words(&self.concrete(type_kind)),
+            _ => vec![false; self.words(type_kind) as usize],
+        }
+    }
+
+    // the same for the words of a struct or enum value, as they are laid out in a boxed one's heap object
+    fn layout_pointer_words(&self, type_kind: &TypeKind) -> Vec<bool> {
+        match self.concrete(type_kind) {
+            TypeKind::Named(name, arguments) if self.structs.contains_key(&name) => self.struct_fields(&name, &arguments).iter()
+                .flat_map(|(_, field_type)| self.pointer_words(field_type))
+                .collect(),
+            TypeKind::Named(name, arguments) => {
+                let mut words = vec![false; self.layout_words(type_kind) as usize];
+                for variant in 0..self.enums[&name].len() {
+                    let payload = self.variant_payload(&name, &arguments, variant);
+                    let pointers = payload.iter().flat_map(|type_kind| self.pointer_words(type_kind));
+                    for (word, pointer) in pointers.enumerate() {
+                        words[1 + word] |= pointer;
+                    }
+                }
+                words
+            }
+            _ => unreachable!("only structs and enums have a layout"),
+        }
+    }
+
+    // a use of a generic lambda gets a copy of the record in its slot, pointing to
+    // code generated for the types this use picked
+    fn visit_generic_use(&mut self, generic: &Generic<'a>, instance: &[TypeKind], offset: i32, program: &mut String) {
+        let mut substitution = self.substitution.clone();
+        for (variable, type_kind) in generic.quantified.iter().zip(instance) {
+            substitution.insert(*variable, self.concrete(type_kind));
+        }
+        let mut types: Vec<(u32, TypeKind)> = substitution.iter().map(|(variable, type_kind)| (*variable, type_kind.clone())).collect();
+        types.sort_by_key(|(variable, _)| *variable);
+        // the lambda can also use the type parameters of the generic function it is in
+        let mut type_arguments: Vec<(&String, &TypeKind)> = self.type_arguments.iter().collect();
+        type_arguments.sort_by_key(|(name, _)| *name);
+        let key = format!("{:p} {:?} {:?}", generic.lambda, types, type_arguments);
+        let symbol = match self.instances.get(&key) {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let symbol = format!("LAMBDA{}", self.lambda_count);
+                self.lambda_count += 1;
+                self.instances.insert(key, symbol.clone());
+                let outer = std::mem::replace(&mut self.substitution, substitution);
+                self.gen_lambda_code(&symbol, generic.lambda, &generic.captures);
+                self.substitution = outer;
+                symbol
+            }
+        };
+
+        let record_words = 1 + generic.lambda.captures.iter().map(|(_, type_kind)| self.words(type_kind)).sum::<i32>();
+        self.stack_depth += 2;
+        program.push_str(format!("\t\tpushq ${}\n", 8 * record_words).as_str());
+        self.push_immediate(self.record_mask(&generic.lambda.captures), program);
+        self.emit_call(runtime::ALLOC, self.stack_depth - 2, None, program);
+        program.push_str(
+            format!("\
+            \t\tmovq {offset}(%rbp), %rsi\n\
+            \t\tleaq {symbol}(%rip), %rdx\n\
+            \t\tmovq %rdx, (%rax)\n\
+            ", offset=offset, symbol=symbol).as_str()
+        );
+        for word in 1..record_words {
+            program.push_str(
+                format!("\
+                \t\tmovq {offset}(%rsi), %rdx\n\
+                \t\tmovq %rdx, {offset}(%rax)\n\
+                ", offset=8 * word).as_str()
+            );
+        }
+        self.stack_depth += 1;
+        program.push_str("\t\tpushq %rax\n");
+    }
+
+    fn visit_variant(&mut self, path: &'a Path<'a>, arguments: &'a [Expr<'a>], program: &mut String) {
+        let enum_name = path.qualifier.lexeme;
+        let index = self.variant_index(enum_name, path.name.lexeme);
+        self.stack_depth += 1;
+        program.push_str(format!("\t\tpushq ${}\n", index).as_str());
+        let base_depth = self.stack_depth;
+        for argument in arguments {
+            self.visit_expr(argument, program);
+        }
+        // zero the words only larger variants use, so every value of the enum has the same size
+        let total_words = self.layout_words(path.type_kind.as_ref().unwrap());
+        let padding = total_words - 1 - (self.stack_depth - base_depth);
+        for _ in 0..padding {
+            program.push_str("\t\tpushq $0\n");
+        }
+        self.stack_depth += padding;
+        if self.is_boxed(path.type_kind.as_ref().unwrap()) {
+            self.box_value(path.type_kind.as_ref().unwrap(), program);
+        }
+    }
+
+    // arms are tried in order, each pattern test jumping to the next arm when it fails.
+    // The scrutinee stays on the stack until the match is done, bindings are copied out of it
+    fn visit_match(&mut self, node: &'a Match<'a>, program: &mut String) {
+        let scrutinee_type = node.scrutinee.type_kind().unwrap();
+        let scrutinee_words = self.words(&scrutinee_type);
+        self.visit_expr(node.scrutinee.as_ref(), program);
+        let scrutinee_offset = -8 * self.stack_depth;
+
+        let label = self.bb_label_counter;
+        self.bb_label_counter += 1;
+        let base_depth = self.stack_depth;
+        let result_words = self.words(node.type_kind.as_ref().unwrap());
+
+        for (index, arm) in node.arms.iter().enumerate() {
+            program.push_str(format!("\tMATCH{}_ARM{}:\n", label, index).as_str());
+            let next_arm = format!("MATCH{}_ARM{}", label, index + 1);
+            self.pattern_test(&arm.pattern, &scrutinee_type, &[scrutinee_offset], &next_arm, program);
+
+            self.scopes.push(HashMap::new());
+            self.pattern_bindings(&arm.pattern, &scrutinee_type, &[scrutinee_offset], program);
+            self.visit_expr(&arm.body, program);
+            self.scopes.pop();
+
+            let bindings = self.stack_depth - base_depth - result_words;
+            if bindings > 0 {
+                self.move_up(result_words, bindings, program);
+            }
+            self.stack_depth = base_depth;
+            program.push_str(format!("\t\tjmp MATCH_DONE{}\n", label).as_str());
+        }
+        // the typechecker made sure some arm always matches
+        program.push_str(
+            format!("\
+            \tMATCH{label}_ARM{arms}:\n\
+            \t\tud2\n\
+            \tMATCH_DONE{label}:\n\
+            ", label=label, arms=node.arms.len()).as_str()
+        );
+
+        self.stack_depth = base_depth + result_words;
+        self.move_up(result_words, scrutinee_words, program);
+    }
+
+    // where a part of the scrutinee is, its lowest word is at path[0](%rbp) when the path has one offset.
+    // Parts of boxed values are in heap objects, each later offset is from the address found at the
+    // one before. Leaves the address the last offset is from in %rcx unless it is %rbp
+    fn follow_path(&self, path: &[i32], program: &mut String) -> (&'static str, i32) {
+        let (last, objects) = path.split_last().unwrap();
+        let mut base = "%rbp";
+        for offset in objects {
+            program.push_str(format!("\t\tmovq {}({}), %rcx\n", offset, base).as_str());
+            base = "%rcx";
+        }
+        (base, *last)
+    }
+
+    // the path to a component at byte offset from the lowest word of a value of type_kind
+    fn component_path(&self, path: &[i32], type_kind: &TypeKind, offset: i32) -> Vec<i32> {
+        let mut path = path.to_vec();
+        if self.is_boxed(type_kind) {
+            path.push(offset);
+        } else {
+            *path.last_mut().unwrap() += offset;
+        }
+        path
+    }
+
+    // jump to fail_label unless the value at path, see follow_path, matches pattern
+    fn pattern_test(&mut self, pattern: &Pattern, type_kind: &TypeKind, path: &[i32], fail_label: &str, program: &mut String) {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Binding(_) => {}
+            Pattern::Literal(token, literal_type) => {
+                let value = match literal_type {
+                    LiteralType::Bool => if token.lexeme == "true" { 1 } else { 0 },
+                    LiteralType::Int(value, _) => literal_value(*value, self.int_type(Some(type_kind.clone()))),
+                    _ => unreachable!("parser only allows integer and bool patterns"),
+                };
+                // like push, cmp only takes 32 bit immediates
+                let operand = if fits_immediate(value) {
+                    format!("${}", value)
+                } else {
+                    program.push_str(format!("\t\tmovabsq ${}, %rax\n", value).as_str());
+                    String::from("%rax")
+                };
+                let (base, offset) = self.follow_path(path, program);
+                program.push_str(
+                    format!("\
+                    \t\tcmpq {}, {}({})\n\
+                    \t\tjne {}\n\
+                    ", operand, offset, base, fail_label).as_str()
+                );
+            }
+            Pattern::Variant(variant) => {
+                let enum_name = variant.enum_name.lexeme;
+                let index = self.variant_index(enum_name, variant.variant.lexeme);
+                let arguments = named_arguments(type_kind);
+                let (tag_offset, field_offsets) = self.variant_layout(enum_name, arguments, index);
+                let payload = self.variant_payload(enum_name, arguments, index);
+                let (base, offset) = self.follow_path(&self.component_path(path, type_kind, tag_offset), program);
+                program.push_str(
+                    format!("\
+                    \t\tcmpq ${}, {}({})\n\
+                    \t\tjne {}\n\
+                    ", index, offset, base, fail_label).as_str()
+                );
+                for ((field, field_type), field_offset) in variant.fields.iter().zip(payload.iter()).zip(field_offsets) {
+                    self.pattern_test(field, field_type, &self.component_path(path, type_kind, field_offset), fail_label, program);
+                }
+            }
+        }
+    }
+
+    // push a copy of every value the pattern binds and put its name in the innermost scope
+    fn pattern_bindings(&mut self, pattern: &Pattern, type_kind: &TypeKind, path: &[i32], program: &mut String) {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Literal(..) => {}
+            Pattern::Binding(name) => {
+                let value_words = self.words(type_kind);
+                let (base, offset) = self.follow_path(path, program);
+                self.push_words(base, offset, value_words, program);
+                self.set_pointers(&self.pointer_words(type_kind));
+                let local = Local {offset: -8 * self.stack_depth, generic: None};
+                self.scopes.last_mut().unwrap().insert(name.lexeme.to_string(), local);
+            }
+            Pattern::Variant(variant) => {
+                let enum_name = variant.enum_name.lexeme;
+                let index = self.variant_index(enum_name, variant.variant.lexeme);
+                let arguments = named_arguments(type_kind);
+                let (_, field_offsets) = self.variant_layout(enum_name, arguments, index);
+                let payload = self.variant_payload(enum_name, arguments, index);
+                for ((field, field_type), field_offset) in variant.fields.iter().zip(payload.iter()).zip(field_offsets) {
+                    self.pattern_bindings(field, field_type, &self.component_path(path, type_kind, field_offset), program);
+                }
+            }
+        }
+    }
+
+    fn visit_len(&mut self, argument: &'a Expr<'a>, program: &mut String) {
+        self.visit_expr(argument, program);
+        match argument.type_kind() {
+            Some(TypeKind::Array(_, length)) => {
+                self.discard(self.expr_words(argument), program);
+                self.stack_depth += 1;
+                program.push_str(format!("\t\tpushq ${}\n", length).as_str());
+            }
+            // the length is stored in front of the bytes
+            _ => program.push_str("\
+                \t\tpopq %rax\n\
+                \t\tmovq (%rax), %rax\n\
+                \t\tpushq %rax\n\
+                "
+            ),
+        }
+    }
+
+    // call a user function or closure. A one word result is left in %rax for the caller to push, a wider one
+    // is written by the callee into space reserved on the stack before the arguments
+    fn gen_call(&mut self, callee: Callee<'a, 'a>, arguments: &'a [Expr<'a>], return_words: i32, program: &mut String) {
+        let result_address = if return_words > 1 {
+            self.stack_depth += return_words;
+            program.push_str(format!("\t\tsubq ${}, %rsp\n", 8 * return_words).as_str());
+            // nothing is written there until the callee returns
+            self.set_pointers(&vec![false; return_words as usize]);
+            Some(-8 * self.stack_depth)
+        } else {
+            None
+        };
+
+        // the closure is evaluated before the arguments and stays under them until the call returns
+        let (symbol, closure_offset) = match callee {
+            Callee::Symbol(symbol) => (symbol, None),
+            Callee::Closure(expr) => {
+                self.visit_expr(expr, program);
+                (String::from("*(%r10)"), Some(-8 * self.stack_depth))
+            }
+        };
+
+        // arguments are evaluated left to right onto the stack, then moved where the ABI wants them
+        let base_depth = self.stack_depth;
+        for argument in arguments {
+            self.visit_expr(argument, program);
+        }
+        if let Some(offset) = closure_offset {
+            program.push_str(format!("\t\tmovq {}(%rbp), %r10\n", offset).as_str());
+        }
+        self.emit_call(symbol.as_str(), base_depth, result_address, program);
+        if closure_offset.is_some() {
+            self.discard(1, program);
+        }
+        if return_words == 1 {
+            self.stack_depth += 1;
+        }
+    }
+
+    // call symbol with the words between base_depth and the top of the stack as its arguments,
+    // the arguments are popped afterwards and the result is left in %rax.
+    // result_address is passed in %rax to functions returning more than one word
+    fn emit_call(&mut self, symbol: &str, base_depth: i32, result_address: Option<i32>, program: &mut String) {
+        let argument_offset = |index: usize| -8 * (base_depth + index as i32 + 1);
+
+        let argument_count = (self.stack_depth - base_depth) as usize;
+        let stack_arguments = argument_count.saturating_sub(ARGUMENT_REGISTERS.len()) as i32;
+        // room for the stack arguments of the callee's tail calls goes above its own
+        let area = match self.argument_areas.get(symbol) {
+            Some(area) => stack_arguments.max(*area as i32),
+            None => stack_arguments,
+        };
+        // %rsp has to be 16 byte aligned at the call instruction
+        let padding = (self.stack_depth + area) % 2;
+        if padding == 1 {
+            program.push_str("\t\tsubq $8, %rsp\n");
+        }
+        for _ in stack_arguments..area {
+            program.push_str("\t\tpushq $0\n");
+        }
+        for index in (ARGUMENT_REGISTERS.len()..argument_count).rev() {
+            program.push_str(format!("\t\tpushq {}(%rbp)\n", argument_offset(index)).as_str());
+        }
+        for (index, register) in ARGUMENT_REGISTERS.iter().enumerate().take(argument_count) {
+            program.push_str(format!("\t\tmovq {}(%rbp), {}\n", argument_offset(index), register).as_str());
+        }
+
+        if let Some(offset) = result_address {
+            program.push_str(format!("\t\tleaq {}(%rbp), %rax\n", offset).as_str());
+        }
+        // the arguments are copied into the callee's frame, so only the words under them are roots
+        let stack_map = self.stack_map(base_depth);
+        program.push_str(format!("\t\tcall {}\n{}", symbol, stack_map).as_str());
+
+        let cleanup = 8 * (argument_count as i32 + area + padding);
+        if cleanup > 0 {
+            program.push_str(format!("\t\taddq ${}, %rsp\n", cleanup).as_str());
+        }
+        self.stack_depth = base_depth;
+    }
+
+    // what follows a call so the collector can find the call's stack map from its return address:
+    // a 7 byte nop whose 4 byte displacement is the distance from the return address to the map.
+    // The map lists the words up to depth that can point to heap objects
+    fn stack_map(&mut self, depth: i32) -> String {
+        let offsets: Vec<i32> = (1..=depth)
+            .filter(|depth| self.pointers.get(*depth as usize - 1).copied().unwrap_or(false))
+            .map(|depth| -8 * depth)
+            .collect();
+        let index = match self.stack_maps.iter().position(|map| *map == offsets) {
+            Some(index) => index,
+            None => {
+                self.stack_maps.push(offsets);
+                self.stack_maps.len() - 1
+            }
+        };
+        format!("\
+        \t\t.byte 0x0f, 0x1f, 0x80\n\
+        \t\t.long STACKMAP{} - . + 3\n\
+        ", index)
+    }
+}
+
+// the calls whose result is the value of expr, so there is nothing left to do after them
+fn find_tail_calls<'a>(expr: &'a Expr<'a>, calls: &mut Vec<&'a Call<'a>>) {
+    match expr {
+        Expr::Call(call) => calls.push(call),
+        Expr::Grouping(grouping) => find_tail_calls(grouping.expr.as_ref(), calls),
+        Expr::Block(block) => {
+            if let Some(expr) = &block.expr {
+                find_tail_calls(expr.as_ref(), calls);
+            }
+        }
+        // without an else the value is unit whatever the then branch gives
+        Expr::If(if_expr) => {
+            if let Some(else_branch) = &if_expr.else_branch {
+                find_tail_calls(if_expr.then_branch.as_ref(), calls);
+                find_tail_calls(else_branch.as_ref(), calls);
+            }
+        }
+        Expr::Match(match_expr) => {
+            for arm in &match_expr.arms {
+                find_tail_calls(&arm.body, calls);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_place(expr: &Expr) -> bool {
+    match expr {
+        Expr::Variable(_) => true,
+        Expr::Index(index) => is_place(index.array.as_ref()),
+        Expr::Field(field) => is_place(field.object.as_ref()),
+        _ => false,
+    }
+}
+
+// the mask cheer_alloc takes, with bit i set when word i of an object can point to another
+// object. Bit 63 stands for every word from there on
+fn pointer_mask(pointers: &[bool]) -> i64 {
+    let mut mask = 0u64;
+    for (word, pointer) in pointers.iter().enumerate() {
+        if *pointer {
+            mask |= 1 << word.min(63);
+        }
+    }
+    mask as i64
+}
+
+// the type arguments of a struct or enum type
+fn named_arguments(type_kind: &TypeKind) -> &[TypeKind] {
+    match type_kind {
+        TypeKind::Named(_, arguments) => arguments,
+        _ => unreachable!("typechecker only gives struct literals and variant patterns struct and enum types"),
+    }
+}
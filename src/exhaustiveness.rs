@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use crate::typechecker::{EnumDefinition, TypeKind};
+
+// Match patterns reduced to what matters for exhaustiveness. Bindings match anything,
+// so they become wildcards. Checking is the usefulness algorithm from Maranget's
+// "Warnings for pattern matching": a pattern is useful if some value matches it
+// but none of the patterns before it.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Wildcard,
+    Constructor(Constructor, Vec<Pattern>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    // index of the variant in its enum's declaration
+    Variant(usize),
+    Bool(bool),
+    Int(i64),
+}
+
+pub struct Checker<'a> {
+    enums: &'a HashMap<String, EnumDefinition>,
+}
+
+impl Checker<'_> {
+    pub fn new(enums: &HashMap<String, EnumDefinition>) -> Checker<'_> {
+        Checker { enums }
+    }
+
+    // false if every value matching pattern is already matched by one of rows
+    pub fn is_useful(&self, rows: &[Pattern], pattern: &Pattern, type_kind: &TypeKind) -> bool {
+        let rows: Vec<Vec<Pattern>> = rows.iter().map(|row| vec![row.clone()]).collect();
+        self.useful(&rows, std::slice::from_ref(pattern), std::slice::from_ref(type_kind)).is_some()
+    }
+
+    // an example of a value none of the rows match, written as a pattern
+    pub fn missing_pattern(&self, rows: &[Pattern], type_kind: &TypeKind) -> Option<String> {
+        let rows: Vec<Vec<Pattern>> = rows.iter().map(|row| vec![row.clone()]).collect();
+        self.useful(&rows, &[Pattern::Wildcard], std::slice::from_ref(type_kind))
+            .map(|witness| self.display(&witness[0], type_kind))
+    }
+
+    // if vector is useful with respect to rows, returns a witness: values for each column
+    // that vector matches and none of the rows do
+    fn useful(&self, rows: &[Vec<Pattern>], vector: &[Pattern], types: &[TypeKind]) -> Option<Vec<Pattern>> {
+        if vector.is_empty() {
+            return if rows.is_empty() { Some(Vec::new()) } else { None };
+        }
+        let type_kind = &types[0];
+        match &vector[0] {
+            Pattern::Constructor(constructor, fields) => {
+                self.useful_constructor(rows, constructor, fields, &vector[1..], types)
+            }
+            Pattern::Wildcard => {
+                let mut used: Vec<Constructor> = Vec::new();
+                for row in rows {
+                    if let Pattern::Constructor(constructor, _) = &row[0] {
+                        if !used.contains(constructor) {
+                            used.push(constructor.clone());
+                        }
+                    }
+                }
+
+                match self.constructors(type_kind) {
+                    // every constructor appears, so the wildcard is useful if it is useful as one of them
+                    Some(all) if all.iter().all(|constructor| used.contains(constructor)) => {
+                        all.iter().find_map(|constructor| {
+                            let fields = vec![Pattern::Wildcard; self.field_types(type_kind, constructor).len()];
+                            self.useful_constructor(rows, constructor, &fields, &vector[1..], types)
+                        })
+                    }
+                    // otherwise only the rows starting with a wildcard can match the missing constructors
+                    all => {
+                        let default: Vec<Vec<Pattern>> = rows.iter()
+                            .filter(|row| matches!(row[0], Pattern::Wildcard))
+                            .map(|row| row[1..].to_vec())
+                            .collect();
+                        self.useful(&default, &vector[1..], &types[1..]).map(|mut witness| {
+                            let missing = all.filter(|_| !used.is_empty())
+                                .and_then(|all| all.into_iter().find(|constructor| !used.contains(constructor)));
+                            let head = match missing {
+                                Some(constructor) => {
+                                    let fields = vec![Pattern::Wildcard; self.field_types(type_kind, &constructor).len()];
+                                    Pattern::Constructor(constructor, fields)
+                                }
+                                None => Pattern::Wildcard,
+                            };
+                            witness.insert(0, head);
+                            witness
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    // usefulness of constructor(fields) followed by rest, by specializing the rows to that constructor
+    fn useful_constructor(&self, rows: &[Vec<Pattern>], constructor: &Constructor, fields: &[Pattern],
+        rest: &[Pattern], types: &[TypeKind]) -> Option<Vec<Pattern>> {
+        let arity = fields.len();
+        let specialized: Vec<Vec<Pattern>> = rows.iter().filter_map(|row| {
+            let head: Vec<Pattern> = match &row[0] {
+                Pattern::Constructor(row_constructor, row_fields) if row_constructor == constructor => row_fields.clone(),
+                Pattern::Constructor(..) => return None,
+                Pattern::Wildcard => vec![Pattern::Wildcard; arity],
+            };
+            Some([head, row[1..].to_vec()].concat())
+        }).collect();
+        let vector = [fields, rest].concat();
+        let types = [self.field_types(&types[0], constructor), types[1..].to_vec()].concat();
+
+        self.useful(&specialized, &vector, &types).map(|witness| {
+            let mut rebuilt = vec![Pattern::Constructor(constructor.clone(), witness[..arity].to_vec())];
+            rebuilt.extend_from_slice(&witness[arity..]);
+            rebuilt
+        })
+    }
+
+    // every constructor of a type, None when there are too many to list
+    fn constructors(&self, type_kind: &TypeKind) -> Option<Vec<Constructor>> {
+        match type_kind {
+            TypeKind::Bool => Some(vec![Constructor::Bool(true), Constructor::Bool(false)]),
+            TypeKind::Named(name, _) => self.enums.get(name)
+                .map(|definition| (0..definition.variants.len()).map(Constructor::Variant).collect()),
+            _ => None,
+        }
+    }
+
+    fn field_types(&self, type_kind: &TypeKind, constructor: &Constructor) -> Vec<TypeKind> {
+        match (type_kind, constructor) {
+            (TypeKind::Named(name, arguments), Constructor::Variant(index)) => self.enums[name].payload(*index, arguments),
+            _ => Vec::new(),
+        }
+    }
+
+    fn display(&self, pattern: &Pattern, type_kind: &TypeKind) -> String {
+        match pattern {
+            Pattern::Wildcard => String::from("_"),
+            Pattern::Constructor(Constructor::Bool(value), _) => value.to_string(),
+            Pattern::Constructor(Constructor::Int(value), _) => value.to_string(),
+            Pattern::Constructor(constructor @ Constructor::Variant(index), fields) => {
+                let name = match type_kind {
+                    TypeKind::Named(name, _) => name,
+                    _ => unreachable!("only enums have variants"),
+                };
+                let path = format!("{}::{}", name, self.enums[name].variants[*index].0);
+                if fields.is_empty() {
+                    return path;
+                }
+                let field_types = self.field_types(type_kind, constructor);
+                let fields: Vec<String> = fields.iter().zip(field_types.iter())
+                    .map(|(field, field_type)| self.display(field, field_type))
+                    .collect();
+                format!("{}({})", path, fields.join(", "))
+            }
+        }
+    }
+}
@@ -1,45 +1,134 @@
-use typechecker::TypeError;
+use std::{collections::HashSet, fs, path::{Path, PathBuf}};
 
+use typechecker::{TypeError, Warning};
 
-mod scan;
-mod token;
-mod parse;
-mod ast;
+
+pub mod scan;
+pub mod token;
+pub mod parse;
+pub mod ast;
 mod codegen;
+mod runtime;
 mod typechecker;
+mod exhaustiveness;
+mod modules;
+pub mod span;
 
 pub enum CompileResult {
-    Program(String),
-    ParseError(parse::ParseError),
+    Program {asm: String, warnings: Vec<Warning>, notes: Vec<String>},
+    ParseError(Vec<parse::ParseError>),
     TypeError(Vec<TypeError>),
-    ScanError(scan::ScanError)
+    // every error the scanner found, and the errors parsing the tokens it recovered
+    ScanError(Vec<scan::ScanError>, Vec<parse::ParseError>),
+    ImportError(modules::ImportError),
+}
+
+// settings that change the code generated for a program, not whether it compiles
+pub struct Options {
+    // jump to a runtime panic when +, - or * overflow instead of wrapping around. Wrapping
+    // operators like +% are never checked
+    pub overflow_checks: bool,
+    // note for every call to a function or closure whether it became a jump that reuses the
+    // caller's frame, and why not if it didn't
+    pub report_tail_calls: bool,
+    // write what the garbage collector did to stderr when the program exits
+    pub gc_stats: bool,
+}
+
+impl Options {
+    pub fn debug() -> Options {
+        Options {overflow_checks: true, report_tail_calls: false, gc_stats: false}
+    }
+
+    pub fn release() -> Options {
+        Options {overflow_checks: false, report_tail_calls: false, gc_stats: false}
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options::debug()
+    }
 }
 
 pub fn compile(program: &str) -> CompileResult {
-    let mut scanner = scan::Scanner::new();
-    match scanner.scan(&program) {
-        scan::ScanResult::Tokens(tokens) => {
-            match parse::parse(&tokens) {
-                parse::ParseResult::AST(mut ast) => {
-                    let mut typechecker = typechecker::TypeChecker::new();
-                    match typechecker.typecheck(&mut ast) {
-                        typechecker::TypeResult::Success => {
-                            let mut code_generator = codegen::CodeGenerator::new();
-                            let asm = code_generator.gen_code(ast);
-                            CompileResult::Program(asm)
-                        }
-                        typechecker::TypeResult::Error => {
-                            return CompileResult::TypeError(typechecker.errors);
-                        }
-                    }
-                }
-                parse::ParseResult::Error(error) => {
-                    return CompileResult::ParseError(error);
-                }
+    compile_with_options(program, &Options::default())
+}
+
+pub fn compile_with_options(program: &str, options: &Options) -> CompileResult {
+    compile_files(modules::Loader::new(&[]).load(program, None), options)
+}
+
+// compile the program that starts in the file at path. Imported modules are looked for next to
+// the file importing them, and then in the directories of search_path in order
+pub fn compile_file(path: &Path, search_path: &[PathBuf], options: &Options) -> CompileResult {
+    match fs::read_to_string(path) {
+        Ok(program) => compile_files(modules::Loader::new(search_path).load(&program, Some(path)), options),
+        Err(error) => CompileResult::ImportError(modules::ImportError {
+            message: format!("Can't read {}: {}", path.display(), error)
+        }),
+    }
+}
+
+fn compile_files(files: Result<Vec<modules::SourceFile>, modules::ImportError>, options: &Options) -> CompileResult {
+    let files = match files {
+        Ok(files) => files,
+        Err(error) => return CompileResult::ImportError(error),
+    };
+    let mut scan_errors = Vec::new();
+    let mut scanned = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let scan::ScanResult {tokens, errors} = scan::scan(index, &file.source);
+        for error in errors {
+            let start = error.span.start(&file.source);
+            let message = format!("{} at line {}, column {}", error.message, start.line, start.column);
+            scan_errors.push(scan::ScanError {message: file.in_file(&message), ..error});
+        }
+        scanned.push(tokens);
+    }
+    // tokens borrow their lexemes, module qualified ones from names
+    let mut names: Vec<HashSet<String>> = Vec::new();
+    for (file, tokens) in files.iter().zip(&scanned) {
+        match modules::qualified_names(file, tokens) {
+            Ok(file_names) => names.push(file_names),
+            Err(error) => return CompileResult::ImportError(error),
+        }
+    }
+    let tokens: Vec<Vec<token::Token>> = files.iter().zip(scanned).zip(&names)
+        .map(|((file, tokens), names)| modules::qualify(file, tokens, names))
+        .collect();
+    let mut programs = Vec::new();
+    let mut parse_errors = Vec::new();
+    for (file, tokens) in files.iter().zip(&tokens) {
+        match parse::parse(tokens) {
+            parse::ParseResult::Ast(ast) => programs.push(ast),
+            parse::ParseResult::Error(errors) => {
+                parse_errors.extend(errors.into_iter().map(|error| parse::ParseError {message: file.in_file(&error.message), ..error}));
             }
         }
-        scan::ScanResult::Error(error) => {
-            return CompileResult::ScanError(error);
+    }
+    if !scan_errors.is_empty() {
+        return CompileResult::ScanError(scan_errors, parse_errors);
+    }
+    if !parse_errors.is_empty() {
+        return CompileResult::ParseError(parse_errors);
+    }
+    let mut ast = match modules::combine(&files, programs) {
+        Ok(ast) => ast,
+        Err(error) => return CompileResult::ImportError(error),
+    };
+
+    let mut typechecker = typechecker::TypeChecker::new(files.iter().map(|file| (file.module.clone(), file.name())).collect());
+    match typechecker.typecheck(&mut ast) {
+        typechecker::TypeResult::Success => {
+            let mut code_generator = codegen::CodeGenerator::new(options);
+            let asm = code_generator.gen_code(&ast);
+            let mut warnings = typechecker.warnings;
+            warnings.append(&mut code_generator.warnings);
+            CompileResult::Program {asm, warnings, notes: code_generator.notes}
+        }
+        typechecker::TypeResult::Error => {
+            CompileResult::TypeError(typechecker.errors)
         }
     }
-}
\ No newline at end of file
+}
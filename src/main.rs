@@ -1,29 +1,72 @@
-use std::{env, fs::{self, File}, io::Write};
+use std::{env, fs::File, io::Write, path::{Path, PathBuf}};
 
-use rcheer_lib::{compile, CompileResult};
+use rcheer_lib::{compile_file, CompileResult, Options};
+
+// flags come before the file name. --release turns off overflow checks unless they are
+// asked for again with --overflow-checks. --report-tail-calls notes which calls became jumps,
+// --gc-stats makes the program report what the garbage collector did,
+// and every --import-path=DIR adds a directory to look for imported modules in
+fn parse_args(args: &[String]) -> Option<(Options, Vec<PathBuf>, &String)> {
+    let mut options = Options::debug();
+    let mut search_path = Vec::new();
+    let mut overflow_checks = None;
+    let mut report_tail_calls = false;
+    let mut gc_stats = false;
+    let mut filename = None;
+    for arg in args {
+        match arg.as_str() {
+            "--release" => options = Options::release(),
+            "--overflow-checks" => overflow_checks = Some(true),
+            "--no-overflow-checks" => overflow_checks = Some(false),
+            "--report-tail-calls" => report_tail_calls = true,
+            "--gc-stats" => gc_stats = true,
+            flag if flag.starts_with("--import-path=") => search_path.push(PathBuf::from(&flag["--import-path=".len()..])),
+            flag if flag.starts_with("--") => return None,
+            _ if filename.is_some() => return None,
+            _ => filename = Some(arg),
+        }
+    }
+    if let Some(overflow_checks) = overflow_checks {
+        options.overflow_checks = overflow_checks;
+    }
+    options.report_tail_calls = report_tail_calls;
+    options.gc_stats = gc_stats;
+    filename.map(|filename| (options, search_path, filename))
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("usage: rcheer [filename]");
-    } else {
-        let filename = &args[1];
-        let contents = fs::read_to_string(filename)
-            .expect("Something went wrong reading the file");
-        let result = compile(&contents);
+    if let Some((options, search_path, filename)) = parse_args(&args[1..]) {
+        let result = compile_file(Path::new(filename), &search_path, &options);
         let output = "output.s";
         match result {
-            CompileResult::Program(asm) => {
+            CompileResult::Program {asm, warnings, notes} => {
+                for warning in warnings {
+                    eprintln!("warning: {}", warning.message);
+                }
+                for note in notes {
+                    eprintln!("note: {}", note);
+                }
                 let mut file = File::create(output)
-                    .expect(format!("Unable to create output file: {}", output).as_str());
+                    .unwrap_or_else(|_| panic!("Unable to create output file: {}", output));
                 file.write_all(asm.as_bytes())
                     .expect("Failed to write to output file: {:?}")
             }   
-            CompileResult::ParseError(p) => {
-                println!("Error in parsing: {}", p.message)
+            CompileResult::ParseError(parse_errors) => {
+                for p in parse_errors {
+                    println!("Error in parsing: {}", p.message)
+                }
+            }
+            CompileResult::ScanError(scan_errors, parse_errors) => {
+                for s in scan_errors {
+                    println!("Error in scanning: {}", s.message)
+                }
+                for p in parse_errors {
+                    println!("Error in parsing: {}", p.message)
+                }
             }
-            CompileResult::ScanError(s) => {
-                println!("Error in scanning: {:?}", s)
+            CompileResult::ImportError(i) => {
+                println!("Error in importing: {}", i.message)
             }
             CompileResult::TypeError(errors) => {
                 println!("Type checking failed:");
@@ -32,5 +75,7 @@ fn main() {
                 }
             }
         }
+    } else {
+        println!("usage: rcheer [--release] [--overflow-checks | --no-overflow-checks] [--report-tail-calls] [--gc-stats] [--import-path=DIR]... [filename]");
     }
 }
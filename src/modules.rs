@@ -0,0 +1,292 @@
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}};
+
+use crate::{ast::Program, scan::Scanner, token::{Token, TokenType}};
+
+// `import geometry;` at the top of a file loads geometry.ch from the importing file's directory,
+// or else from the first directory of the search path that has it. Files are combined into one
+// program by rewriting their tokens before they are parsed: everything a module declares is
+// named like geometry::area, in the module itself too, and `geometry :: area` in a file that
+// imports it becomes that one name. Tokens borrow their lexemes, so the names are made first,
+// see qualified_names
+
+#[derive(Debug)]
+pub struct ImportError {
+    pub message: String,
+}
+
+pub struct SourceFile {
+    // None for the file the program starts in
+    pub module: Option<String>,
+    // None for a program that wasn't read from a file
+    pub path: Option<PathBuf>,
+    pub source: String,
+    // the modules the file imports
+    pub imports: Vec<String>,
+}
+
+impl SourceFile {
+    // how messages refer to the file
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path.display().to_string(),
+            None => String::from("the program"),
+        }
+    }
+
+    // messages about imported files say which file they are about
+    pub fn in_file(&self, message: &str) -> String {
+        match (&self.module, &self.path) {
+            (Some(_), Some(path)) => format!("{}: {}", path.display(), message),
+            _ => String::from(message),
+        }
+    }
+}
+
+pub struct Loader<'p> {
+    search_path: &'p [PathBuf],
+    // the module name of every file loaded so far
+    modules: HashMap<PathBuf, String>,
+    // the files being loaded, each one imported by the one before it
+    loading: Vec<(Option<PathBuf>, String)>,
+    // every file loaded, in the order they were scanned. A span's file is its index here
+    files: Vec<SourceFile>,
+}
+
+impl<'p> Loader<'p> {
+    pub fn new(search_path: &'p [PathBuf]) -> Loader<'p> {
+        Loader {search_path, modules: HashMap::new(), loading: Vec::new(), files: Vec::new()}
+    }
+
+    // source is the text of the file the program starts in, imports are looked for next to
+    // path, or in the current directory when there is no path
+    pub fn load(mut self, source: &str, path: Option<&Path>) -> Result<Vec<SourceFile>, ImportError> {
+        let path = path.map(|path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        let name = match &path {
+            Some(path) => path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+            None => String::from("the program"),
+        };
+        self.loading.push((path.clone(), name));
+        self.load_file(None, String::from(source), path)?;
+        Ok(self.files)
+    }
+
+    fn load_file(&mut self, module: Option<&str>, source: String, path: Option<PathBuf>) -> Result<(), ImportError> {
+        let directory = match &path {
+            Some(path) => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => PathBuf::from("."),
+        };
+        let imports = imports(Scanner::new(self.files.len(), &source));
+        let imported = imports.iter().map(|(name, _)| name.clone()).collect();
+        self.files.push(SourceFile {module: module.map(String::from), path, source, imports: imported});
+
+        for (name, line) in &imports {
+            let import_path = self.find(name, *line, &directory)?;
+            if let Some(start) = self.loading.iter().position(|(loading, _)| loading.as_ref() == Some(&import_path)) {
+                let mut cycle: Vec<&str> = self.loading[start..].iter().map(|(_, name)| name.as_str()).collect();
+                cycle.push(name);
+                let importer = &self.loading[self.loading.len() - 1].1;
+                return Err(import_error(format!("Import cycle {}, {} imports {} at line {}",
+                    cycle.join(" -> "), importer, name, line)));
+            }
+            if self.modules.contains_key(&import_path) {
+                continue;
+            }
+            if let Some((other, _)) = self.modules.iter().find(|(_, module)| *module == name) {
+                return Err(import_error(format!("Module {} at line {} is {}, but {} was already imported as {}",
+                    name, line, import_path.display(), other.display(), name)));
+            }
+            let source = fs::read_to_string(&import_path).map_err(|error|
+                import_error(format!("Can't read module {} at {}: {}", name, import_path.display(), error))
+            )?;
+            self.loading.push((Some(import_path.clone()), name.clone()));
+            self.load_file(Some(name), source, Some(import_path.clone()))?;
+            self.loading.pop();
+            self.modules.insert(import_path, name.clone());
+        }
+        Ok(())
+    }
+
+    // the file for module name, next to the importing file or else on the search path
+    fn find(&self, name: &str, line: i32, directory: &Path) -> Result<PathBuf, ImportError> {
+        let file = format!("{}.ch", name);
+        let mut directories = vec![directory.to_path_buf()];
+        directories.extend(self.search_path.iter().cloned());
+        for directory in &directories {
+            let candidate = directory.join(&file);
+            if candidate.is_file() {
+                return Ok(candidate.canonicalize().unwrap_or(candidate));
+            }
+        }
+        let searched: Vec<String> = directories.iter().map(|directory| directory.display().to_string()).collect();
+        Err(import_error(format!("Can't find module {} imported at line {}, looked for {} in {}",
+            name, line, file, searched.join(", "))))
+    }
+}
+
+fn import_error(message: String) -> ImportError {
+    ImportError {message}
+}
+
+// the names and lines of the import declarations a file starts with, the file is only
+// scanned as far as they go. The parser reports any that are malformed
+fn imports(mut scanner: Scanner) -> Vec<(String, i32)> {
+    let mut imports = Vec::new();
+    loop {
+        match (scanner.next(), scanner.next(), scanner.next()) {
+            (Some(Ok(import)), Some(Ok(name)), Some(Ok(semicolon))) if import.token_type == TokenType::Import
+                && name.token_type == TokenType::Identifier && semicolon.token_type == TokenType::Semicolon => {
+                imports.push((String::from(name.lexeme), name.line));
+            }
+            _ => break,
+        }
+    }
+    imports
+}
+
+// every name qualify gives the tokens of file: module::name for each use of a module the
+// file imports, and for what the file declares if it is a module. The tokens borrow these,
+// so they are kept for as long as the tokens are. Only a module can declare its names, so
+// declaring one in another file is an error
+pub fn qualified_names(file: &SourceFile, tokens: &[Token]) -> Result<HashSet<String>, ImportError> {
+    let mut names = HashSet::new();
+    for (index, triple) in tokens.windows(3).enumerate() {
+        if let [module, colons, name] = triple {
+            if module.token_type == TokenType::Identifier && colons.token_type == TokenType::ColonColon
+                && name.token_type == TokenType::Identifier && file.imports.iter().any(|import| import == module.lexeme) {
+                let declared = index > 0
+                    && matches!(tokens[index - 1].token_type, TokenType::Fn | TokenType::Struct | TokenType::Enum);
+                if declared {
+                    return Err(ImportError {message: file.in_file(&format!(
+                        "Can't declare {}::{} at line {}, only module {} can declare names in it",
+                        module.lexeme, name.lexeme, name.line, module.lexeme
+                    ))});
+                }
+                names.insert(format!("{}::{}", module.lexeme, name.lexeme));
+            }
+        }
+    }
+    if let Some(module) = &file.module {
+        names.extend(declared_items(tokens).iter().map(|item| format!("{}::{}", module, item)));
+    }
+    Ok(names)
+}
+
+// the tokens of file with the names its imports and module declaration call for
+pub fn qualify<'t>(file: &SourceFile, tokens: Vec<Token<'t>>, names: &'t HashSet<String>) -> Vec<Token<'t>> {
+    let mut tokens = qualify_imported(tokens, &file.imports, names);
+    if let Some(module) = &file.module {
+        qualify_items(&mut tokens, module, names);
+    }
+    tokens
+}
+
+fn qualified_name<'n>(names: &'n HashSet<String>, module: &str, name: &str) -> &'n str {
+    names.get(&format!("{}::{}", module, name)).expect("qualified_names should have made every qualified name")
+}
+
+// module :: name for the modules a file imports becomes the single name module::name
+fn qualify_imported<'t>(tokens: Vec<Token<'t>>, modules: &[String], names: &'t HashSet<String>) -> Vec<Token<'t>> {
+    let mut qualified: Vec<Token> = Vec::new();
+    for token in tokens {
+        let length = qualified.len();
+        if length >= 2 && token.token_type == TokenType::Identifier
+            && qualified[length - 1].token_type == TokenType::ColonColon
+            && qualified[length - 2].token_type == TokenType::Identifier
+            && modules.iter().any(|module| module == qualified[length - 2].lexeme) {
+            qualified.truncate(length - 1);
+            let module = qualified.last_mut().unwrap();
+            module.lexeme = qualified_name(names, module.lexeme, token.lexeme);
+            module.span = module.span.to(token.span);
+        } else {
+            qualified.push(token);
+        }
+    }
+    qualified
+}
+
+// the names of the functions, structs and enums declared at the top level
+fn declared_items<'t>(tokens: &[Token<'t>]) -> Vec<&'t str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    for pair in tokens.windows(2) {
+        match pair[0].token_type {
+            TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => depth -= 1,
+            TokenType::Fn | TokenType::Struct | TokenType::Enum if depth == 0
+                && pair[1].token_type == TokenType::Identifier => items.push(pair[1].lexeme),
+            _ => {}
+        }
+    }
+    items
+}
+
+// rename the functions, structs and enums a module declares to module::name. Every use of the
+// name is renamed, so a local variable with the same name still shadows the function like it
+// did. Fields and variants are looked up by their own name and stay as they are
+fn qualify_items<'t>(tokens: &mut [Token<'t>], module: &str, names: &'t HashSet<String>) {
+    let items = declared_items(tokens);
+
+    // the brackets the token is inside of, innermost last, and whether they are an enum's body.
+    // Lambda parameters are between |s
+    let mut brackets: Vec<(TokenType, bool)> = Vec::new();
+    let mut declaring = None;
+    for index in 0..tokens.len() {
+        let previous = if index > 0 { Some(tokens[index - 1].token_type) } else { None };
+        let next = tokens.get(index + 1).map(|token| token.token_type);
+        match tokens[index].token_type {
+            TokenType::LeftParen | TokenType::LeftBracket => brackets.push((tokens[index].token_type, false)),
+            TokenType::Fn | TokenType::Struct | TokenType::Enum if brackets.is_empty() => {
+                declaring = Some(tokens[index].token_type);
+            }
+            TokenType::LeftBrace => {
+                let enum_body = brackets.is_empty() && declaring == Some(TokenType::Enum);
+                brackets.push((TokenType::LeftBrace, enum_body));
+            }
+            TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                brackets.pop();
+            }
+            TokenType::Pipe => match brackets.last() {
+                Some((TokenType::Pipe, _)) => {
+                    brackets.pop();
+                }
+                _ => brackets.push((TokenType::Pipe, false)),
+            },
+            TokenType::Identifier if items.contains(&tokens[index].lexeme) => {
+                let in_braces = matches!(brackets.last(), Some((TokenType::LeftBrace, _)));
+                let enum_body = matches!(brackets.last(), Some((_, true)));
+                let starts_entry = matches!(previous, Some(TokenType::LeftBrace) | Some(TokenType::Comma));
+                let member = match previous {
+                    Some(TokenType::Dot) | Some(TokenType::ColonColon) | Some(TokenType::Import) => true,
+                    // a field of a struct declaration or literal, or a variant of an enum
+                    _ => starts_entry && (enum_body || (in_braces && next == Some(TokenType::Colon))),
+                };
+                if !member {
+                    tokens[index].lexeme = qualified_name(names, module, tokens[index].lexeme);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// one program with the declarations of every file, the statements of the program come from
+// the file it starts in. programs are the files parsed, in the same order
+pub fn combine<'t>(files: &[SourceFile], programs: Vec<Program<'t>>) -> Result<Program<'t>, ImportError> {
+    let mut combined = Program {functions: Vec::new(), structs: Vec::new(), enums: Vec::new(), main: None};
+    for (file, program) in files.iter().zip(programs) {
+        let Program {functions, structs, enums, main} = program;
+        match (&file.module, main) {
+            (Some(module), Some(_)) => {
+                return Err(ImportError {message: file.in_file(
+                    &format!("Module {} can only declare functions, structs and enums, it has statements at the top level", module)
+                )});
+            }
+            (Some(_), None) => {}
+            (None, main) => combined.main = main,
+        }
+        combined.functions.extend(functions);
+        combined.structs.extend(structs);
+        combined.enums.extend(enums);
+    }
+    Ok(combined)
+}
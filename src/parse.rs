@@ -1,147 +1,550 @@
-use std::{fmt::format, iter::Peekable, slice::Iter};
+use std::{iter::Peekable, slice::Iter};
 
-use crate::{ast::{Binary, BinaryOp, Expr, Grouping, If, Literal, LiteralType, Unary, UnaryOp}, token::{Token, TokenType}};
+use crate::{ast::{Arm, Array, Assign, Binary, BinaryOp, Block, Call, Cast, EnumDecl, Expr, Field, FieldDecl, Function, Grouping, If, Index, Lambda, LambdaParam, Let, Literal, LiteralType, Logical, LogicalOp, Match, Param, Path, Pattern, Program, Stmt, StructDecl, StructLiteral, Unary, UnaryOp, Variable, VariantDecl, VariantPattern, While}, span::Span, token::{Token, TokenType}, typechecker::{IntType, TypeKind}};
 
 
 pub enum ParseResult<'t> {
-    AST(Expr<'t>),
-    Error(ParseError)
+    Ast(Program<'t>),
+    // every error found, parsing carries on with the next statement after one
+    Error(Vec<ParseError>)
 }
 
 pub struct ParseError {
     pub message: String,
+    // the token the error is about, or where the file ends when it ran out of tokens
+    pub span: Span,
 }
 
-// program => expression ;
-pub fn parse(tokens: &Vec<Token>) -> ParseResult {
-    let mut tokens = tokens.iter().peekable();
-    match expression(&mut tokens) {
-        Ok(expr) => {
-            match tokens.peek() {
-                Some(token) => {
-                    // finished parsing, but there's still some tokens left
-                    ParseResult::Error(ParseError {message: format!("Finished parsing, but some tokens remain: {:?}", token)})
+// errors about running out of tokens are made with this span, parse replaces it with where the last token ends
+const END_OF_TOKENS: Span = Span {file: usize::MAX, start_byte: 0, end_byte: 0};
+
+fn end_of_tokens(message: String) -> ParseError {
+    ParseError {message, span: END_OF_TOKENS}
+}
+
+// program => import* ( function | struct_decl | enum_decl )* block_body ;
+pub fn parse<'t>(all_tokens: &'t [Token<'t>]) -> ParseResult<'t> {
+    let end = all_tokens.last().map_or(Span::default(), |token| token.span.end_point());
+    let mut tokens = all_tokens.iter().peekable();
+    let mut errors = Vec::new();
+    loop {
+        let restart = all_tokens.len() - tokens.len();
+        let error = match program(&mut tokens) {
+            Ok(program) => {
+                match tokens.peek() {
+                    Some(token) => {
+                        // finished parsing, but there's still some tokens left
+                        ParseError {message: format!("Finished parsing, but some tokens remain: {:?}", token), span: token.span}
+                    }
+                    None if errors.is_empty() => {
+                        return ParseResult::Ast(program);
+                    }
+                    None => break,
                 }
-                None => {
-                    ParseResult::AST(expr)
+            }
+            Err(err) if err.span == END_OF_TOKENS => ParseError {span: end, ..err},
+            Err(err) => err,
+        };
+        errors.push(error);
+        synchronize(all_tokens, &mut tokens, restart);
+    }
+    ParseResult::Error(errors)
+}
+
+// skip what's left of the statement or declaration an error is in: up to and including the
+// next ; outside braces or the } closing the braces the error is in, or up to the next keyword
+// that starts one outside braces. A keyword at restart, where parsing last started, is skipped
+// too so parsing always moves on
+fn synchronize<'t>(all_tokens: &'t [Token<'t>], tokens: &mut Peekable<Iter<'t, Token<'t>>>, restart: usize) {
+    let position = all_tokens.len() - tokens.len();
+    let mut depth = all_tokens[..position].iter().fold(0, brace_depth);
+    while let Some(token) = tokens.peek() {
+        let starts_statement = matches!(token.token_type,
+            TokenType::Fn | TokenType::Struct | TokenType::Enum | TokenType::Let | TokenType::While | TokenType::Import);
+        if depth == 0 && starts_statement && all_tokens.len() - tokens.len() > restart {
+            return;
+        }
+        let token = tokens.next().unwrap();
+        match token.token_type {
+            TokenType::Semicolon if depth == 0 => return,
+            // the block is over unless an else follows it, a ; after it goes with it
+            TokenType::RightBrace if depth == 1 && !check(tokens, TokenType::Else) => {
+                if check(tokens, TokenType::Semicolon) {
+                    tokens.next();
                 }
+                return;
             }
+            _ => {}
         }
-        Err(err) => {
-            ParseResult::Error(err)
+        depth = brace_depth(depth, token);
+    }
+}
+
+// how many braces are open after token, a } without a { is ignored
+fn brace_depth(depth: usize, token: &Token) -> usize {
+    match token.token_type {
+        TokenType::LeftBrace => depth + 1,
+        TokenType::RightBrace => depth.saturating_sub(1),
+        _ => depth,
+    }
+}
+
+fn program<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Program<'t>, ParseError> {
+    let mut functions = Vec::new();
+    let mut structs = Vec::new();
+    let mut enums = Vec::new();
+    // the files imported are loaded before parsing, see modules
+    while check(tokens, TokenType::Import) {
+        import(tokens)?;
+    }
+    while let Some(token) = tokens.peek() {
+        match token.token_type {
+            TokenType::Fn => functions.push(function(tokens)?),
+            TokenType::Struct => structs.push(struct_decl(tokens)?),
+            TokenType::Enum => enums.push(enum_decl(tokens)?),
+            _ => break,
         }
     }
+    // the statements after the functions form the body of main, like a block without braces
+    let body = block_body(tokens)?;
+    let main = if body.statements.is_empty() && body.expr.is_none() {
+        None
+    } else {
+        Some(Expr::Block(body))
+    };
+    Ok(Program {functions, structs, enums, main})
 }
 
-// expression -> equality | if_expr
-fn expression<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
-    match tokens.peek() {
-        Some(token) => {
-            match token.token_type {
-                TokenType::If => {
-                    if_expr(tokens)
+// import -> "import" IDENTIFIER ";"
+fn import<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<(), ParseError> {
+    consume(tokens, TokenType::Import, "import")?;
+    consume(tokens, TokenType::Identifier, "module name")?;
+    consume(tokens, TokenType::Semicolon, ";")?;
+    Ok(())
+}
+
+// enum_decl -> "enum" IDENTIFIER type_params? "{" variant ( "," variant )* ","? "}"
+// variant -> IDENTIFIER ( "(" type ( "," type )* ")" )?
+fn enum_decl<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<EnumDecl<'t>, ParseError> {
+    tokens.next(); // consume "enum"
+    let name = consume(tokens, TokenType::Identifier, "enum name")?;
+    let type_params = type_params(tokens)?;
+    consume(tokens, TokenType::LeftBrace, "{")?;
+
+    let mut variants = Vec::new();
+    while !check(tokens, TokenType::RightBrace) {
+        let name = consume(tokens, TokenType::Identifier, "variant name")?;
+        let mut payload = Vec::new();
+        if check(tokens, TokenType::LeftParen) {
+            tokens.next();
+            loop {
+                payload.push(parse_type(tokens)?);
+                if !check(tokens, TokenType::Comma) {
+                    break;
                 }
-                _ => {
-                    equality(tokens)
+                tokens.next();
+            }
+            consume(tokens, TokenType::RightParen, ")")?;
+        }
+        variants.push(VariantDecl {name, payload});
+        if !check(tokens, TokenType::Comma) {
+            break;
+        }
+        tokens.next();
+    }
+    consume(tokens, TokenType::RightBrace, "}")?;
+    if variants.is_empty() {
+        return Err(ParseError{message: format!("Enums need at least one variant: {:?}", name), span: name.span});
+    }
+    Ok(EnumDecl {name, type_params, variants, recursive: false})
+}
+
+// struct_decl -> "struct" IDENTIFIER type_params? "{" field_decl ( "," field_decl )* ","? "}"
+// field_decl -> IDENTIFIER ":" type
+fn struct_decl<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<StructDecl<'t>, ParseError> {
+    tokens.next(); // consume "struct"
+    let name = consume(tokens, TokenType::Identifier, "struct name")?;
+    let type_params = type_params(tokens)?;
+    consume(tokens, TokenType::LeftBrace, "{")?;
+
+    let mut fields = Vec::new();
+    while !check(tokens, TokenType::RightBrace) {
+        let name = consume(tokens, TokenType::Identifier, "field name")?;
+        consume(tokens, TokenType::Colon, ":")?;
+        let type_kind = parse_type(tokens)?;
+        fields.push(FieldDecl {name, type_kind});
+        if !check(tokens, TokenType::Comma) {
+            break;
+        }
+        tokens.next();
+    }
+    consume(tokens, TokenType::RightBrace, "}")?;
+    // `Name {}` would read as a variable followed by an empty block
+    if fields.is_empty() {
+        return Err(ParseError{message: format!("Structs need at least one field: {:?}", name), span: name.span});
+    }
+    Ok(StructDecl {name, type_params, fields, recursive: false})
+}
+
+// function -> "fn" IDENTIFIER type_params? "(" parameters? ")" "->" type block
+// parameters -> IDENTIFIER ":" type ( "," IDENTIFIER ":" type )*
+fn function<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Function<'t>, ParseError> {
+    tokens.next(); // consume "fn"
+    let name = consume(tokens, TokenType::Identifier, "function name")?;
+    let type_params = type_params(tokens)?;
+    consume(tokens, TokenType::LeftParen, "(")?;
+
+    let mut params = Vec::new();
+    if !check(tokens, TokenType::RightParen) {
+        loop {
+            let name = consume(tokens, TokenType::Identifier, "parameter name")?;
+            consume(tokens, TokenType::Colon, ":")?;
+            let type_kind = parse_type(tokens)?;
+            params.push(Param {name, type_kind});
+            if !check(tokens, TokenType::Comma) {
+                break;
+            }
+            tokens.next();
+        }
+    }
+    consume(tokens, TokenType::RightParen, ")")?;
+
+    consume(tokens, TokenType::Arrow, "->")?;
+    let return_type = parse_type(tokens)?;
+
+    let body = block(tokens)?;
+
+    Ok(Function {name, type_params, params, return_type, body})
+}
+
+// type_params -> "<" IDENTIFIER ( "," IDENTIFIER )* ">"
+fn type_params<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Vec<&'t Token<'t>>, ParseError> {
+    let mut type_params = Vec::new();
+    if !check(tokens, TokenType::Less) {
+        return Ok(type_params);
+    }
+    tokens.next();
+    loop {
+        type_params.push(consume(tokens, TokenType::Identifier, "type parameter name")?);
+        if !check(tokens, TokenType::Comma) {
+            break;
+        }
+        tokens.next();
+    }
+    consume(tokens, TokenType::Greater, ">")?;
+    Ok(type_params)
+}
+
+// type -> "int" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "float" | "bool" | "str"
+//       | "[" type ";" NUMBER "]" | "fn" "(" ( type ( "," type )* )? ")" "->" type
+//       | IDENTIFIER ( "<" type ( "," type )* ">" )?
+fn parse_type<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<TypeKind, ParseError> {
+    if check(tokens, TokenType::Fn) {
+        tokens.next();
+        consume(tokens, TokenType::LeftParen, "(")?;
+        let mut params = Vec::new();
+        if !check(tokens, TokenType::RightParen) {
+            loop {
+                params.push(parse_type(tokens)?);
+                if !check(tokens, TokenType::Comma) {
+                    break;
                 }
+                tokens.next();
             }
         }
-        None => {Err(ParseError{message: String::from("Reached EOF while parsing")})}
+        consume(tokens, TokenType::RightParen, ")")?;
+        consume(tokens, TokenType::Arrow, "->")?;
+        let return_type = parse_type(tokens)?;
+        return Ok(TypeKind::Function(params, Box::new(return_type)));
+    }
+    if check(tokens, TokenType::LeftBracket) {
+        tokens.next();
+        let element_type = parse_type(tokens)?;
+        consume(tokens, TokenType::Semicolon, ";")?;
+        let length_token = consume(tokens, TokenType::IntLiteral, "array length")?;
+        let length = match length_token.int {
+            Some((length, None)) if length > 0 => length as usize,
+            _ => return Err(ParseError{message: format!("Array length must be a positive integer: {:?}", length_token), span: length_token.span}),
+        };
+        consume(tokens, TokenType::RightBracket, "]")?;
+        return Ok(TypeKind::Array(Box::new(element_type), length));
+    }
+    let token = consume(tokens, TokenType::Identifier, "type")?;
+    if let Some(int_type) = IntType::from_name(token.lexeme) {
+        return Ok(TypeKind::Int(int_type));
+    }
+    match token.lexeme {
+        "int" => Ok(TypeKind::Int(IntType::I64)),
+        "float" => Ok(TypeKind::Float),
+        "bool" => Ok(TypeKind::Bool),
+        "str" => Ok(TypeKind::Str),
+        // the typechecker makes sure a struct or enum with this name exists, or that it
+        // names a type parameter
+        name => {
+            // module::name is one name once the module is imported
+            if check(tokens, TokenType::ColonColon) {
+                return Err(ParseError{message: format!("Module {} at line {}, column {} is not imported", name, token.line, token.column), span: token.span});
+            }
+            let mut arguments = Vec::new();
+            if check(tokens, TokenType::Less) {
+                tokens.next();
+                loop {
+                    arguments.push(parse_type(tokens)?);
+                    if !check(tokens, TokenType::Comma) {
+                        break;
+                    }
+                    tokens.next();
+                }
+                consume(tokens, TokenType::Greater, ">")?;
+            }
+            Ok(TypeKind::Named(String::from(name), arguments))
+        }
     }
 }
 
-// if_expr -> "if" expression "{" expression "}" ("else {" expression "}")?
-fn if_expr<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
-    let token = tokens.next().unwrap(); // consume "if"
-    let condition = Box::new(expression(tokens)?);
-    
+// true if the next token has the given type, without consuming it
+fn check<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>, token_type: TokenType) -> bool {
+    match tokens.peek() {
+        Some(token) => token.token_type == token_type,
+        None => false,
+    }
+}
+
+// consume the next token if it has the expected type, otherwise report what was found instead.
+// Error tokens before it are skipped
+fn consume<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>, token_type: TokenType, expected: &str) -> Result<&'t Token<'t>, ParseError> {
+    while check(tokens, TokenType::Error) {
+        tokens.next();
+    }
     match tokens.peek() {
         Some(token) => {
-            match token.token_type {
-                TokenType::LeftBrace => {
-                    tokens.next();
-                }
-                _ => {
-                    return Err(ParseError{message: format!("Expect {{ got: {:?}", token)});
-                }
+            if token.token_type == token_type {
+                Ok(tokens.next().unwrap())
+            } else {
+                Err(ParseError{message: format!("Expect {} got: {:?}", expected, token), span: token.span})
             }
         }
         None => {
-            return Err(ParseError{message: format!("Expect {{ reached EOF")});
+            Err(end_of_tokens(format!("Expect {} reached EOF", expected)))
         }
-    };
+    }
+}
+
+// the span of the tokens taken from tokens since start was cloned from it. They come after
+// the span before, where the span is empty if none were taken
+fn consumed_span<'t>(before: Span, start: &Peekable<Iter<'t, Token<'t>>>, tokens: &Peekable<Iter<'t, Token<'t>>>) -> Span {
+    let count = start.len() - tokens.len();
+    let mut consumed = start.clone();
+    match consumed.peek() {
+        Some(first) if count > 0 => first.span.to(consumed.nth(count - 1).unwrap().span),
+        _ => before.end_point(),
+    }
+}
+
+// block -> "{" block_body "}"
+fn block<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let open = consume(tokens, TokenType::LeftBrace, "{")?;
+    let body = block_body(tokens)?;
+    let close = consume(tokens, TokenType::RightBrace, "}")?;
+    Ok(Expr::Block(Block {span: open.span.to(close.span), ..body}))
+}
 
-    let then_branch = Box::new(expression(tokens)?);
+// block_body -> statement* expression?
+// statement -> let_statement | expression ";" | if_expr | while_expr | block
+// if, while and blocks don't need a ";" to be used as statements, like in Rust
+fn block_body<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Block<'t>, ParseError> {
+    let mut statements = Vec::new();
+    let mut expr = None;
+    // an empty body is an empty span where it would start
+    let mut span = tokens.peek().map(|token| token.span.start_point()).unwrap_or_default();
+    let start = span;
+    while !at_block_end(tokens) {
+        if check(tokens, TokenType::Let) {
+            let statement = let_statement(tokens)?;
+            span = start.to(statement.span);
+            statements.push(Stmt::Let(statement));
+            continue;
+        }
+
+        let statement = expression(tokens)?;
+        span = start.to(statement.span());
+        if check(tokens, TokenType::Semicolon) {
+            span = start.to(tokens.next().unwrap().span);
+            statements.push(Stmt::Expr(statement));
+        } else if at_block_end(tokens) {
+            expr = Some(Box::new(statement));
+        } else if is_block_like(&statement) {
+            statements.push(Stmt::Expr(statement));
+        } else {
+            return Err(match tokens.peek() {
+                Some(token) => ParseError{message: format!("Expect ; got: {:?}", token), span: token.span},
+                None => end_of_tokens(String::from("Expect ; reached EOF")),
+            });
+        }
+    }
+    Ok(Block {statements, expr, span, type_kind: None})
+}
 
+fn at_block_end<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> bool {
+    match tokens.peek() {
+        Some(token) => token.token_type == TokenType::RightBrace,
+        None => true,
+    }
+}
+
+fn is_block_like(expr: &Expr) -> bool {
+    matches!(expr, Expr::If(_) | Expr::While(_) | Expr::Block(_) | Expr::Match(_))
+}
+
+// expression -> if_expr | while_expr | match_expr | assignment
+fn expression<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
     match tokens.peek() {
         Some(token) => {
             match token.token_type {
-                TokenType::RightBrace => {
-                    tokens.next();
+                TokenType::If => {
+                    if_expr(tokens)
+                }
+                TokenType::While => {
+                    while_expr(tokens)
+                }
+                TokenType::Match => {
+                    match_expr(tokens)
                 }
                 _ => {
-                    return Err(ParseError{message: format!("Expect }} got: {:?}", token)});
+                    assignment(tokens)
                 }
             }
         }
-        None => {
-            return Err(ParseError{message: format!("Expect }} reached EOF")});
-        }
+        None => {Err(end_of_tokens(String::from("Reached EOF while parsing")))}
+    }
+}
+
+// let_statement -> "let" "mut"? IDENTIFIER ( ":" type )? "=" expression ";"
+fn let_statement<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Let<'t>, ParseError> {
+    let token = tokens.next().unwrap(); // consume "let"
+    let mutable = check(tokens, TokenType::Mut);
+    if mutable {
+        tokens.next();
+    }
+    let name = consume(tokens, TokenType::Identifier, "identifier")?;
+    let type_kind = if check(tokens, TokenType::Colon) {
+        tokens.next();
+        Some(parse_type(tokens)?)
+    } else {
+        None
     };
+    consume(tokens, TokenType::Equal, "=")?;
+    let initializer = expression(tokens)?;
+    let semicolon = consume(tokens, TokenType::Semicolon, ";")?;
+
+    Ok(Let{name, mutable, type_kind, initializer, quantified: Vec::new(), span: token.span.to(semicolon.span)})
+}
+
+// if_expr -> "if" expression block ( "else" ( if_expr | block ) )?
+fn if_expr<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let token = tokens.next().unwrap(); // consume "if"
+    let condition = Box::new(expression(tokens)?);
+    let then_branch = Box::new(block(tokens)?);
 
     let else_branch = match tokens.peek() {
-        Some(token) => {
-            match token.token_type {
-                TokenType::Else => {
-                    tokens.next(); // consume "else"
-                    // match {
-                    match tokens.peek() {
-                        Some(token) => {
-                            match token.token_type {
-                                TokenType::LeftBrace => {
-                                    tokens.next();
-                                }
-                                _ => {
-                                    return Err(ParseError{message: format!("Expect {{ got: {:?}", token)});
-                                }
-                            }
-                        }
-                        None => {
-                            return Err(ParseError{message: format!("Expect {{ reached EOF")});
-                        }
-                    };
-                
-                    let else_branch = expression(tokens)?;
-                
-                    // match }
-                    match tokens.peek() {
-                        Some(token) => {
-                            match token.token_type {
-                                TokenType::RightBrace => {
-                                    tokens.next();
-                                }
-                                _ => {
-                                    return Err(ParseError{message: format!("Expect }} got: {:?}", token)});
-                                }
-                            }
-                        }
-                        None => {
-                            return Err(ParseError{message: format!("Expect }} reached EOF")});
-                        }
-                    };
-                    Some(Box::new(else_branch))
-                }
-                _ => None
+        Some(token) if token.token_type == TokenType::Else => {
+            tokens.next(); // consume "else"
+            if check(tokens, TokenType::If) {
+                Some(Box::new(if_expr(tokens)?))
+            } else {
+                Some(Box::new(block(tokens)?))
             }
         }
-        None => None
-    };    
+        _ => None
+    };
+
+    let end = else_branch.as_ref().unwrap_or(&then_branch).span();
+    Ok(Expr::If(If{token, condition, then_branch, else_branch, span: token.span.to(end), type_kind: None}))
+}
+
+// while_expr -> "while" expression block
+fn while_expr<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let token = tokens.next().unwrap(); // consume "while"
+    let condition = Box::new(expression(tokens)?);
+    let body = Box::new(block(tokens)?);
+
+    let span = token.span.to(body.span());
+    Ok(Expr::While(While{token, condition, body, span, type_kind: None}))
+}
+
+// assignment -> place "=" expression | logic_or
+// place -> IDENTIFIER ( "[" expression "]" | "." IDENTIFIER )*
+fn assignment<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let expr = logic_or(tokens)?;
+    if !check(tokens, TokenType::Equal) {
+        return Ok(expr);
+    }
+    let token = tokens.next().unwrap();
+    let value = Box::new(expression(tokens)?);
+    if is_place(&expr) {
+        let span = expr.span().to(value.span());
+        Ok(Expr::Assign(Assign {token, target: Box::new(expr), value, span, type_kind: None}))
+    } else {
+        Err(ParseError{message: format!("Invalid assignment target before {:?}", token), span: expr.span()})
+    }
+}
 
-    Ok(Expr::If(If{token, condition, then_branch, else_branch, type_kind: None}))
+fn is_place(expr: &Expr) -> bool {
+    match expr {
+        Expr::Variable(_) => true,
+        Expr::Index(index) => is_place(index.array.as_ref()),
+        Expr::Field(field) => is_place(field.object.as_ref()),
+        _ => false,
+    }
+}
+
+// logic_or -> logic_and ( ( "||" | ERROR ) logic_and )*
+fn logic_or<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let mut expr = logic_and(tokens)?;
+    loop {
+        if check(tokens, TokenType::PipePipe) {
+            let token = tokens.next().unwrap();
+            let right = logic_and(tokens)?;
+            let span = expr.span().to(right.span());
+            expr = Expr::Logical(Logical {token, operation: LogicalOp::Or, left: Box::new(expr), right: Box::new(right), span, type_kind: None})
+        } else if check(tokens, TokenType::Error) {
+            // where an operator goes, like the & of 1 & 2. The scanner has reported it, the
+            // operands are parsed for the errors after it
+            let token = tokens.next().unwrap();
+            let mut span = expr.span().to(token.span);
+            if !at_expression_end(tokens) {
+                span = span.to(logic_and(tokens)?.span());
+            }
+            expr = Expr::Literal(Literal {token, literal_type: LiteralType::Error, span, type_kind: None});
+        } else {
+            return Ok(expr);
+        }
+    }
+}
+
+// whether the next token can't start an expression, but can come after one
+fn at_expression_end(tokens: &mut Peekable<Iter<Token>>) -> bool {
+    match tokens.peek() {
+        Some(token) => matches!(token.token_type, TokenType::Semicolon | TokenType::Comma
+            | TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace),
+        None => true,
+    }
+}
+
+// logic_and -> equality ( "&&" equality )*
+fn logic_and<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let mut expr = equality(tokens)?;
+    while check(tokens, TokenType::AmpAmp) {
+        let token = tokens.next().unwrap();
+        let right = equality(tokens)?;
+        let span = expr.span().to(right.span());
+        expr = Expr::Logical(Logical {token, operation: LogicalOp::And, left: Box::new(expr), right: Box::new(right), span, type_kind: None})
+    }
+    Ok(expr)
 }
 
 // equality -> comparison ( ( "!=" | "==" ) comparison )*
-fn equality<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
+fn equality<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
     let mut expr = comparison(tokens)?;
     loop {
         let op_token;
@@ -169,12 +572,13 @@ fn equality<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, Pars
             }
         };
         let right = comparison(tokens)?;
-        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), type_kind: None})
+        let span = expr.span().to(right.span());
+        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), span, type_kind: None})
     }
     Ok(expr)
 }
 // comparison -> term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
-fn comparison<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
+fn comparison<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
     let mut expr = term(tokens)?;
     loop {
         let op_token;
@@ -212,13 +616,14 @@ fn comparison<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, Pa
             }
         };
         let right = term(tokens)?;
-        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), type_kind: None})
+        let span = expr.span().to(right.span());
+        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), span, type_kind: None})
     }
     Ok(expr)
 }
 
-// term => factor (("+"|"-") factor)* ;
-fn term<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
+// term => factor (("+" | "-" | "+%" | "-%") factor)* ;
+fn term<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
     let mut expr = factor(tokens)?;
     loop {
         let op_token;
@@ -236,6 +641,16 @@ fn term<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseErr
                         operation = BinaryOp::Minus;
                         tokens.next();
                     }
+                    TokenType::PlusPercent => {
+                        op_token = *token;
+                        operation = BinaryOp::WrappingAdd;
+                        tokens.next();
+                    }
+                    TokenType::MinusPercent => {
+                        op_token = *token;
+                        operation = BinaryOp::WrappingMinus;
+                        tokens.next();
+                    }
                     _ => {
                         break;
                     }
@@ -246,14 +661,15 @@ fn term<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseErr
             }
         };
         let right = factor(tokens)?;
-        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), type_kind: None})
+        let span = expr.span().to(right.span());
+        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), span, type_kind: None})
     }
     Ok(expr)
 }
 
-// factor => unary (("*") unary)*
-fn factor<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
-    let mut expr = unary(tokens)?;
+// factor => cast (("*" | "/" | "%" | "*%") cast)*
+fn factor<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let mut expr = cast(tokens)?;
     loop {
         let op_token;
         let operation;
@@ -265,6 +681,21 @@ fn factor<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseE
                         operation = BinaryOp::Times;
                         tokens.next();
                     }
+                    TokenType::Slash => {
+                        op_token = *token;
+                        operation = BinaryOp::Divide;
+                        tokens.next();
+                    }
+                    TokenType::Percent => {
+                        op_token = *token;
+                        operation = BinaryOp::Modulo;
+                        tokens.next();
+                    }
+                    TokenType::StarPercent => {
+                        op_token = *token;
+                        operation = BinaryOp::WrappingTimes;
+                        tokens.next();
+                    }
                     _ => {
                         break;
                     }
@@ -274,75 +705,307 @@ fn factor<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseE
                 break;
             }
         };
-        let right = unary(tokens)?;
-        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), type_kind: None})
+        let right = cast(tokens)?;
+        let span = expr.span().to(right.span());
+        expr = Expr::Binary(Binary {token: op_token, operation, left: Box::new(expr), right: Box::new(right), span, type_kind: None})
+    }
+    Ok(expr)
+}
+
+// cast -> unary ( "as" type )*
+fn cast<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let mut expr = unary(tokens)?;
+    while check(tokens, TokenType::As) {
+        let token = tokens.next().unwrap();
+        // types don't keep their tokens, so the cast ends at the last one parse_type took
+        let type_start = tokens.clone();
+        let target = parse_type(tokens)?;
+        let span = expr.span().to(consumed_span(token.span, &type_start, tokens));
+        expr = Expr::Cast(Cast {token, expr: Box::new(expr), target, span, type_kind: None});
     }
     Ok(expr)
 }
 
-// unary -> ( "-" ) unary | primary
-fn unary<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
+// unary -> ( "-" | "-%" | "!" ) unary | call
+fn unary<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
     match tokens.peek() {
         Some(token) => {
-            let op_token;
-            let operation;
             match token.token_type {
                 TokenType::Minus => {
-                    op_token = *token;
-                    operation = UnaryOp::Minus;
+                    let op_token = *token;
+                    let operation = UnaryOp::Minus;
+                    tokens.next();
+                    let right = unary(tokens)?;
+                    let span = op_token.span.to(right.span());
+                    Ok(Expr::Unary(Unary {token: op_token, operation, right: Box::new(right), span, type_kind: None}))
+                }
+                TokenType::MinusPercent => {
+                    let op_token = *token;
+                    let operation = UnaryOp::WrappingMinus;
                     tokens.next();
                     let right = unary(tokens)?;
-                    Ok(Expr::Unary(Unary {token: op_token, operation, right: Box::new(right), type_kind: None}))
+                    let span = op_token.span.to(right.span());
+                    Ok(Expr::Unary(Unary {token: op_token, operation, right: Box::new(right), span, type_kind: None}))
+                }
+                TokenType::Bang => {
+                    let op_token = *token;
+                    let operation = UnaryOp::Not;
+                    tokens.next();
+                    let right = unary(tokens)?;
+                    let span = op_token.span.to(right.span());
+                    Ok(Expr::Unary(Unary {token: op_token, operation, right: Box::new(right), span, type_kind: None}))
                 }
                 _ => {
-                    primary(tokens)
+                    call(tokens)
                 }
             }
         }
         None => {
-            Err(ParseError{message: String::from("Reached EOF while parsing")})
+            Err(end_of_tokens(String::from("Reached EOF while parsing")))
         }
     }
 }
 
-// primary => NUMBER | "(" expression ")";
-fn primary<'t>(tokens: &mut Peekable<Iter<'t, Token>>) -> Result<Expr<'t>, ParseError> {
+// call -> primary ( "(" arguments? ")" | "[" expression "]" | "." IDENTIFIER )*
+// arguments -> expression ( "," expression )*
+fn call<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let mut expr = primary(tokens)?;
+    loop {
+        if check(tokens, TokenType::LeftParen) {
+            let token = tokens.next().unwrap();
+            let mut arguments = Vec::new();
+            if !check(tokens, TokenType::RightParen) {
+                loop {
+                    arguments.push(expression(tokens)?);
+                    if !check(tokens, TokenType::Comma) {
+                        break;
+                    }
+                    tokens.next();
+                }
+            }
+            let close = consume(tokens, TokenType::RightParen, ")")?;
+            let span = expr.span().to(close.span);
+            expr = Expr::Call(Call {token, callee: Box::new(expr), arguments, span, type_kind: None});
+        } else if check(tokens, TokenType::LeftBracket) {
+            let token = tokens.next().unwrap();
+            let index = expression(tokens)?;
+            let close = consume(tokens, TokenType::RightBracket, "]")?;
+            let span = expr.span().to(close.span);
+            expr = Expr::Index(Index {token, array: Box::new(expr), index: Box::new(index), span, type_kind: None});
+        } else if check(tokens, TokenType::Dot) {
+            let token = tokens.next().unwrap();
+            let name = consume(tokens, TokenType::Identifier, "field name")?;
+            let span = expr.span().to(name.span);
+            expr = Expr::Field(Field {token, object: Box::new(expr), name, span, type_kind: None});
+        } else {
+            break;
+        }
+    }
+    Ok(expr)
+}
+
+// primary => NUMBER | FLOAT | STRING | "true" | "false" | ERROR | IDENTIFIER | "(" expression ")" | block | array | struct_literal | path | lambda ;
+fn primary<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
     match tokens.peek() {
         Some(token) => {
+            if let Some((value, suffix)) = token.int {
+                let token = *token;
+                tokens.next();
+                return Ok(Expr::Literal(Literal { token, literal_type: LiteralType::Int(value, suffix), span: token.span, type_kind: None}));
+            }
             match token.token_type {
-                TokenType::IntLiteral => {
+                TokenType::FloatLiteral => {
                     let token = *token;
                     tokens.next();
-                    Ok(Expr::Literal(Literal { token, literal_type: LiteralType::Int, type_kind: None}))
+                    Ok(Expr::Literal(Literal { token, literal_type: LiteralType::Float, span: token.span, type_kind: None}))
                 }
-                TokenType::LeftParen => {
+                TokenType::StringLiteral => {
+                    let token = *token;
                     tokens.next();
-                    let expr = expression(tokens)?;
-                    let node = Expr::Grouping(Grouping {expr: Box::new(expr), type_kind: None});
-                    match tokens.peek() {
-                        Some(token) => {
-                            match token.token_type {
-                                TokenType::RightParen => {
-                                    tokens.next();
-                                    Ok(node)
-                                }
-                                _ => {
-                                    Err(ParseError{message: format!("Expected ), found: {:?}", token)})
-                                }
-                            }
-                        }
-                        None => {
-                            Err(ParseError{message: format!("Reached EOF while parsing, expected )")})
-                        }
+                    Ok(Expr::Literal(Literal { token, literal_type: LiteralType::Str, span: token.span, type_kind: None}))
+                }
+                TokenType::True | TokenType::False => {
+                    let token = *token;
+                    tokens.next();
+                    Ok(Expr::Literal(Literal { token, literal_type: LiteralType::Bool, span: token.span, type_kind: None}))
+                }
+                // the scanner reported this already, parsing it like a literal finds the errors after it
+                TokenType::Error => {
+                    let token = *token;
+                    tokens.next();
+                    Ok(Expr::Literal(Literal { token, literal_type: LiteralType::Error, span: token.span, type_kind: None}))
+                }
+                TokenType::Identifier => {
+                    let token = *token;
+                    if is_struct_literal(tokens) {
+                        return struct_literal(tokens);
                     }
+                    tokens.next();
+                    if check(tokens, TokenType::ColonColon) {
+                        tokens.next();
+                        let name = consume(tokens, TokenType::Identifier, "name after ::")?;
+                        return Ok(Expr::Path(Path {qualifier: token, name, span: token.span.to(name.span), type_kind: None}));
+                    }
+                    Ok(Expr::Variable(Variable { token, instance: Vec::new(), span: token.span, type_kind: None }))
+                }
+                TokenType::LeftBrace => {
+                    block(tokens)
+                }
+                TokenType::LeftBracket => {
+                    array(tokens)
+                }
+                TokenType::Pipe | TokenType::PipePipe => {
+                    lambda(tokens)
+                }
+                TokenType::LeftParen => {
+                    let open = tokens.next().unwrap();
+                    let expr = expression(tokens)?;
+                    let close = consume(tokens, TokenType::RightParen, ")")?;
+                    Ok(Expr::Grouping(Grouping {expr: Box::new(expr), span: open.span.to(close.span), type_kind: None}))
                 }
                 _ => {
-                    Err(ParseError{message: format!("Expected primary expression, found: {:?}", token)})
+                    Err(ParseError{message: format!("Expected primary expression, found: {:?}", token), span: token.span})
                 }
             }
         }
         None => {
-            Err(ParseError{message: String::from("Reached EOF while parsing")})
+            Err(end_of_tokens(String::from("Reached EOF while parsing")))
+        }
+    }
+}
+
+// array -> "[" expression ( "," expression )* ","? "]"
+fn array<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let token = consume(tokens, TokenType::LeftBracket, "[")?;
+    let mut elements = Vec::new();
+    while !check(tokens, TokenType::RightBracket) {
+        elements.push(expression(tokens)?);
+        if !check(tokens, TokenType::Comma) {
+            break;
+        }
+        tokens.next();
+    }
+    let close = consume(tokens, TokenType::RightBracket, "]")?;
+    if elements.is_empty() {
+        return Err(ParseError{message: format!("Array literals need at least one element: {:?}", token), span: token.span.to(close.span)});
+    }
+    Ok(Expr::Array(Array {token, elements, span: token.span.to(close.span), type_kind: None}))
+}
+
+// lambda -> ( "||" | "|" lambda_param ( "," lambda_param )* "|" ) expression
+// lambda_param -> IDENTIFIER ( ":" type )?
+fn lambda<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let token = tokens.next().unwrap(); // consume "|" or "||"
+    let mut params = Vec::new();
+    if token.token_type == TokenType::Pipe {
+        loop {
+            let name = consume(tokens, TokenType::Identifier, "parameter name")?;
+            let type_kind = if check(tokens, TokenType::Colon) {
+                tokens.next();
+                Some(parse_type(tokens)?)
+            } else {
+                None
+            };
+            params.push(LambdaParam {name, type_kind});
+            if !check(tokens, TokenType::Comma) {
+                break;
+            }
+            tokens.next();
+        }
+        consume(tokens, TokenType::Pipe, "|")?;
+    }
+    let body = Box::new(expression(tokens)?);
+    let span = token.span.to(body.span());
+    Ok(Expr::Lambda(Lambda {token, params, body, captures: Vec::new(), span, type_kind: None}))
+}
+
+// IDENTIFIER "{" IDENTIFIER ":" starts a struct literal, anything else after a name
+// is a block, like the body of `if flag { x }`
+fn is_struct_literal<'t>(tokens: &Peekable<Iter<'t, Token<'t>>>) -> bool {
+    let mut lookahead = tokens.clone();
+    let expected = [TokenType::Identifier, TokenType::LeftBrace, TokenType::Identifier, TokenType::Colon];
+    expected.iter().all(|token_type| match lookahead.next() {
+        Some(token) => token.token_type == *token_type,
+        None => false,
+    })
+}
+
+// struct_literal -> IDENTIFIER "{" IDENTIFIER ":" expression ( "," IDENTIFIER ":" expression )* ","? "}"
+fn struct_literal<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let name = consume(tokens, TokenType::Identifier, "struct name")?;
+    consume(tokens, TokenType::LeftBrace, "{")?;
+    let mut fields = Vec::new();
+    while !check(tokens, TokenType::RightBrace) {
+        let field = consume(tokens, TokenType::Identifier, "field name")?;
+        consume(tokens, TokenType::Colon, ":")?;
+        fields.push((field, expression(tokens)?));
+        if !check(tokens, TokenType::Comma) {
+            break;
+        }
+        tokens.next();
+    }
+    let close = consume(tokens, TokenType::RightBrace, "}")?;
+    Ok(Expr::StructLiteral(StructLiteral {name, fields, span: name.span.to(close.span), type_kind: None}))
+}
+
+// match_expr -> "match" expression "{" arm ( "," arm )* ","? "}"
+// arm -> pattern "=>" expression
+fn match_expr<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Expr<'t>, ParseError> {
+    let token = tokens.next().unwrap(); // consume "match"
+    let scrutinee = Box::new(expression(tokens)?);
+    consume(tokens, TokenType::LeftBrace, "{")?;
+
+    let mut arms = Vec::new();
+    while !check(tokens, TokenType::RightBrace) {
+        let pattern = pattern(tokens)?;
+        consume(tokens, TokenType::FatArrow, "=>")?;
+        let body = expression(tokens)?;
+        // like statements, arms with a block-like body don't need a separator
+        let needs_comma = !is_block_like(&body);
+        arms.push(Arm {pattern, body});
+        if check(tokens, TokenType::Comma) {
+            tokens.next();
+        } else if needs_comma {
+            break;
         }
     }
-}
\ No newline at end of file
+    let close = consume(tokens, TokenType::RightBrace, "}")?;
+    if arms.is_empty() {
+        return Err(ParseError{message: format!("Match needs at least one arm: {:?}", token), span: token.span.to(close.span)});
+    }
+    Ok(Expr::Match(Match {token, scrutinee, arms, span: token.span.to(close.span), type_kind: None}))
+}
+
+// pattern -> "_" | IDENTIFIER | NUMBER | "true" | "false" | path ( "(" pattern ( "," pattern )* ")" )?
+fn pattern<'t>(tokens: &mut Peekable<Iter<'t, Token<'t>>>) -> Result<Pattern<'t>, ParseError> {
+    let token = match tokens.next() {
+        Some(token) => token,
+        None => return Err(end_of_tokens(String::from("Expect pattern reached EOF"))),
+    };
+    if let Some((value, suffix)) = token.int {
+        return Ok(Pattern::Literal(token, LiteralType::Int(value, suffix)));
+    }
+    match token.token_type {
+        TokenType::True | TokenType::False => Ok(Pattern::Literal(token, LiteralType::Bool)),
+        TokenType::Identifier if token.lexeme == "_" => Ok(Pattern::Wildcard(token)),
+        TokenType::Identifier if check(tokens, TokenType::ColonColon) => {
+            tokens.next();
+            let variant = consume(tokens, TokenType::Identifier, "variant name")?;
+            let mut fields = Vec::new();
+            if check(tokens, TokenType::LeftParen) {
+                tokens.next();
+                loop {
+                    fields.push(pattern(tokens)?);
+                    if !check(tokens, TokenType::Comma) {
+                        break;
+                    }
+                    tokens.next();
+                }
+                consume(tokens, TokenType::RightParen, ")")?;
+            }
+            Ok(Pattern::Variant(VariantPattern {enum_name: token, variant, fields}))
+        }
+        TokenType::Identifier => Ok(Pattern::Binding(token)),
+        _ => Err(ParseError{message: format!("Expect pattern got: {:?}", token), span: token.span}),
+    }
+}
@@ -0,0 +1,966 @@
+// Routines emitted alongside every program. They only use Linux syscalls, so
+// compiled programs don't depend on anything from libc.
+
+// exit statuses for runtime errors, distinct from each other so scripts can tell them apart
+pub const DIVIDE_BY_ZERO_STATUS: i32 = 200;
+pub const DIVIDE_OVERFLOW_STATUS: i32 = 201;
+pub const INDEX_OUT_OF_BOUNDS_STATUS: i32 = 202;
+pub const OUT_OF_MEMORY_STATUS: i32 = 203;
+pub const OVERFLOW_STATUS: i32 = 204;
+
+pub const DIVIDE_BY_ZERO: &str = "cheer_divide_by_zero";
+pub const DIVIDE_OVERFLOW: &str = "cheer_divide_overflow";
+// expects the index in %rdi and the array's length in %rsi
+pub const INDEX_OUT_OF_BOUNDS: &str = "cheer_index_out_of_bounds";
+// expects the operator as a str in %rdi and the line it is on in %rsi
+pub const OVERFLOW: &str = "cheer_overflow";
+
+// print the value in %rdi followed by a newline
+pub const PRINT_INT: &str = "cheer_print_int";
+// only needed for u64, narrower unsigned values are never negative as an int
+pub const PRINT_UINT: &str = "cheer_print_uint";
+// the bits of a float, with up to 15 significant digits. Very big and very small magnitudes
+// print in scientific notation like 1.5e-7
+pub const PRINT_FLOAT: &str = "cheer_print_float";
+pub const PRINT_BOOL: &str = "cheer_print_bool";
+pub const PRINT_STR: &str = "cheer_print_str";
+
+// compare the strings in %rdi and %rsi, leaving 1 in %rax when their contents match
+pub const STR_EQ: &str = "cheer_str_eq";
+
+// allocate %rdi bytes of heap memory, leaving its 16 byte aligned address in %rax. Bit i of %rsi
+// is set when word i of the object can point to another object, bit 63 for every word after that
+pub const ALLOC: &str = "cheer_alloc";
+// free the objects that can't be reached from the stack any more, the allocator calls it with
+// the address of its own return address in %rdi. Every call the compiler emits is followed by
+// a 7 byte nop whose displacement is the distance from the return address to the call's stack
+// map: the number of words in the frame that can point to objects, then their offsets from %rbp
+pub const COLLECT: &str = "cheer_collect";
+// main stores its frame pointer here, the collector follows frames up to it
+pub const STACK_BASE: &str = "cheer_stack_base";
+// write what the collector did to stderr
+pub const GC_STATS: &str = "cheer_gc_stats";
+
+// memory is mapped from the kernel in chunks of this many bytes, bigger allocations get their own
+const HEAP_CHUNK_SIZE: i32 = 1 << 20;
+
+pub fn routines() -> String {
+    format!("{}{}{}{}{}{}", panics(), printing(), float_printing(), strings(), memory(), gc_stats())
+}
+
+fn panics() -> String {
+    format!("\
+    \t.section\t.rodata\n\
+    \tcheer_divide_by_zero_message:\n\
+    \t\t.ascii \"error: attempt to divide by zero\\n\"\n\
+    \t.set cheer_divide_by_zero_length, .-cheer_divide_by_zero_message\n\
+    \tcheer_divide_overflow_message:\n\
+    \t\t.ascii \"error: attempt to divide with overflow\\n\"\n\
+    \t.set cheer_divide_overflow_length, .-cheer_divide_overflow_message\n\
+    \tcheer_index_message:\n\
+    \t\t.ascii \"error: index out of bounds: the len is \"\n\
+    \t.set cheer_index_length, .-cheer_index_message\n\
+    \tcheer_index_but_message:\n\
+    \t\t.ascii \" but the index is \"\n\
+    \t.set cheer_index_but_length, .-cheer_index_but_message\n\
+    \tcheer_overflow_message:\n\
+    \t\t.ascii \"error: arithmetic overflow in \"\n\
+    \t.set cheer_overflow_length, .-cheer_overflow_message\n\
+    \tcheer_overflow_line_message:\n\
+    \t\t.ascii \" at line \"\n\
+    \t.set cheer_overflow_line_length, .-cheer_overflow_line_message\n\
+    \tcheer_out_of_memory_message:\n\
+    \t\t.ascii \"error: out of memory\\n\"\n\
+    \t.set cheer_out_of_memory_length, .-cheer_out_of_memory_message\n\
+    \t.text\n\
+    \t{divide_by_zero}:\n\
+    \t\tleaq cheer_divide_by_zero_message(%rip), %rsi\n\
+    \t\tmovq $cheer_divide_by_zero_length, %rdx\n\
+    \t\tmovq ${divide_by_zero_status}, %rdi\n\
+    \t\tjmp cheer_panic\n\
+    \t{divide_overflow}:\n\
+    \t\tleaq cheer_divide_overflow_message(%rip), %rsi\n\
+    \t\tmovq $cheer_divide_overflow_length, %rdx\n\
+    \t\tmovq ${divide_overflow_status}, %rdi\n\
+    \t\tjmp cheer_panic\n\
+    \t{index_out_of_bounds}:\n\
+    \t\t# syscalls and cheer_write_int leave %r12 and %r13 alone\n\
+    \t\tmovq %rdi, %r12\n\
+    \t\tmovq %rsi, %r13\n\
+    \t\tleaq cheer_index_message(%rip), %rsi\n\
+    \t\tmovq $cheer_index_length, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tmovq %r13, %rdi\n\
+    \t\tmovq $2, %rsi\n\
+    \t\tcall cheer_write_int\n\
+    \t\tleaq cheer_index_but_message(%rip), %rsi\n\
+    \t\tmovq $cheer_index_but_length, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tmovq %r12, %rdi\n\
+    \t\tmovq $2, %rsi\n\
+    \t\tcall cheer_write_int\n\
+    \t\tleaq cheer_newline_text(%rip), %rsi\n\
+    \t\tmovq $1, %rdx\n\
+    \t\tmovq ${index_out_of_bounds_status}, %rdi\n\
+    \t\tjmp cheer_panic\n\
+    \t{overflow}:\n\
+    \t\tmovq %rdi, %r12\n\
+    \t\tmovq %rsi, %r13\n\
+    \t\tleaq cheer_overflow_message(%rip), %rsi\n\
+    \t\tmovq $cheer_overflow_length, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tmovq (%r12), %rdx\n\
+    \t\tleaq 8(%r12), %rsi\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tleaq cheer_overflow_line_message(%rip), %rsi\n\
+    \t\tmovq $cheer_overflow_line_length, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tmovq %r13, %rdi\n\
+    \t\tmovq $2, %rsi\n\
+    \t\tcall cheer_write_int\n\
+    \t\tleaq cheer_newline_text(%rip), %rsi\n\
+    \t\tmovq $1, %rdx\n\
+    \t\tmovq ${overflow_status}, %rdi\n\
+    \t\tjmp cheer_panic\n\
+    \tcheer_out_of_memory:\n\
+    \t\tleaq cheer_out_of_memory_message(%rip), %rsi\n\
+    \t\tmovq $cheer_out_of_memory_length, %rdx\n\
+    \t\tmovq ${out_of_memory_status}, %rdi\n\
+    \t\tjmp cheer_panic\n\
+    \t# write the message in %rsi (length %rdx) to stderr, then exit with status %rdi\n\
+    \tcheer_panic:\n\
+    \t\tpushq %rdi\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tpopq %rdi\n\
+    \t\tmovq $60, %rax\n\
+    \t\tsyscall\n\
+    ",
+    divide_by_zero=DIVIDE_BY_ZERO,
+    divide_by_zero_status=DIVIDE_BY_ZERO_STATUS,
+    divide_overflow=DIVIDE_OVERFLOW,
+    divide_overflow_status=DIVIDE_OVERFLOW_STATUS,
+    index_out_of_bounds=INDEX_OUT_OF_BOUNDS,
+    index_out_of_bounds_status=INDEX_OUT_OF_BOUNDS_STATUS,
+    out_of_memory_status=OUT_OF_MEMORY_STATUS,
+    overflow=OVERFLOW,
+    overflow_status=OVERFLOW_STATUS,
+    )
+}
+
+fn printing() -> String {
+    format!("\
+    \t.section\t.rodata\n\
+    \tcheer_true_text:\n\
+    \t\t.ascii \"true\\n\"\n\
+    \tcheer_false_text:\n\
+    \t\t.ascii \"false\\n\"\n\
+    \tcheer_newline_text:\n\
+    \t\t.ascii \"\\n\"\n\
+    \t.text\n\
+    \t# write %rdi in decimal to the file descriptor in %rsi\n\
+    \tcheer_write_int:\n\
+    \t\tpushq %rbp\n\
+    \t\tmovq %rsp, %rbp\n\
+    \t\tsubq $32, %rsp\n\
+    \t\tmovq %rsi, %r8\n\
+    \t\t# digits are written backwards from the end of the buffer\n\
+    \t\tmovq %rbp, %rsi\n\
+    \t\tmovq $10, %rcx\n\
+    \t\tmovq %rdi, %rax\n\
+    \t\t# work on the negative value, so i64::MIN doesn't need a special case\n\
+    \t\ttestq %rax, %rax\n\
+    \t\tjle cheer_write_int_digit\n\
+    \t\tnegq %rax\n\
+    \tcheer_write_int_digit:\n\
+    \t\tdecq %rsi\n\
+    \t\tcqto\n\
+    \t\tidivq %rcx\n\
+    \t\tnegq %rdx\n\
+    \t\taddq $48, %rdx\n\
+    \t\tmovb %dl, (%rsi)\n\
+    \t\ttestq %rax, %rax\n\
+    \t\tjne cheer_write_int_digit\n\
+    \t\ttestq %rdi, %rdi\n\
+    \t\tjns cheer_write_int_write\n\
+    \t\tdecq %rsi\n\
+    \t\tmovb $45, (%rsi)\n\
+    \tcheer_write_int_write:\n\
+    \t\tmovq %rbp, %rdx\n\
+    \t\tsubq %rsi, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq %r8, %rdi\n\
+    \t\tsyscall\n\
+    \t\tleave\n\
+    \t\tret\n\
+    \t{print_int}:\n\
+    \t\tmovq $1, %rsi\n\
+    \t\tcall cheer_write_int\n\
+    \t\tleaq cheer_newline_text(%rip), %rsi\n\
+    \t\tmovq $1, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tret\n\
+    \t{print_uint}:\n\
+    \t\tpushq %rbp\n\
+    \t\tmovq %rsp, %rbp\n\
+    \t\tsubq $32, %rsp\n\
+    \t\t# digits are written backwards from the newline at the end of the buffer\n\
+    \t\tleaq -1(%rbp), %rsi\n\
+    \t\tmovb $10, (%rsi)\n\
+    \t\tmovq $10, %rcx\n\
+    \t\tmovq %rdi, %rax\n\
+    \tcheer_print_uint_digit:\n\
+    \t\tdecq %rsi\n\
+    \t\txorq %rdx, %rdx\n\
+    \t\tdivq %rcx\n\
+    \t\taddq $48, %rdx\n\
+    \t\tmovb %dl, (%rsi)\n\
+    \t\ttestq %rax, %rax\n\
+    \t\tjne cheer_print_uint_digit\n\
+    \t\tmovq %rbp, %rdx\n\
+    \t\tsubq %rsi, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tleave\n\
+    \t\tret\n\
+    \t{print_bool}:\n\
+    \t\tleaq cheer_true_text(%rip), %rsi\n\
+    \t\tmovq $5, %rdx\n\
+    \t\ttestq %rdi, %rdi\n\
+    \t\tjne cheer_print_bool_write\n\
+    \t\tleaq cheer_false_text(%rip), %rsi\n\
+    \t\tmovq $6, %rdx\n\
+    \tcheer_print_bool_write:\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tret\n\
+    \t{print_str}:\n\
+    \t\tmovq (%rdi), %rdx\n\
+    \t\tleaq 8(%rdi), %rsi\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tleaq cheer_newline_text(%rip), %rsi\n\
+    \t\tmovq $1, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tret\n\
+    ",
+    print_int=PRINT_INT,
+    print_uint=PRINT_UINT,
+    print_bool=PRINT_BOOL,
+    print_str=PRINT_STR,
+    )
+}
+
+// the digits of a float come from scaling it by a power of ten to a 15 digit integer. Powers
+// up to 10^22 are exact doubles, so for most values that is one correctly rounded operation
+fn float_printing() -> String {
+    let powers: Vec<String> = (0..=22).map(|power| format!("1e{}", power)).collect();
+    format!("\
+    \t.section\t.rodata\n\
+    \t.align 8\n\
+    \tcheer_powers_of_ten:\n\
+    \t\t.double {powers}\n\
+    \t.text\n\
+    \t# multiply %xmm0 by 10 to the power of %rdi\n\
+    \tcheer_scale_float:\n\
+    \t\tleaq cheer_powers_of_ten(%rip), %rdx\n\
+    \t\tmovsd 176(%rdx), %xmm1\n\
+    \tcheer_scale_float_up:\n\
+    \t\tcmpq $22, %rdi\n\
+    \t\tjle cheer_scale_float_down\n\
+    \t\tmulsd %xmm1, %xmm0\n\
+    \t\tsubq $22, %rdi\n\
+    \t\tjmp cheer_scale_float_up\n\
+    \tcheer_scale_float_down:\n\
+    \t\tcmpq $-22, %rdi\n\
+    \t\tjge cheer_scale_float_last\n\
+    \t\tdivsd %xmm1, %xmm0\n\
+    \t\taddq $22, %rdi\n\
+    \t\tjmp cheer_scale_float_down\n\
+    \tcheer_scale_float_last:\n\
+    \t\ttestq %rdi, %rdi\n\
+    \t\tjs cheer_scale_float_divide\n\
+    \t\tmulsd (%rdx,%rdi,8), %xmm0\n\
+    \t\tret\n\
+    \tcheer_scale_float_divide:\n\
+    \t\tnegq %rdi\n\
+    \t\tdivsd (%rdx,%rdi,8), %xmm0\n\
+    \t\tret\n\
+    \t{print_float}:\n\
+    \t\tpushq %rbp\n\
+    \t\tmovq %rsp, %rbp\n\
+    \t\tsubq $96, %rsp\n\
+    \t\t# the text is written forwards from -96(%rbp), the digits are at -32(%rbp)\n\
+    \t\tleaq -96(%rbp), %rsi\n\
+    \t\tmovq %rdi, %rax\n\
+    \t\tbtrq $63, %rax\n\
+    \t\tmovabsq $0x7ff0000000000000, %rdx\n\
+    \t\tcmpq %rdx, %rax\n\
+    \t\tja cheer_print_float_nan\n\
+    \t\ttestq %rdi, %rdi\n\
+    \t\tjns cheer_print_float_positive\n\
+    \t\tmovb $45, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \tcheer_print_float_positive:\n\
+    \t\tcmpq %rdx, %rax\n\
+    \t\tje cheer_print_float_infinity\n\
+    \t\ttestq %rax, %rax\n\
+    \t\tje cheer_print_float_zero\n\
+    \t\tmovq %rax, %xmm2\n\
+    \t\t# guess the decimal exponent e from the binary one, log10(2) is about 78913 / 2^18\n\
+    \t\tmovq %rax, %r8\n\
+    \t\tshrq $52, %r8\n\
+    \t\tsubq $1023, %r8\n\
+    \t\timulq $78913, %r8\n\
+    \t\tsarq $18, %r8\n\
+    \tcheer_print_float_scale:\n\
+    \t\t# the value times 10^(14 - e), rounded, has 15 digits when e is right\n\
+    \t\tmovq $14, %rdi\n\
+    \t\tsubq %r8, %rdi\n\
+    \t\tmovapd %xmm2, %xmm0\n\
+    \t\tcall cheer_scale_float\n\
+    \t\tcvtsd2siq %xmm0, %r9\n\
+    \t\tmovabsq $1000000000000000, %rax\n\
+    \t\tcmpq %rax, %r9\n\
+    \t\tjl cheer_print_float_not_above\n\
+    \t\tincq %r8\n\
+    \t\tjmp cheer_print_float_scale\n\
+    \tcheer_print_float_not_above:\n\
+    \t\tmovabsq $100000000000000, %rax\n\
+    \t\tcmpq %rax, %r9\n\
+    \t\tjge cheer_print_float_digits\n\
+    \t\tdecq %r8\n\
+    \t\tjmp cheer_print_float_scale\n\
+    \tcheer_print_float_digits:\n\
+    \t\tmovq %r9, %rax\n\
+    \t\tmovq $10, %rcx\n\
+    \t\tleaq -17(%rbp), %rdi\n\
+    \tcheer_print_float_digit:\n\
+    \t\tdecq %rdi\n\
+    \t\txorl %edx, %edx\n\
+    \t\tdivq %rcx\n\
+    \t\taddq $48, %rdx\n\
+    \t\tmovb %dl, (%rdi)\n\
+    \t\tleaq -32(%rbp), %rdx\n\
+    \t\tcmpq %rdx, %rdi\n\
+    \t\tjne cheer_print_float_digit\n\
+    \t\t# %r10 digits are left without the trailing zeros, the first one never is\n\
+    \t\tmovq $15, %r10\n\
+    \tcheer_print_float_trim:\n\
+    \t\tcmpb $48, -33(%rbp,%r10)\n\
+    \t\tjne cheer_print_float_trimmed\n\
+    \t\tdecq %r10\n\
+    \t\tjmp cheer_print_float_trim\n\
+    \tcheer_print_float_trimmed:\n\
+    \t\t# the digits are copied from %rdi up to %r11\n\
+    \t\tleaq -32(%rbp,%r10), %r11\n\
+    \t\tcmpq $-5, %r8\n\
+    \t\tjl cheer_print_float_scientific\n\
+    \t\tcmpq $14, %r8\n\
+    \t\tjg cheer_print_float_scientific\n\
+    \t\ttestq %r8, %r8\n\
+    \t\tjs cheer_print_float_small\n\
+    \t\t# e + 1 digits before the point, zeros once the digits run out\n\
+    \t\tleaq 1(%r8), %rcx\n\
+    \tcheer_print_float_integer:\n\
+    \t\tmovb $48, (%rsi)\n\
+    \t\tcmpq %r11, %rdi\n\
+    \t\tjae cheer_print_float_integer_next\n\
+    \t\tmovb (%rdi), %al\n\
+    \t\tmovb %al, (%rsi)\n\
+    \t\tincq %rdi\n\
+    \tcheer_print_float_integer_next:\n\
+    \t\tincq %rsi\n\
+    \t\tdecq %rcx\n\
+    \t\tjne cheer_print_float_integer\n\
+    \t\tmovb $46, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \t\tcmpq %r11, %rdi\n\
+    \t\tjb cheer_print_float_fraction\n\
+    \t\tmovb $48, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \t\tjmp cheer_print_float_write\n\
+    \tcheer_print_float_small:\n\
+    \t\t# 0. and -e - 1 zeros before the digits\n\
+    \t\tmovw $0x2e30, (%rsi)\n\
+    \t\taddq $2, %rsi\n\
+    \t\tmovq %r8, %rcx\n\
+    \t\tnotq %rcx\n\
+    \tcheer_print_float_leading_zero:\n\
+    \t\ttestq %rcx, %rcx\n\
+    \t\tje cheer_print_float_fraction\n\
+    \t\tmovb $48, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \t\tdecq %rcx\n\
+    \t\tjmp cheer_print_float_leading_zero\n\
+    \tcheer_print_float_fraction:\n\
+    \t\tmovb (%rdi), %al\n\
+    \t\tmovb %al, (%rsi)\n\
+    \t\tincq %rdi\n\
+    \t\tincq %rsi\n\
+    \t\tcmpq %r11, %rdi\n\
+    \t\tjb cheer_print_float_fraction\n\
+    \t\tjmp cheer_print_float_write\n\
+    \tcheer_print_float_scientific:\n\
+    \t\t# one digit before the point, the exponent is written by cheer_write_int\n\
+    \t\tmovb (%rdi), %al\n\
+    \t\tmovb %al, (%rsi)\n\
+    \t\tincq %rdi\n\
+    \t\tincq %rsi\n\
+    \t\tcmpq %r11, %rdi\n\
+    \t\tjae cheer_print_float_exponent\n\
+    \t\tmovb $46, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \tcheer_print_float_mantissa:\n\
+    \t\tmovb (%rdi), %al\n\
+    \t\tmovb %al, (%rsi)\n\
+    \t\tincq %rdi\n\
+    \t\tincq %rsi\n\
+    \t\tcmpq %r11, %rdi\n\
+    \t\tjb cheer_print_float_mantissa\n\
+    \tcheer_print_float_exponent:\n\
+    \t\tmovb $101, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \t\tmovq %rsi, %rdx\n\
+    \t\tleaq -96(%rbp), %rsi\n\
+    \t\tsubq %rsi, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tmovq %r8, %rdi\n\
+    \t\tmovq $1, %rsi\n\
+    \t\tcall cheer_write_int\n\
+    \t\tleaq -96(%rbp), %rsi\n\
+    \t\tjmp cheer_print_float_write\n\
+    \tcheer_print_float_nan:\n\
+    \t\tmovl $0x4e614e, (%rsi)\n\
+    \t\taddq $3, %rsi\n\
+    \t\tjmp cheer_print_float_write\n\
+    \tcheer_print_float_infinity:\n\
+    \t\tmovl $0x666e69, (%rsi)\n\
+    \t\taddq $3, %rsi\n\
+    \t\tjmp cheer_print_float_write\n\
+    \tcheer_print_float_zero:\n\
+    \t\tmovl $0x302e30, (%rsi)\n\
+    \t\taddq $3, %rsi\n\
+    \tcheer_print_float_write:\n\
+    \t\tmovb $10, (%rsi)\n\
+    \t\tincq %rsi\n\
+    \t\tmovq %rsi, %rdx\n\
+    \t\tleaq -96(%rbp), %rsi\n\
+    \t\tsubq %rsi, %rdx\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $1, %rdi\n\
+    \t\tsyscall\n\
+    \t\tleave\n\
+    \t\tret\n\
+    ",
+    powers=powers.join(", "),
+    print_float=PRINT_FLOAT,
+    )
+}
+
+fn strings() -> String {
+    format!("\
+    \t{str_eq}:\n\
+    \t\txorq %rax, %rax\n\
+    \t\tmovq (%rdi), %rcx\n\
+    \t\tcmpq (%rsi), %rcx\n\
+    \t\tjne cheer_str_eq_done\n\
+    \t\tleaq 8(%rdi), %rdi\n\
+    \t\tleaq 8(%rsi), %rsi\n\
+    \t\t# an empty string leaves the zero flag set by the length comparison\n\
+    \t\trepe cmpsb\n\
+    \t\tsete %al\n\
+    \tcheer_str_eq_done:\n\
+    \t\tret\n\
+    ",
+    str_eq=STR_EQ,
+    )
+}
+
+// Objects live in chunks mapped with mmap. Every object has a 16 byte header in front of it:
+// the size of its block with flags in the low bits, then a mask of the words that can hold
+// pointers to other objects. A chunk starts with a link to the next chunk and its end address,
+// followed by a bitmap with a bit for every 16 bytes telling where objects start, so a word
+// can be told to point to an object without knowing where it came from.
+//
+// New objects are taken from a free list, first fit, or else bumped off the newest chunk. Once
+// that is full and enough has been allocated since the last collection, the heap is collected
+// with mark-sweep. The roots are the words the stack maps of the calls in progress list, found
+// by following the saved %rbp of every frame up to main's. The payload words of an enum are
+// listed when they hold pointers for any variant, so a root can be a number that only looks
+// like a pointer, and only words pointing to the start of an object keep it alive
+fn memory() -> String {
+    format!("\
+    \t.bss\n\
+    \t.align 8\n\
+    \t{stack_base}:\n\
+    \t\t.zero 8\n\
+    \tcheer_heap_chunks:\n\
+    \t\t.zero 8\n\
+    \tcheer_heap_next:\n\
+    \t\t.zero 8\n\
+    \tcheer_heap_end:\n\
+    \t\t.zero 8\n\
+    \tcheer_free_list:\n\
+    \t\t.zero 8\n\
+    \tcheer_mark_base:\n\
+    \t\t.zero 8\n\
+    \tcheer_mark_top:\n\
+    \t\t.zero 8\n\
+    \tcheer_mark_end:\n\
+    \t\t.zero 8\n\
+    \t# bytes allocated since the last collection, and how many make the next one worth it\n\
+    \tcheer_gc_since:\n\
+    \t\t.zero 8\n\
+    \tcheer_gc_threshold:\n\
+    \t\t.zero 8\n\
+    \tcheer_gc_collections:\n\
+    \t\t.zero 8\n\
+    \tcheer_gc_allocated:\n\
+    \t\t.zero 8\n\
+    \tcheer_gc_freed:\n\
+    \t\t.zero 8\n\
+    \tcheer_gc_in_use:\n\
+    \t\t.zero 8\n\
+    \tcheer_gc_heap:\n\
+    \t\t.zero 8\n\
+    \t.text\n\
+    \t{alloc}:\n\
+    \t\tpushq %rbx\n\
+    \t\tpushq %r12\n\
+    \t\tpushq %r13\n\
+    \t\t# the block holds the header and the object, rounded up to 16 bytes\n\
+    \t\tleaq 31(%rdi), %rbx\n\
+    \t\tandq $-16, %rbx\n\
+    \t\tmovq %rsi, %r12\n\
+    \t\t# set once the heap has been collected for this allocation\n\
+    \t\txorq %r13, %r13\n\
+    \tcheer_alloc_retry:\n\
+    \t\t# %rcx points to the link to the free block in %rax\n\
+    \t\tleaq cheer_free_list(%rip), %rcx\n\
+    \tcheer_alloc_free:\n\
+    \t\tmovq (%rcx), %rax\n\
+    \t\ttestq %rax, %rax\n\
+    \t\tje cheer_alloc_bump\n\
+    \t\tmovq (%rax), %rdx\n\
+    \t\tandq $-16, %rdx\n\
+    \t\tcmpq %rbx, %rdx\n\
+    \t\tjae cheer_alloc_found\n\
+    \t\tleaq 16(%rax), %rcx\n\
+    \t\tjmp cheer_alloc_free\n\
+    \tcheer_alloc_found:\n\
+    \t\tsubq %rbx, %rdx\n\
+    \t\tcmpq $32, %rdx\n\
+    \t\tjb cheer_alloc_whole\n\
+    \t\t# the rest of the block takes its place on the free list\n\
+    \t\tleaq (%rax,%rbx), %rsi\n\
+    \t\torq $2, %rdx\n\
+    \t\tmovq %rdx, (%rsi)\n\
+    \t\tmovq 16(%rax), %rdx\n\
+    \t\tmovq %rdx, 16(%rsi)\n\
+    \t\tmovq %rsi, (%rcx)\n\
+    \t\tjmp cheer_alloc_done\n\
+    \tcheer_alloc_whole:\n\
+    \t\taddq %rdx, %rbx\n\
+    \t\tmovq 16(%rax), %rdx\n\
+    \t\tmovq %rdx, (%rcx)\n\
+    \t\tjmp cheer_alloc_done\n\
+    \tcheer_alloc_bump:\n\
+    \t\tmovq cheer_heap_next(%rip), %rax\n\
+    \t\tleaq (%rax,%rbx), %rdx\n\
+    \t\tcmpq cheer_heap_end(%rip), %rdx\n\
+    \t\tja cheer_alloc_full\n\
+    \t\tmovq %rdx, cheer_heap_next(%rip)\n\
+    \tcheer_alloc_done:\n\
+    \t\tmovq %rbx, (%rax)\n\
+    \t\tmovq %r12, 8(%rax)\n\
+    \t\taddq %rbx, cheer_gc_since(%rip)\n\
+    \t\taddq %rbx, cheer_gc_allocated(%rip)\n\
+    \t\taddq %rbx, cheer_gc_in_use(%rip)\n\
+    \t\tmovq %rax, %rdi\n\
+    \t\tcall cheer_chunk_of\n\
+    \t\tmovq %rax, %rcx\n\
+    \t\tsubq %rdx, %rcx\n\
+    \t\tshrq $4, %rcx\n\
+    \t\tbtsq %rcx, 16(%rdx)\n\
+    \t\tleaq 16(%rax), %rax\n\
+    \t\tpopq %r13\n\
+    \t\tpopq %r12\n\
+    \t\tpopq %rbx\n\
+    \t\tret\n\
+    \tcheer_alloc_full:\n\
+    \t\ttestq %r13, %r13\n\
+    \t\tjne cheer_alloc_chunk\n\
+    \t\tcmpq $0, cheer_heap_chunks(%rip)\n\
+    \t\tje cheer_alloc_chunk\n\
+    \t\tmovq cheer_gc_since(%rip), %rdx\n\
+    \t\tcmpq cheer_gc_threshold(%rip), %rdx\n\
+    \t\tjb cheer_alloc_chunk\n\
+    \t\tmovq $1, %r13\n\
+    \t\tleaq 24(%rsp), %rdi\n\
+    \t\tcall {collect}\n\
+    \t\tjmp cheer_alloc_retry\n\
+    \tcheer_alloc_chunk:\n\
+    \t\t# what is left of the newest chunk becomes a free block\n\
+    \t\tmovq cheer_heap_next(%rip), %rdi\n\
+    \t\tmovq cheer_heap_end(%rip), %rdx\n\
+    \t\tsubq %rdi, %rdx\n\
+    \t\tje cheer_alloc_map\n\
+    \t\torq $2, %rdx\n\
+    \t\tmovq %rdx, (%rdi)\n\
+    \t\tcmpq $32, %rdx\n\
+    \t\tjb cheer_alloc_map\n\
+    \t\tmovq cheer_free_list(%rip), %rcx\n\
+    \t\tmovq %rcx, 16(%rdi)\n\
+    \t\tmovq %rdi, cheer_free_list(%rip)\n\
+    \tcheer_alloc_map:\n\
+    \t\t# big blocks get a chunk with room for them and their part of the bitmap\n\
+    \t\tmovq %rbx, %rsi\n\
+    \t\tshrq $6, %rsi\n\
+    \t\tleaq 8191(%rbx,%rsi), %rsi\n\
+    \t\tandq $-4096, %rsi\n\
+    \t\tmovq ${chunk_size}, %rdx\n\
+    \t\tcmpq %rdx, %rsi\n\
+    \t\tcmovb %rdx, %rsi\n\
+    \t\tpushq %rsi\n\
+    \t\t# mmap(NULL, size, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0)\n\
+    \t\txorq %rdi, %rdi\n\
+    \t\tmovq $3, %rdx\n\
+    \t\tmovq $34, %r10\n\
+    \t\tmovq $-1, %r8\n\
+    \t\txorq %r9, %r9\n\
+    \t\tmovq $9, %rax\n\
+    \t\tsyscall\n\
+    \t\tpopq %rsi\n\
+    \t\t# errors come back as -4095 to -1\n\
+    \t\tcmpq $-4096, %rax\n\
+    \t\tja cheer_out_of_memory\n\
+    \t\tmovq cheer_heap_chunks(%rip), %rdx\n\
+    \t\tmovq %rdx, (%rax)\n\
+    \t\tmovq %rax, cheer_heap_chunks(%rip)\n\
+    \t\tleaq (%rax,%rsi), %rdx\n\
+    \t\tmovq %rdx, 8(%rax)\n\
+    \t\tmovq %rdx, cheer_heap_end(%rip)\n\
+    \t\taddq %rsi, cheer_gc_heap(%rip)\n\
+    \t\tshrq $7, %rsi\n\
+    \t\tleaq 16(%rax,%rsi), %rdx\n\
+    \t\tmovq %rdx, cheer_heap_next(%rip)\n\
+    \t\tjmp cheer_alloc_bump\n\
+    \t# the chunk holding the address in %rdi in %rdx, or 0 when it isn't in the heap\n\
+    \tcheer_chunk_of:\n\
+    \t\tmovq cheer_heap_chunks(%rip), %rdx\n\
+    \tcheer_chunk_of_next:\n\
+    \t\ttestq %rdx, %rdx\n\
+    \t\tje cheer_chunk_of_done\n\
+    \t\tcmpq %rdx, %rdi\n\
+    \t\tjb cheer_chunk_of_skip\n\
+    \t\tcmpq 8(%rdx), %rdi\n\
+    \t\tjb cheer_chunk_of_done\n\
+    \tcheer_chunk_of_skip:\n\
+    \t\tmovq (%rdx), %rdx\n\
+    \t\tjmp cheer_chunk_of_next\n\
+    \tcheer_chunk_of_done:\n\
+    \t\tret\n\
+    \t# mark the object %rdi points to and push its block on the mark stack, if it points to\n\
+    \t# an object that isn't marked yet\n\
+    \tcheer_mark:\n\
+    \t\tcall cheer_chunk_of\n\
+    \t\ttestq %rdx, %rdx\n\
+    \t\tje cheer_mark_done\n\
+    \t\tleaq -16(%rdi), %rax\n\
+    \t\tcmpq %rdx, %rax\n\
+    \t\tjb cheer_mark_done\n\
+    \t\tmovq %rax, %rcx\n\
+    \t\tsubq %rdx, %rcx\n\
+    \t\ttestq $15, %rcx\n\
+    \t\tjne cheer_mark_done\n\
+    \t\tshrq $4, %rcx\n\
+    \t\tbtq %rcx, 16(%rdx)\n\
+    \t\tjnc cheer_mark_done\n\
+    \t\tmovq (%rax), %rcx\n\
+    \t\ttestq $1, %rcx\n\
+    \t\tjne cheer_mark_done\n\
+    \t\torq $1, %rcx\n\
+    \t\tmovq %rcx, (%rax)\n\
+    \t\tmovq cheer_mark_top(%rip), %rcx\n\
+    \t\tcmpq cheer_mark_end(%rip), %rcx\n\
+    \t\tjb cheer_mark_push\n\
+    \t\t# the mark stack is full, map it the first time and double it after that\n\
+    \t\tpushq %rax\n\
+    \t\tmovq cheer_mark_base(%rip), %rdi\n\
+    \t\tmovq cheer_mark_end(%rip), %rsi\n\
+    \t\tsubq %rdi, %rsi\n\
+    \t\ttestq %rdi, %rdi\n\
+    \t\tjne cheer_mark_grow\n\
+    \t\tmovq $65536, %rsi\n\
+    \t\tmovq $3, %rdx\n\
+    \t\tmovq $34, %r10\n\
+    \t\tmovq $-1, %r8\n\
+    \t\txorq %r9, %r9\n\
+    \t\tmovq $9, %rax\n\
+    \t\tsyscall\n\
+    \t\tcmpq $-4096, %rax\n\
+    \t\tja cheer_out_of_memory\n\
+    \t\tmovq %rax, cheer_mark_base(%rip)\n\
+    \t\tmovq %rax, cheer_mark_top(%rip)\n\
+    \t\taddq %rsi, %rax\n\
+    \t\tmovq %rax, cheer_mark_end(%rip)\n\
+    \t\tjmp cheer_mark_grown\n\
+    \tcheer_mark_grow:\n\
+    \t\t# mremap(base, size, 2 * size, MREMAP_MAYMOVE)\n\
+    \t\tleaq (%rsi,%rsi), %rdx\n\
+    \t\tmovq $1, %r10\n\
+    \t\tmovq $25, %rax\n\
+    \t\tsyscall\n\
+    \t\tcmpq $-4096, %rax\n\
+    \t\tja cheer_out_of_memory\n\
+    \t\tmovq %rax, cheer_mark_base(%rip)\n\
+    \t\taddq %rsi, %rax\n\
+    \t\tmovq %rax, cheer_mark_top(%rip)\n\
+    \t\taddq %rsi, %rax\n\
+    \t\tmovq %rax, cheer_mark_end(%rip)\n\
+    \tcheer_mark_grown:\n\
+    \t\tpopq %rax\n\
+    \t\tmovq cheer_mark_top(%rip), %rcx\n\
+    \tcheer_mark_push:\n\
+    \t\tmovq %rax, (%rcx)\n\
+    \t\taddq $8, %rcx\n\
+    \t\tmovq %rcx, cheer_mark_top(%rip)\n\
+    \tcheer_mark_done:\n\
+    \t\tret\n\
+    \t{collect}:\n\
+    \t\tpushq %rbx\n\
+    \t\tpushq %r12\n\
+    \t\tpushq %r13\n\
+    \t\tpushq %r14\n\
+    \t\tpushq %r15\n\
+    \t\tincq cheer_gc_collections(%rip)\n\
+    \t\t# %r12 is where a frame's call returns to, %r13 the frame, %r14 the entry of the\n\
+    \t\t# call's stack map being looked at and %r15 how many are left\n\
+    \t\tmovq (%rdi), %r12\n\
+    \t\tmovq %rbp, %r13\n\
+    \tcheer_collect_frame:\n\
+    \t\tmovslq 3(%r12), %r14\n\
+    \t\taddq %r12, %r14\n\
+    \t\tmovq (%r14), %r15\n\
+    \tcheer_collect_root:\n\
+    \t\ttestq %r15, %r15\n\
+    \t\tje cheer_collect_caller\n\
+    \t\taddq $8, %r14\n\
+    \t\tmovq (%r14), %rax\n\
+    \t\tmovq (%r13,%rax), %rdi\n\
+    \t\tcall cheer_mark\n\
+    \t\tdecq %r15\n\
+    \t\tjmp cheer_collect_root\n\
+    \tcheer_collect_caller:\n\
+    \t\tcmpq {stack_base}(%rip), %r13\n\
+    \t\tje cheer_collect_trace\n\
+    \t\tmovq 8(%r13), %r12\n\
+    \t\tmovq (%r13), %r13\n\
+    \t\tjmp cheer_collect_frame\n\
+    \tcheer_collect_trace:\n\
+    \t\t# mark what the objects on the mark stack point to until it is empty\n\
+    \t\tmovq cheer_mark_top(%rip), %rax\n\
+    \t\tcmpq cheer_mark_base(%rip), %rax\n\
+    \t\tje cheer_collect_sweep\n\
+    \t\tsubq $8, %rax\n\
+    \t\tmovq %rax, cheer_mark_top(%rip)\n\
+    \t\tmovq (%rax), %r12\n\
+    \t\tmovq (%r12), %r13\n\
+    \t\tandq $-16, %r13\n\
+    \t\taddq %r12, %r13\n\
+    \t\tmovq 8(%r12), %r14\n\
+    \t\tleaq 16(%r12), %r15\n\
+    \t\txorq %rbx, %rbx\n\
+    \tcheer_collect_word:\n\
+    \t\tcmpq %r13, %r15\n\
+    \t\tjae cheer_collect_trace\n\
+    \t\t# bit 63 of the mask stands for every word from the 63rd on\n\
+    \t\tmovq $63, %rcx\n\
+    \t\tcmpq %rcx, %rbx\n\
+    \t\tcmovb %rbx, %rcx\n\
+    \t\tbtq %rcx, %r14\n\
+    \t\tjnc cheer_collect_next_word\n\
+    \t\tmovq (%r15), %rdi\n\
+    \t\tcall cheer_mark\n\
+    \tcheer_collect_next_word:\n\
+    \t\taddq $8, %r15\n\
+    \t\tincq %rbx\n\
+    \t\tjmp cheer_collect_word\n\
+    \tcheer_collect_sweep:\n\
+    \t\t# unmarked blocks are freed, and runs of free blocks are joined and make up the\n\
+    \t\t# new free list. %r12 is the chunk, %r15 the block and %rbx the start of the run\n\
+    \t\tmovq $0, cheer_free_list(%rip)\n\
+    \t\tmovq cheer_heap_chunks(%rip), %r12\n\
+    \tcheer_sweep_chunk:\n\
+    \t\ttestq %r12, %r12\n\
+    \t\tje cheer_sweep_done\n\
+    \t\tmovq 8(%r12), %r13\n\
+    \t\tmovq %r13, %rax\n\
+    \t\tsubq %r12, %rax\n\
+    \t\tshrq $7, %rax\n\
+    \t\tleaq 16(%r12,%rax), %r15\n\
+    \t\t# the newest chunk only has blocks up to where allocation got to\n\
+    \t\tcmpq cheer_heap_chunks(%rip), %r12\n\
+    \t\tjne cheer_sweep_start\n\
+    \t\tmovq cheer_heap_next(%rip), %r13\n\
+    \tcheer_sweep_start:\n\
+    \t\txorq %rbx, %rbx\n\
+    \tcheer_sweep_block:\n\
+    \t\tcmpq %r13, %r15\n\
+    \t\tjae cheer_sweep_chunk_end\n\
+    \t\tmovq (%r15), %rax\n\
+    \t\tmovq %rax, %rdx\n\
+    \t\tandq $-16, %rdx\n\
+    \t\ttestq $2, %rax\n\
+    \t\tjne cheer_sweep_free\n\
+    \t\ttestq $1, %rax\n\
+    \t\tje cheer_sweep_garbage\n\
+    \t\tandq $-2, %rax\n\
+    \t\tmovq %rax, (%r15)\n\
+    \t\tcall cheer_sweep_close\n\
+    \t\tjmp cheer_sweep_next\n\
+    \tcheer_sweep_garbage:\n\
+    \t\taddq %rdx, cheer_gc_freed(%rip)\n\
+    \t\tsubq %rdx, cheer_gc_in_use(%rip)\n\
+    \t\tmovq %r15, %rcx\n\
+    \t\tsubq %r12, %rcx\n\
+    \t\tshrq $4, %rcx\n\
+    \t\tbtrq %rcx, 16(%r12)\n\
+    \tcheer_sweep_free:\n\
+    \t\ttestq %rbx, %rbx\n\
+    \t\tjne cheer_sweep_join\n\
+    \t\tmovq %r15, %rbx\n\
+    \t\tmovq %rdx, %rax\n\
+    \t\torq $2, %rax\n\
+    \t\tmovq %rax, (%r15)\n\
+    \t\tjmp cheer_sweep_next\n\
+    \tcheer_sweep_join:\n\
+    \t\taddq %rdx, (%rbx)\n\
+    \tcheer_sweep_next:\n\
+    \t\taddq %rdx, %r15\n\
+    \t\tjmp cheer_sweep_block\n\
+    \tcheer_sweep_chunk_end:\n\
+    \t\tcall cheer_sweep_close\n\
+    \t\tmovq (%r12), %r12\n\
+    \t\tjmp cheer_sweep_chunk\n\
+    \tcheer_sweep_done:\n\
+    \t\tmovq $0, cheer_gc_since(%rip)\n\
+    \t\t# collect again once as much as is in use has been allocated, but at least a chunk's worth\n\
+    \t\tmovq ${chunk_size}, %rax\n\
+    \t\tmovq cheer_gc_in_use(%rip), %rdx\n\
+    \t\tcmpq %rax, %rdx\n\
+    \t\tcmova %rdx, %rax\n\
+    \t\tmovq %rax, cheer_gc_threshold(%rip)\n\
+    \t\tpopq %r15\n\
+    \t\tpopq %r14\n\
+    \t\tpopq %r13\n\
+    \t\tpopq %r12\n\
+    \t\tpopq %rbx\n\
+    \t\tret\n\
+    \t# put the run of free blocks starting at %rbx on the free list, if it has room for the link\n\
+    \tcheer_sweep_close:\n\
+    \t\ttestq %rbx, %rbx\n\
+    \t\tje cheer_sweep_closed\n\
+    \t\tmovq (%rbx), %rax\n\
+    \t\tandq $-16, %rax\n\
+    \t\tcmpq $32, %rax\n\
+    \t\tjb cheer_sweep_closed\n\
+    \t\tmovq cheer_free_list(%rip), %rax\n\
+    \t\tmovq %rax, 16(%rbx)\n\
+    \t\tmovq %rbx, cheer_free_list(%rip)\n\
+    \tcheer_sweep_closed:\n\
+    \t\txorq %rbx, %rbx\n\
+    \t\tret\n\
+    ",
+    alloc=ALLOC,
+    collect=COLLECT,
+    stack_base=STACK_BASE,
+    chunk_size=HEAP_CHUNK_SIZE,
+    )
+}
+
+fn gc_stats() -> String {
+    format!("\
+    \t.section\t.rodata\n\
+    \tcheer_gc_stats_message:\n\
+    \t\t.ascii \"gc: \"\n\
+    \t.set cheer_gc_stats_length, .-cheer_gc_stats_message\n\
+    \tcheer_gc_collections_message:\n\
+    \t\t.ascii \" collections, \"\n\
+    \t.set cheer_gc_collections_length, .-cheer_gc_collections_message\n\
+    \tcheer_gc_allocated_message:\n\
+    \t\t.ascii \" bytes allocated, \"\n\
+    \t.set cheer_gc_allocated_length, .-cheer_gc_allocated_message\n\
+    \tcheer_gc_freed_message:\n\
+    \t\t.ascii \" bytes freed, \"\n\
+    \t.set cheer_gc_freed_length, .-cheer_gc_freed_message\n\
+    \tcheer_gc_in_use_message:\n\
+    \t\t.ascii \" bytes in use, \"\n\
+    \t.set cheer_gc_in_use_length, .-cheer_gc_in_use_message\n\
+    \tcheer_gc_heap_message:\n\
+    \t\t.ascii \" bytes of heap\\n\"\n\
+    \t.set cheer_gc_heap_length, .-cheer_gc_heap_message\n\
+    \t.text\n\
+    \t{gc_stats}:\n\
+    \t\tleaq cheer_gc_stats_message(%rip), %rsi\n\
+    \t\tmovq $cheer_gc_stats_length, %rdx\n\
+    \t\tcall cheer_write_error\n\
+    \t\tmovq cheer_gc_collections(%rip), %rdi\n\
+    \t\tleaq cheer_gc_collections_message(%rip), %rsi\n\
+    \t\tmovq $cheer_gc_collections_length, %rdx\n\
+    \t\tcall cheer_write_stat\n\
+    \t\tmovq cheer_gc_allocated(%rip), %rdi\n\
+    \t\tleaq cheer_gc_allocated_message(%rip), %rsi\n\
+    \t\tmovq $cheer_gc_allocated_length, %rdx\n\
+    \t\tcall cheer_write_stat\n\
+    \t\tmovq cheer_gc_freed(%rip), %rdi\n\
+    \t\tleaq cheer_gc_freed_message(%rip), %rsi\n\
+    \t\tmovq $cheer_gc_freed_length, %rdx\n\
+    \t\tcall cheer_write_stat\n\
+    \t\tmovq cheer_gc_in_use(%rip), %rdi\n\
+    \t\tleaq cheer_gc_in_use_message(%rip), %rsi\n\
+    \t\tmovq $cheer_gc_in_use_length, %rdx\n\
+    \t\tcall cheer_write_stat\n\
+    \t\tmovq cheer_gc_heap(%rip), %rdi\n\
+    \t\tleaq cheer_gc_heap_message(%rip), %rsi\n\
+    \t\tmovq $cheer_gc_heap_length, %rdx\n\
+    \t\tcall cheer_write_stat\n\
+    \t\tret\n\
+    \t# write %rdi in decimal and then the text at %rsi, %rdx bytes long, to stderr\n\
+    \tcheer_write_stat:\n\
+    \t\tpushq %rsi\n\
+    \t\tpushq %rdx\n\
+    \t\tmovq $2, %rsi\n\
+    \t\tcall cheer_write_int\n\
+    \t\tpopq %rdx\n\
+    \t\tpopq %rsi\n\
+    \tcheer_write_error:\n\
+    \t\tmovq $1, %rax\n\
+    \t\tmovq $2, %rdi\n\
+    \t\tsyscall\n\
+    \t\tret\n\
+    ",
+    gc_stats=GC_STATS,
+    )
+}
@@ -1,274 +1,480 @@
 use std::{iter::Peekable, str::Chars};
 
-use crate::token::{Token, TokenType};
+use crate::{span::Span, token::{Token, TokenType}, typechecker::IntType};
 
-pub enum ScanResult {
-    Tokens(Vec<Token>),
-    Error(ScanError),
+// scanning carries on after an error, tokens has an Error token in place of the text each
+// error is about so the parser can carry on too
+pub struct ScanResult<'src> {
+    pub tokens: Vec<Token<'src>>,
+    pub errors: Vec<ScanError>,
 }
 
+// the span covers the text the error is about, from its first character
 #[derive(Debug)]
 pub struct ScanError {
     pub message: String,
-    pub line: i32,
-    pub column: i32,
+    pub span: Span,
 }
 
-pub struct Scanner {
+// the tokens of source one at a time, their lexemes are slices of it
+pub struct Scanner<'src> {
+    source: &'src str,
+    chars: Peekable<Chars<'src>>,
+    // the position of the last character consumed, and of the first one of the token being scanned
     line: i32,
     column: i32,
+    start_position: (i32, i32),
+    // spans of the tokens scanned are in this file
+    file: usize,
+    // the offset of the next character, and where the token being scanned started
+    byte: usize,
+    start: usize,
 }
 
-impl Scanner {
-
-    pub fn new() -> Scanner {
-        Scanner {line: 1, column: 0}
+// every token in source, file is the index spans get
+pub fn scan(file: usize, source: &str) -> ScanResult<'_> {
+    let mut scanner = Scanner::new(file, source);
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(result) = scanner.next() {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => {
+                tokens.extend(scanner.error_token());
+                errors.push(error);
+            }
+        }
     }
+    ScanResult {tokens, errors}
+}
 
-    pub fn scan(&mut self, program: &str) -> ScanResult {
-        let mut tokens = Vec::new();
-        let mut chars = program.chars().peekable();
+impl<'src> Iterator for Scanner<'src> {
+    type Item = Result<Token<'src>, ScanError>;
 
+    fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let peeked = chars.peek();
-            match peeked {
-                Some(char) => {
-                    match char {
-                        '+' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::Plus,
-                                lexeme: String::from("+"), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '-' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::Minus,
-                                lexeme: String::from("-"), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '*' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::Star, 
-                                lexeme: String::from("*"), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '(' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::LeftParen, 
-                                lexeme: String::from("("), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        ')' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::RightParen,
-                                lexeme: String::from(")"), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '{' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::LeftBrace, 
-                                lexeme: String::from("{"), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '}' => {
-                            self.advance_char(&mut chars);
-                            let token = Token {token_type: TokenType::RightBrace,
-                                lexeme: String::from("}"), line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9' => {
-                            let char = char.clone();
-                            self.advance_char(&mut chars);
-                            let token = self.match_number(&mut chars, char);
-                            tokens.push(token)
-                        }
-                        '0' => {
-                            self.advance_char(&mut chars);
-                            let token = Token { token_type: TokenType::IntLiteral, lexeme: String::from("0"),
-                                line: self.line, column: self.column };
-                            tokens.push(token);
-
-                            if let Some(char) = chars.peek() {
-                                match char {
-                                    '1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9' => {
-                                        return ScanResult::Error(ScanError {
-                                            message: String::from("Leading zeros in integer literals are not permitted"),
-                                            line: self.line,
-                                            column: self.column,
-                                        });
-                                    }
-                                    _ => {}
-                                }
+            self.start = self.byte;
+            self.start_position = (self.line, self.column + 1);
+            let char = *self.chars.peek()?;
+            self.advance();
+            let token_type = match char {
+                '+' => if self.next_is('%') { TokenType::PlusPercent } else { TokenType::Plus },
+                '-' => {
+                    if self.next_is('>') {
+                        TokenType::Arrow
+                    } else if self.next_is('%') {
+                        TokenType::MinusPercent
+                    } else {
+                        TokenType::Minus
+                    }
+                }
+                '*' => if self.next_is('%') { TokenType::StarPercent } else { TokenType::Star },
+                '/' => {
+                    match self.chars.peek() {
+                        // a line comment ends at the newline, which is scanned as usual
+                        Some('/') => {
+                            while !matches!(self.chars.peek(), Some('\n') | None) {
+                                self.advance();
                             }
-                            
+                            continue;
                         }
-                        '>' => {
-                            self.advance_char(&mut chars);
-                            let mut token_type = TokenType::Greater;
-                            let mut lexeme = String::from(">");
-                            match chars.peek() {
-                                Some(char) => {
-                                    match char {
-                                        '=' => {
-                                            self.advance_char(&mut chars);
-                                            token_type = TokenType::GreaterEqual;
-                                            lexeme = String::from(">=");
-                                        }
-                                        _ => {
-
-                                        }
-                                    }
-                                }
-                                None => {}
+                        Some('*') => {
+                            if let Err(error) = self.skip_block_comment() {
+                                // there is no Error token for a comment, it is skipped like any other
+                                self.start = self.byte;
+                                return Some(Err(error));
                             }
-                            let token = Token {token_type, lexeme, line: self.line, column: self.column};
-                            tokens.push(token);
+                            continue;
                         }
-                        '<' => {
-                            self.advance_char(&mut chars);
-                            let mut token_type = TokenType::Less;
-                            let mut lexeme = String::from("<");
-                            match chars.peek() {
-                                Some(char) => {
-                                    match char {
-                                        '=' => {
-                                            self.advance_char(&mut chars);
-                                            token_type = TokenType::LessEqual;
-                                            lexeme = String::from("<=");
-                                        }
-                                        _ => {
-
-                                        }
-                                    }
-                                }
-                                None => {}
-                            }
-                            let token = Token {token_type, lexeme, line: self.line, column: self.column};
-                            tokens.push(token);
+                        _ => TokenType::Slash,
+                    }
+                }
+                '%' => TokenType::Percent,
+                '(' => TokenType::LeftParen,
+                ')' => TokenType::RightParen,
+                '{' => TokenType::LeftBrace,
+                '}' => TokenType::RightBrace,
+                '[' => TokenType::LeftBracket,
+                ']' => TokenType::RightBracket,
+                ',' => TokenType::Comma,
+                '.' => TokenType::Dot,
+                ':' => if self.next_is(':') { TokenType::ColonColon } else { TokenType::Colon },
+                ';' => TokenType::Semicolon,
+                '0'..='9' => {
+                    let result = self.match_number(char);
+                    if result.is_err() {
+                        // a literal can go wrong before its end, like 1.5u8 at the u, the rest
+                        // of it is part of the Error token
+                        while matches!(self.chars.peek(), Some(char) if char.is_alphanumeric() || *char == '_') {
+                            self.advance();
                         }
-                        '=' => {
-                            self.advance_char(&mut chars);
-                            let mut token_type = TokenType::Equal;
-                            let mut lexeme = String::from("=");
-                            match chars.peek() {
-                                Some(char) => {
-                                    match char {
-                                        '=' => {
-                                            self.advance_char(&mut chars);
-                                            token_type = TokenType::EqualEqual;
-                                            lexeme = String::from("==");
-                                        }
-                                        _ => {
+                    }
+                    return Some(result);
+                }
+                '>' => if self.next_is('=') { TokenType::GreaterEqual } else { TokenType::Greater },
+                '<' => if self.next_is('=') { TokenType::LessEqual } else { TokenType::Less },
+                '=' => {
+                    if self.next_is('=') {
+                        TokenType::EqualEqual
+                    } else if self.next_is('>') {
+                        TokenType::FatArrow
+                    } else {
+                        TokenType::Equal
+                    }
+                }
+                '!' => if self.next_is('=') { TokenType::BangEqual } else { TokenType::Bang },
+                '&' => {
+                    if !self.next_is('&') {
+                        return Some(Err(ScanError {
+                            message: String::from("Unrecognized input &, did you mean &&"),
+                            span: self.span(),
+                        }));
+                    }
+                    TokenType::AmpAmp
+                }
+                // a single | opens and closes a lambda's parameter list
+                '|' => if self.next_is('|') { TokenType::PipePipe } else { TokenType::Pipe },
+                '"' => return Some(self.match_string()),
+                '\t' | ' ' => continue,
+                '\n' => {
+                    self.line += 1;
+                    self.column = 0;
+                    continue;
+                }
+                _ if char.is_alphabetic() || char == '_' => self.match_alphabetic(),
+                _ => {
+                    return Some(Err(ScanError {
+                        message: format!("Unrecognized input {}", char),
+                        span: self.span(),
+                    }));
+                }
+            };
+            return Some(Ok(self.token(token_type)));
+        }
+    }
+}
 
-                                        }
-                                    }
-                                }
-                                None => {}
-                            }
-                            let token = Token {token_type, lexeme, line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '!' => {
-                            self.advance_char(&mut chars);
-                            let mut token_type = TokenType::Bang;
-                            let mut lexeme = String::from("!");
-                            match chars.peek() {
-                                Some(char) => {
-                                    match char {
-                                        '=' => {
-                                            self.advance_char(&mut chars);
-                                            token_type = TokenType::BangEqual;
-                                            lexeme = String::from("!=");
-                                        }
-                                        _ => {
+impl<'src> Scanner<'src> {
 
-                                        }
-                                    }
-                                }
-                                None => {}
-                            }
-                            let token = Token {token_type, lexeme, line: self.line, column: self.column};
-                            tokens.push(token);
-                        }
-                        '\t'|' ' => {
-                            self.advance_char(&mut chars)
-                        }
-                        '\n' => {
-                            self.advance_char(&mut chars);
-                            self.line += 1;
-                            self.column = 0;
-                        }
-                        _ => {
-                            if char.is_alphabetic() {
-                                let token = self.match_alphabetic(&mut chars);
-                                tokens.push(token);
-                            } else {
-                                return ScanResult::Error(ScanError {
-                                    message: format!("Unrecognized input {}", char),
-                                    line: self.line,
-                                    column: self.column,
-                                });
-                            }
-                        }
-                    };
+    pub fn new(file: usize, source: &'src str) -> Scanner<'src> {
+        Scanner {source, chars: source.chars().peekable(), line: 1, column: 0, start_position: (1, 1), file, byte: 0, start: 0}
+    }
+
+    // right after an error, an Error token for the text it is about
+    pub fn error_token(&self) -> Option<Token<'src>> {
+        if self.start == self.byte {
+            return None;
+        }
+        Some(self.token(TokenType::Error))
+    }
+
+    // an integer or float literal, the first digit is consumed already
+    fn match_number(&mut self, first_digit: char) -> Result<Token<'src>, ScanError> {
+        if first_digit == '0' {
+            let mut ahead = self.chars.clone();
+            match ahead.find(|char| *char != '_') {
+                Some('0'..='9') => {
+                    self.match_digits();
+                    self.finish_number()?;
+                    return Err(ScanError {
+                        message: String::from("Leading zeros in integer literals are not permitted"),
+                        span: self.span(),
+                    });
                 }
-                None => {
-                    break;
+                _ if matches!(self.chars.peek(), Some('x' | 'o' | 'b')) => return self.match_radix_number(),
+                _ => {}
+            }
+        }
+        self.match_digits();
+        self.finish_number()
+    }
+
+    // the fraction and exponent of a float literal like 1.5 or 2e-3, or else an integer
+    // literal's suffix. The fraction needs a digit after the ., and the exponent after the e
+    fn finish_number(&mut self) -> Result<Token<'src>, ScanError> {
+        let mut float = false;
+        let mut ahead = self.chars.clone();
+        if ahead.next() == Some('.') && matches!(ahead.next(), Some('0'..='9')) {
+            float = true;
+            self.advance();
+            self.match_digits();
+        }
+        let mut ahead = self.chars.clone();
+        if let Some('e' | 'E') = ahead.next() {
+            let sign = matches!(ahead.peek(), Some('+' | '-'));
+            if sign {
+                ahead.next();
+            }
+            if matches!(ahead.next(), Some('0'..='9')) {
+                float = true;
+                self.advance();
+                if sign {
+                    self.advance();
                 }
+                self.match_digits();
             }
         }
+        if !float {
+            self.match_suffix()?;
+            return self.int_literal_token();
+        }
+        let lexeme = self.lexeme();
+        if let Some(char) = self.chars.peek() {
+            if char.is_alphanumeric() || *char == '_' {
+                return Err(ScanError {
+                    message: format!("Float literal {} can't have a suffix", lexeme),
+                    span: self.span(),
+                });
+            }
+        }
+        if lexeme.replace('_', "").parse::<f64>().map_or(true, f64::is_infinite) {
+            return Err(ScanError {
+                message: format!("Float literal {} is too big for type float", lexeme),
+                span: self.span(),
+            });
+        }
+        Ok(self.token(TokenType::FloatLiteral))
+    }
 
-        ScanResult::Tokens(tokens)
+    fn match_digits(&mut self) {
+        while let Some('0'..='9' | '_') = self.chars.peek() {
+            self.advance();
+        }
     }
 
-    fn match_number(&mut self, chars: &mut Peekable<Chars>, first_char: char) -> Token {
-        let mut lexeme = format!("{}", first_char);
+    // a hex, octal or binary literal like 0xff, 0o17 or 0b1010_0101, expects the 0 to be
+    // consumed already
+    fn match_radix_number(&mut self) -> Result<Token<'src>, ScanError> {
+        let (radix, base) = match self.chars.peek() {
+            Some('x') => (16, "hex"),
+            Some('o') => (8, "octal"),
+            _ => (2, "binary"),
+        };
+        self.advance();
+        while matches!(self.chars.peek(), Some(char) if char.is_digit(radix) || *char == '_') {
+            self.advance();
+        }
+        let lexeme = self.lexeme();
+        if let Some(digit @ '0'..='9') = self.chars.peek() {
+            let message = format!("Invalid digit {} in {} literal {}", digit, base, lexeme);
+            self.advance();
+            return Err(ScanError {message, span: self.span()});
+        }
+        if lexeme[2..].chars().all(|char| char == '_') {
+            return Err(ScanError {
+                message: format!("The {} literal {} has no digits", base, lexeme),
+                span: self.span(),
+            });
+        }
+        self.match_suffix()?;
+        self.int_literal_token()
+    }
+
+    // integer literals have to fit in 64 bits, the typechecker checks they fit their type
+    fn int_literal_token(&self) -> Result<Token<'src>, ScanError> {
+        match int_literal(self.lexeme()) {
+            Some(int) => Ok(Token {int: Some(int), ..self.token(TokenType::IntLiteral)}),
+            None => Err(ScanError {
+                message: format!("Integer literal {} doesn't fit in 64 bits", self.lexeme()),
+                span: self.span(),
+            }),
+        }
+    }
+
+    // a type suffix right after an integer literal's digits, like the u8 in 5u8, is part of its lexeme
+    fn match_suffix(&mut self) -> Result<(), ScanError> {
+        let digits = self.lexeme();
+        let suffix_start = self.byte;
+        while matches!(self.chars.peek(), Some(char) if char.is_alphanumeric() || *char == '_') {
+            self.advance();
+        }
+        let suffix = &self.source[suffix_start..self.byte];
+        if !suffix.is_empty() && IntType::from_name(suffix).is_none() {
+            return Err(ScanError {
+                message: format!("Invalid suffix {} on integer literal {}, expected one of i8, i16, i32, i64, u8, u16, u32 or u64",
+                    suffix, digits),
+                span: self.span(),
+            });
+        }
+        Ok(())
+    }
+
+    // the lexeme keeps the quotes and escape sequences as written, see unescape for the value.
+    // Expects the opening quote to be consumed already
+    fn match_string(&mut self) -> Result<Token<'src>, ScanError> {
         loop {
-            match chars.peek() {
-                Some(char) => {
-                    match char {
-                        '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9' => {
-                            lexeme.push(*char);
-                            self.advance_char(chars)
-                        }
-                        _ => {
-                            break;
-                        }
+            match self.chars.peek() {
+                Some('"') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') => {
+                    // take the escaped character too, so \" doesn't end the string
+                    self.advance();
+                    if let Some('\n') = self.chars.peek() {
+                        continue;
                     }
+                    self.advance();
                 }
+                Some('\n') => {
+                    self.advance();
+                    self.line += 1;
+                    self.column = 0;
+                }
+                Some(_) => self.advance(),
                 None => {
-                    break;
+                    return Err(ScanError {message: String::from("Unterminated string literal"), span: self.span()});
                 }
             }
-        };
-        Token {token_type: TokenType::IntLiteral, lexeme, line: self.line, column: self.column}
+        }
+
+        if let Err(message) = unescape(self.lexeme()) {
+            return Err(ScanError {message, span: self.span()});
+        }
+        Ok(self.token(TokenType::StringLiteral))
     }
 
-    fn match_alphabetic(&mut self, chars: &mut Peekable<Chars>) -> Token {
-        let mut lexeme = String::from("");
-        loop {
-            if let Some(possible_alphabetic) = chars.peek() {
-                if possible_alphabetic.is_alphanumeric() {
-                    lexeme.push(*possible_alphabetic);
-                    self.advance_char(chars);
-                } else {
-                    break;
+    // block comments nest, so /* */ can comment out code that has comments in it. Expects the
+    // opening / to be consumed already
+    fn skip_block_comment(&mut self) -> Result<(), ScanError> {
+        self.advance();
+        let mut depth = 1;
+        while depth > 0 {
+            match self.chars.peek() {
+                Some('/') => {
+                    self.advance();
+                    if self.next_is('*') {
+                        depth += 1;
+                    }
+                }
+                Some('*') => {
+                    self.advance();
+                    if self.next_is('/') {
+                        depth -= 1;
+                    }
+                }
+                Some('\n') => {
+                    self.advance();
+                    self.line += 1;
+                    self.column = 0;
+                }
+                Some(_) => self.advance(),
+                None => {
+                    return Err(ScanError {message: String::from("Unterminated block comment"), span: self.span()});
                 }
-            } else {
-                break;
             }
-        };
+        }
+        Ok(())
+    }
+
+    // an identifier or keyword, its first character is consumed already
+    fn match_alphabetic(&mut self) -> TokenType {
+        while matches!(self.chars.peek(), Some(char) if char.is_alphanumeric() || *char == '_') {
+            self.advance();
+        }
 
-        let token_type = match &lexeme[..] {
+        match self.lexeme() {
             "else" => TokenType::Else,
+            "fn" => TokenType::Fn,
             "if" => TokenType::If,
+            "let" => TokenType::Let,
+            "mut" => TokenType::Mut,
+            "while" => TokenType::While,
+            "struct" => TokenType::Struct,
+            "enum" => TokenType::Enum,
+            "match" => TokenType::Match,
+            "as" => TokenType::As,
+            "import" => TokenType::Import,
+            "true" => TokenType::True,
+            "false" => TokenType::False,
             _ => TokenType::Identifier,
-        };
-        Token {token_type, lexeme, line: self.line, column: self.column}
+        }
     }
 
-    fn advance_char(&mut self, chars: &mut Peekable<Chars>) {
-        chars.next();
+    // consume the next character if it is expected
+    fn next_is(&mut self, expected: char) -> bool {
+        if self.chars.peek() == Some(&expected) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    fn advance(&mut self) {
+        if let Some(char) = self.chars.next() {
+            self.byte += char.len_utf8();
+        }
         self.column += 1;
     }
-}
\ No newline at end of file
+
+    // the text of the token scanned up to the current character
+    fn lexeme(&self) -> &'src str {
+        &self.source[self.start..self.byte]
+    }
+
+    fn span(&self) -> Span {
+        Span::new(self.file, self.start, self.byte)
+    }
+
+    fn token(&self, token_type: TokenType) -> Token<'src> {
+        let (line, column) = self.start_position;
+        Token {token_type, lexeme: self.lexeme(), line, column, span: self.span(), int: None}
+    }
+}
+
+// value of an integer literal lexeme and the type its suffix names, 0x_ffu8 is (255, Some(U8)).
+// None if the value doesn't fit in 64 bits
+fn int_literal(lexeme: &str) -> Option<(u64, Option<IntType>)> {
+    let (radix, rest) = match lexeme.get(..2) {
+        Some("0x") => (16, &lexeme[2..]),
+        Some("0o") => (8, &lexeme[2..]),
+        Some("0b") => (2, &lexeme[2..]),
+        _ => (10, lexeme),
+    };
+    let end = rest.find(|char: char| !char.is_digit(radix) && char != '_').unwrap_or(rest.len());
+    let digits: String = rest[..end].chars().filter(|char| *char != '_').collect();
+    let value = u64::from_str_radix(&digits, radix).ok()?;
+    Some((value, IntType::from_name(&rest[end..])))
+}
+
+// value of a string literal lexeme: strips the quotes and replaces escape sequences
+pub fn unescape(lexeme: &str) -> Result<String, String> {
+    let mut value = String::new();
+    let mut chars = lexeme[1..lexeme.len() - 1].chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            value.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            Some('"') => value.push('"'),
+            Some('\\') => value.push('\\'),
+            Some('u') => {
+                if chars.next() != Some('{') {
+                    return Err(String::from("Unicode escape must look like \\u{1F600}"));
+                }
+                let mut digits = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(digit) if digit.is_ascii_hexdigit() && digits.len() < 6 => digits.push(digit),
+                        _ => return Err(String::from("Unicode escape must be 1 to 6 hex digits inside braces")),
+                    }
+                }
+                let code_point = u32::from_str_radix(&digits, 16)
+                    .map_err(|_| String::from("Unicode escape must be 1 to 6 hex digits inside braces"))?;
+                match char::from_u32(code_point) {
+                    Some(char) => value.push(char),
+                    None => return Err(format!("Unicode escape {:X} is not a valid character", code_point)),
+                }
+            }
+            Some(other) => return Err(format!("Unknown escape sequence \\{}", other.escape_default())),
+            None => return Err(String::from("String literal ends with an unfinished escape sequence")),
+        }
+    }
+    Ok(value)
+}
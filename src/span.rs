@@ -0,0 +1,64 @@
+// Where a token or a piece of syntax is in the source. Spans are byte offsets, so they stay
+// cheap to copy and combine, and are only turned into lines and columns to show them
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    // the file's index in the order the program's files were scanned, the file the program
+    // starts in is 0
+    pub file: usize,
+    pub start_byte: usize,
+    // one past the last byte
+    pub end_byte: usize,
+}
+
+// lines and columns start at 1, columns count characters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: i32,
+    pub column: i32,
+}
+
+impl Span {
+    pub fn new(file: usize, start_byte: usize, end_byte: usize) -> Span {
+        Span {file, start_byte, end_byte}
+    }
+
+    // the span from the start of this one to the end of end, which comes later in the same file
+    pub fn to(self, end: Span) -> Span {
+        Span {file: self.file, start_byte: self.start_byte, end_byte: end.end_byte.max(self.end_byte)}
+    }
+
+    // an empty span where this one starts
+    pub fn start_point(self) -> Span {
+        Span {file: self.file, start_byte: self.start_byte, end_byte: self.start_byte}
+    }
+
+    // an empty span where this one ends
+    pub fn end_point(self) -> Span {
+        Span {file: self.file, start_byte: self.end_byte, end_byte: self.end_byte}
+    }
+
+    // the position of the span's first character in source, the text of the span's file
+    pub fn start(&self, source: &str) -> Position {
+        position(source, self.start_byte)
+    }
+
+    // the position of the span's last character, where an empty span starts
+    pub fn end(&self, source: &str) -> Position {
+        match source.get(self.start_byte..self.end_byte).and_then(|text| text.char_indices().last()) {
+            Some((offset, _)) => position(source, self.start_byte + offset),
+            None => position(source, self.start_byte),
+        }
+    }
+}
+
+// the position of the character starting at byte in source. A byte past the end is just after
+// the last character
+pub fn position(source: &str, byte: usize) -> Position {
+    let before = &source[..byte.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    Position {
+        line: before.matches('\n').count() as i32 + 1,
+        column: before[line_start..].chars().count() as i32 + 1,
+    }
+}
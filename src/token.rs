@@ -1,22 +1,39 @@
+use crate::{span::Span, typechecker::IntType};
 
-#[derive(Debug)]
-pub struct Token {
-    pub lexeme: String,
+
+// line and column are where the token's first character is, like span.start gives them. The
+// lexeme is the token's text in the source, or a name the module loader gave it
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'src> {
+    pub lexeme: &'src str,
     pub token_type: TokenType,
     pub line: i32,
     pub column: i32,
+    pub span: Span,
+    // for an integer literal, its value and the type its suffix names, 5u8 has (5, Some(U8))
+    pub int: Option<(u64, Option<IntType>)>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TokenType {
     IntLiteral,
+    FloatLiteral,
+    StringLiteral,
     Plus,
     Minus,
     Star,
+    // the wrapping operators +%, -% and *%
+    PlusPercent,
+    MinusPercent,
+    StarPercent,
+    Slash,
+    Percent,
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Greater,
     GreaterEqual,
     Less,
@@ -25,7 +42,30 @@ pub enum TokenType {
     EqualEqual,
     Bang,
     BangEqual,
+    AmpAmp,
+    Pipe,
+    PipePipe,
+    Semicolon,
+    Comma,
+    Dot,
+    Colon,
+    ColonColon,
+    Arrow,
+    FatArrow,
     If,
     Else,
+    Fn,
+    Let,
+    Mut,
+    While,
+    Struct,
+    Enum,
+    Match,
+    As,
+    Import,
+    True,
+    False,
     Identifier,
+    // text the scanner couldn't make a token of, it has reported why
+    Error,
 }
\ No newline at end of file
@@ -1,9 +1,16 @@
-use std::mem;
+use std::{collections::{HashMap, HashSet}, fmt};
 
-use crate::{ast::{Binary, BinaryOp, Expr, Grouping, If, Literal, LiteralType, Unary, UnaryOp}};
+use crate::{exhaustiveness, span::Span, token::Token, ast::{Array, Assign, Binary, BinaryOp, Block, Call, Cast, Expr, Field, Function, Grouping, If, Index, Lambda, Literal, LiteralType, Logical, Match, Path, Pattern, Program, Stmt, StructLiteral, Unary, UnaryOp, Variable, While}};
 
 pub struct TypeError {
     pub message: String,
+    // the code the error is about
+    pub span: Span,
+}
+
+// problems that don't stop the program from compiling, like unreachable match arms
+pub struct Warning {
+    pub message: String,
 }
 
 pub enum TypeResult {
@@ -12,31 +19,458 @@ pub enum TypeResult {
 }
 
 pub struct TypeChecker {
-    pub errors: Vec<TypeError>
+    pub errors: Vec<TypeError>,
+    pub warnings: Vec<Warning>,
+    // innermost scope is last, so shadowing is resolved by searching from the back
+    scopes: Vec<HashMap<String, Binding>>,
+    functions: HashMap<String, FunctionSignature>,
+    structs: HashMap<String, StructDefinition>,
+    enums: HashMap<String, EnumDefinition>,
+    // the lambdas whose bodies are being checked, innermost last
+    lambdas: Vec<LambdaScope>,
+    // what each type variable has been unified with, indexed by the variable's number
+    substitution: Vec<Option<TypeKind>>,
+    // what introduced each type variable, for reporting the ones that are never worked out
+    origins: Vec<(String, Span)>,
+    // variables quantified by let-polymorphic lambdas, they stay unresolved in the lambda's body
+    generalized: HashSet<u32>,
+    // variables already reported as impossible to infer
+    reported: HashSet<u32>,
+    // variables that can only stand for an integer type, like the type of an unsuffixed literal.
+    // The ones nothing else decides become int
+    integers: HashSet<u32>,
+    // checks on types that may only be known once the whole program has been inferred
+    deferred: Vec<Deferred>,
+    // type parameters of the function being checked, annotations in its body can use them
+    type_params: Vec<String>,
+    // the generic function being checked, and the generic functions each of them uses with
+    // the type arguments of every use
+    generic_function: Option<String>,
+    instantiations: HashMap<String, Vec<(String, Vec<TypeKind>)>>,
+    // the module and name of every file of the program, the module is None for the file it
+    // starts in. A span's file is an index into these
+    files: Vec<(Option<String>, String)>,
+}
+
+// functions provided by the runtime, they can't be redefined
+pub const BUILTINS: [&str; 2] = ["print", "len"];
+
+#[derive(Debug, Clone)]
+struct Binding {
+    type_kind: TypeKind,
+    mutable: bool,
+    // type variables of type_kind that every use replaces with fresh ones
+    quantified: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct FunctionSignature {
+    type_params: Vec<String>,
+    params: Vec<TypeKind>,
+    return_type: TypeKind,
+    line: i32,
+    span: Span,
+}
+
+// print, len and == accept several types, the one they get has to be checked after inference
+#[derive(Debug)]
+struct Deferred {
+    requirement: Requirement,
+    type_kind: TypeKind,
+    // the operator or call the requirement comes from
+    location: String,
+    span: Span,
+}
+
+#[derive(Debug)]
+enum Requirement {
+    Printable,
+    HasLength,
+    Comparable,
+    Signed,
+}
+
+// why two types couldn't be unified
+enum Mismatch {
+    Conflict,
+    // a variable would have to contain itself
+    Infinite,
+}
+
+// variables declared in scopes before boundary are outside the lambda, using one captures it
+#[derive(Debug)]
+struct LambdaScope {
+    boundary: usize,
+    captures: Vec<(String, TypeKind)>,
 }
 
-#[derive(Debug, Copy, Clone)]
+// fields in declaration order, which is also the order they are laid out in
+#[derive(Debug, Clone)]
+struct StructDefinition {
+    type_params: Vec<String>,
+    fields: Vec<(String, TypeKind)>,
+}
+
+impl StructDefinition {
+    fn field_type(&self, field_name: &str, arguments: &[TypeKind]) -> Option<TypeKind> {
+        self.fields.iter()
+            .find(|(name, _)| name == field_name)
+            .map(|(_, field_type)| field_type.substitute_params(&param_mapping(&self.type_params, arguments)))
+    }
+}
+
+// variant names and payload types in declaration order, a variant's tag is its index
+#[derive(Debug, Clone)]
+pub struct EnumDefinition {
+    pub type_params: Vec<String>,
+    pub variants: Vec<(String, Vec<TypeKind>)>,
+}
+
+impl EnumDefinition {
+    // the payload types of a variant of the enum with the given type arguments
+    pub fn payload(&self, variant: usize, arguments: &[TypeKind]) -> Vec<TypeKind> {
+        let mapping = param_mapping(&self.type_params, arguments);
+        self.variants[variant].1.iter().map(|type_kind| type_kind.substitute_params(&mapping)).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TypeKind {
-    Int,
+    Int(IntType),
+    // 64 bit IEEE 754, kept in a word like every other scalar
+    Float,
     Bool,
+    Str,
+    // element type and length, the elements are stored inline
+    Array(Box<TypeKind>, usize),
+    // a struct or enum, looked up by name in the struct and enum tables, with the types
+    // given for its type parameters
+    Named(String, Vec<TypeKind>),
+    // parameter types and return type. Named functions and lambdas both have this type
+    Function(Vec<TypeKind>, Box<TypeKind>),
+    // the type of expressions evaluated only for their effect, like loops and assignments
+    Unit,
+    // a type inference hasn't worked out yet, numbered in the order they were introduced
+    Var(u32),
+    // a type parameter of the generic function, struct or enum it appears in. It only
+    // matches itself, so a generic body is checked once for every type it can be used at
+    Param(String),
     Error
 }
 
+impl TypeKind {
+    // replace every variable mapping has a type for
+    pub fn substitute(&self, mapping: &HashMap<u32, TypeKind>) -> TypeKind {
+        match self {
+            TypeKind::Var(variable) => mapping.get(variable).cloned().unwrap_or(TypeKind::Var(*variable)),
+            TypeKind::Array(element_type, length) => TypeKind::Array(Box::new(element_type.substitute(mapping)), *length),
+            TypeKind::Function(params, return_type) => TypeKind::Function(
+                params.iter().map(|param| param.substitute(mapping)).collect(),
+                Box::new(return_type.substitute(mapping)),
+            ),
+            TypeKind::Named(name, arguments) => TypeKind::Named(
+                name.clone(),
+                arguments.iter().map(|argument| argument.substitute(mapping)).collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    // replace every type parameter mapping has a type for
+    pub fn substitute_params(&self, mapping: &HashMap<String, TypeKind>) -> TypeKind {
+        match self {
+            TypeKind::Param(name) => mapping.get(name).cloned().unwrap_or(TypeKind::Param(name.clone())),
+            TypeKind::Array(element_type, length) => TypeKind::Array(Box::new(element_type.substitute_params(mapping)), *length),
+            TypeKind::Function(params, return_type) => TypeKind::Function(
+                params.iter().map(|param| param.substitute_params(mapping)).collect(),
+                Box::new(return_type.substitute_params(mapping)),
+            ),
+            TypeKind::Named(name, arguments) => TypeKind::Named(
+                name.clone(),
+                arguments.iter().map(|argument| argument.substitute_params(mapping)).collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+}
+
+// every integer is held in a 64 bit word, sign or zero extended from its width, so values of
+// different widths compare and print the same way
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntType {
+    // the types a literal suffix or type annotation can name, int is another name for i64
+    pub fn from_name(name: &str) -> Option<IntType> {
+        match name {
+            "i8" => Some(IntType::I8),
+            "i16" => Some(IntType::I16),
+            "i32" => Some(IntType::I32),
+            "i64" => Some(IntType::I64),
+            "u8" => Some(IntType::U8),
+            "u16" => Some(IntType::U16),
+            "u32" => Some(IntType::U32),
+            "u64" => Some(IntType::U64),
+            _ => None,
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            IntType::I8 | IntType::U8 => 8,
+            IntType::I16 | IntType::U16 => 16,
+            IntType::I32 | IntType::U32 => 32,
+            IntType::I64 | IntType::U64 => 64,
+        }
+    }
+
+    pub fn signed(self) -> bool {
+        matches!(self, IntType::I8 | IntType::I16 | IntType::I32 | IntType::I64)
+    }
+
+    pub fn min(self) -> i128 {
+        if self.signed() { -(1 << (self.bits() - 1)) } else { 0 }
+    }
+
+    pub fn max(self) -> i128 {
+        if self.signed() { (1 << (self.bits() - 1)) - 1 } else { (1 << self.bits()) - 1 }
+    }
+
+    // the 64 bit word holding the low bits of value as a number of this type
+    pub fn wrap(self, value: i64) -> i64 {
+        let unused = 64 - self.bits();
+        if self.signed() {
+            (value << unused) >> unused
+        } else {
+            ((value as u64) << unused >> unused) as i64
+        }
+    }
+}
+
+// pairs a generic declaration's type parameters with the types given for them
+pub fn param_mapping(params: &[String], arguments: &[TypeKind]) -> HashMap<String, TypeKind> {
+    params.iter().cloned().zip(arguments.iter().cloned()).collect()
+}
+
+// types are shown the way they are written in programs
+impl fmt::Display for TypeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeKind::Int(int_type) => write!(f, "{}", int_type),
+            TypeKind::Float => write!(f, "float"),
+            TypeKind::Bool => write!(f, "bool"),
+            TypeKind::Str => write!(f, "str"),
+            TypeKind::Array(element_type, length) => write!(f, "[{}; {}]", element_type, length),
+            TypeKind::Named(name, arguments) if arguments.is_empty() => write!(f, "{}", name),
+            TypeKind::Named(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+                write!(f, "{}<{}>", name, arguments.join(", "))
+            }
+            TypeKind::Function(params, return_type) => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "fn({}) -> {}", params.join(", "), return_type)
+            }
+            TypeKind::Unit => write!(f, "()"),
+            TypeKind::Var(_) => write!(f, "_"),
+            TypeKind::Param(name) => write!(f, "{}", name),
+            TypeKind::Error => write!(f, "{{error}}"),
+        }
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            IntType::I8 => "i8",
+            IntType::I16 => "i16",
+            IntType::I32 => "i32",
+            IntType::I64 => "int",
+            IntType::U8 => "u8",
+            IntType::U16 => "u16",
+            IntType::U32 => "u32",
+            IntType::U64 => "u64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 impl TypeChecker {
-    pub fn new() -> TypeChecker {
-        TypeChecker { errors: Vec::new() }
+    pub fn new(files: Vec<(Option<String>, String)>) -> TypeChecker {
+        TypeChecker { errors: Vec::new(), warnings: Vec::new(), scopes: Vec::new(), functions: HashMap::new(),
+            structs: HashMap::new(), enums: HashMap::new(), lambdas: Vec::new(), substitution: Vec::new(), origins: Vec::new(),
+            generalized: HashSet::new(), reported: HashSet::new(), integers: HashSet::new(), deferred: Vec::new(), type_params: Vec::new(),
+            generic_function: None, instantiations: HashMap::new(), files }
     }
 
-    pub fn typecheck(&mut self, expr: &mut Expr) -> TypeResult {
-        self.type_expr(expr);
-        if self.errors.len() > 0 {
+    pub fn typecheck(&mut self, program: &mut Program) -> TypeResult {
+        // structs and enums can refer to each other in any order too. Declared types are
+        // rewritten in the tree so their type parameters are Params from here on
+        // where each type and function is first defined
+        let mut types: HashMap<&str, &Token> = HashMap::new();
+        let mut functions: HashMap<&str, &Token> = HashMap::new();
+        for struct_decl in &mut program.structs {
+            let name = struct_decl.name.lexeme;
+            if let Some(first) = types.get(name) {
+                self.errors.push(TypeError {message:
+                    format!("Struct {} is defined more than once, {} and {}", name, self.location(first), self.location(struct_decl.name)),
+                    span: struct_decl.name.span,
+                });
+                continue;
+            }
+            types.insert(name, struct_decl.name);
+            let type_params = self.declare_type_params(&struct_decl.type_params, &format!("struct {}", name));
+            let mut fields: Vec<(String, TypeKind)> = Vec::new();
+            for field in &mut struct_decl.fields {
+                field.type_kind = bind_params(&field.type_kind, &type_params);
+                if fields.iter().any(|(field_name, _)| *field_name == field.name.lexeme) {
+                    self.errors.push(TypeError {message:
+                        format!("Field {} of struct {} is declared more than once", field.name.lexeme, name),
+                        span: field.name.span,
+                    });
+                    continue;
+                }
+                fields.push((field.name.lexeme.to_string(), field.type_kind.clone()));
+            }
+            self.structs.insert(name.to_string(), StructDefinition {type_params, fields});
+        }
+        for enum_decl in &mut program.enums {
+            let name = enum_decl.name.lexeme;
+            if let Some(first) = types.get(name) {
+                self.errors.push(TypeError {message:
+                    format!("Type {} is defined more than once, {} and {}", name, self.location(first), self.location(enum_decl.name)),
+                    span: enum_decl.name.span,
+                });
+                continue;
+            }
+            types.insert(name, enum_decl.name);
+            let type_params = self.declare_type_params(&enum_decl.type_params, &format!("enum {}", name));
+            let mut variants: Vec<(String, Vec<TypeKind>)> = Vec::new();
+            for variant in &mut enum_decl.variants {
+                variant.payload = variant.payload.iter().map(|type_kind| bind_params(type_kind, &type_params)).collect();
+                if variants.iter().any(|(variant_name, _)| *variant_name == variant.name.lexeme) {
+                    self.errors.push(TypeError {message:
+                        format!("Variant {} of enum {} is declared more than once", variant.name.lexeme, name),
+                        span: variant.name.span,
+                    });
+                    continue;
+                }
+                variants.push((variant.name.lexeme.to_string(), variant.payload.clone()));
+            }
+            self.enums.insert(name.to_string(), EnumDefinition {type_params, variants});
+        }
+
+        for struct_decl in &mut program.structs {
+            for field in &struct_decl.fields {
+                self.check_type_exists(&field.type_kind, field.name);
+            }
+            // a struct can only hold itself through an enum, which has variants that end the chain
+            let mut visited = Vec::new();
+            if struct_decl.fields.iter().any(|field| self.contains_type(struct_decl.name.lexeme, &field.type_kind, false, &mut visited)) {
+                self.errors.push(TypeError {message:
+                    format!("Struct {} at line {} contains itself without an enum in between, so it can't have any values",
+                    struct_decl.name.lexeme, struct_decl.name.line
+                ), span: struct_decl.name.span});
+            }
+            let mut visited = Vec::new();
+            struct_decl.recursive = struct_decl.fields.iter()
+                .any(|field| self.contains_type(struct_decl.name.lexeme, &field.type_kind, true, &mut visited));
+        }
+        for enum_decl in &mut program.enums {
+            for variant in &enum_decl.variants {
+                for type_kind in &variant.payload {
+                    self.check_type_exists(type_kind, variant.name);
+                }
+            }
+            let mut visited = Vec::new();
+            enum_decl.recursive = enum_decl.variants.iter()
+                .flat_map(|variant| variant.payload.iter())
+                .any(|type_kind| self.contains_type(enum_decl.name.lexeme, type_kind, true, &mut visited));
+        }
+
+        // collect every signature first, so functions can call each other in any order
+        for function in &mut program.functions {
+            let name = function.name.lexeme;
+            if BUILTINS.contains(&name) {
+                self.errors.push(TypeError {message:
+                    format!("Function {} at line {} has the same name as a builtin function", name, function.name.line),
+                    span: function.name.span,
+                });
+                continue;
+            }
+            if let Some(first) = functions.get(name) {
+                self.errors.push(TypeError {message:
+                    format!("Function {} is defined more than once, {} and {}", name, self.location(first), self.location(function.name)),
+                    span: function.name.span,
+                });
+                continue;
+            }
+            functions.insert(name, function.name);
+            let type_params = self.declare_type_params(&function.type_params, &format!("function {}", name));
+            for param in &mut function.params {
+                param.type_kind = bind_params(&param.type_kind, &type_params);
+                self.check_type_exists(&param.type_kind, param.name);
+            }
+            function.return_type = bind_params(&function.return_type, &type_params);
+            self.check_type_exists(&function.return_type, function.name);
+            let params = function.params.iter().map(|param| param.type_kind.clone()).collect();
+            let signature = FunctionSignature {type_params, params, return_type: function.return_type.clone(), line: function.name.line, span: function.name.span};
+            self.functions.insert(name.to_string(), signature);
+        }
+
+        for function in &mut program.functions {
+            self.type_function(function);
+        }
+
+        match &mut program.main {
+            Some(expr) => {
+                self.type_expr(expr);
+            }
+            None => {
+                match self.functions.get("main") {
+                    Some(signature) if !signature.type_params.is_empty() => {
+                        self.errors.push(TypeError {message: String::from("Function main must not have type parameters"), span: signature.span});
+                    }
+                    Some(signature) if signature.params.is_empty() => {}
+                    Some(signature) => {
+                        self.errors.push(TypeError {message: String::from("Function main must not take any parameters"), span: signature.span});
+                    }
+                    None => {
+                        self.errors.push(TypeError {message:
+                            String::from("Program needs either a trailing expression or a function named main"),
+                            span: Span::default(),
+                        });
+                    }
+                }
+            }
+        }
+
+        self.default_integers();
+        self.check_deferred();
+        self.check_instantiation_cycles();
+        for function in &mut program.functions {
+            self.finish_expr(&mut function.body);
+        }
+        if let Some(expr) = &mut program.main {
+            self.finish_expr(expr);
+        }
+
+        if !self.errors.is_empty() {
             TypeResult::Error
         } else {
             TypeResult::Success
         }
     }
 
-    fn type_expr<'t>(&mut self, expr: &'t mut Expr) -> TypeKind {
+    fn type_expr(&mut self, expr: &mut Expr) -> TypeKind {
         match expr {
             Expr::Binary(binary) => {
                 self.type_binary(binary)
@@ -51,132 +485,1519 @@ impl TypeChecker {
                 self.type_grouping(grouping)
             }
             Expr::If(if_expr) => self.type_if(if_expr),
+            Expr::Variable(variable) => self.type_variable(variable),
+            Expr::Block(block) => self.type_block(block),
+            Expr::Call(call) => self.type_call(call),
+            Expr::Logical(logical) => self.type_logical(logical),
+            Expr::While(while_expr) => self.type_while(while_expr),
+            Expr::Assign(assign) => self.type_assign(assign),
+            Expr::Array(array) => self.type_array(array),
+            Expr::Index(index) => self.type_index(index),
+            Expr::StructLiteral(struct_literal) => self.type_struct_literal(struct_literal),
+            Expr::Field(field) => self.type_field(field),
+            Expr::Path(path) => self.type_path(path),
+            Expr::Match(match_expr) => self.type_match(match_expr),
+            Expr::Lambda(lambda) => self.type_lambda(lambda, None),
+            Expr::Cast(cast) => self.type_cast(cast),
         }
     }
 
-    fn type_binary<'t>(&mut self, binary: &'t mut Binary) -> TypeKind {
-        let left_kind = self.type_expr(binary.left.as_mut());
-        let right_kind = self.type_expr(binary.right.as_mut());
+    // report types naming a struct or enum that doesn't exist, token is where the type was written
+    fn check_type_exists(&mut self, type_kind: &TypeKind, token: &Token) {
+        match type_kind {
+            TypeKind::Array(element_type, _) => self.check_type_exists(element_type, token),
+            TypeKind::Function(params, return_type) => {
+                for param in params {
+                    self.check_type_exists(param, token);
+                }
+                self.check_type_exists(return_type, token);
+            }
+            TypeKind::Named(name, arguments) => {
+                let type_params = match (self.structs.get(name), self.enums.get(name)) {
+                    (Some(definition), _) => definition.type_params.len(),
+                    (_, Some(definition)) => definition.type_params.len(),
+                    _ => {
+                        self.errors.push(TypeError {message:
+                            format!("Unknown type {} at line {}, column {}", name, token.line, token.column),
+                            span: token.span,
+                        });
+                        return;
+                    }
+                };
+                if type_params != arguments.len() {
+                    self.errors.push(TypeError {message:
+                        format!("Type {} at line {}, column {} takes {} type arguments but got {}",
+                        name, token.line, token.column, type_params, arguments.len()
+                    ), span: token.span});
+                }
+                for argument in arguments {
+                    self.check_type_exists(argument, token);
+                }
+            }
+            _ => {}
+        }
+    }
 
-        let type_kind = match (&binary.operation, left_kind, right_kind) {
-            (BinaryOp::Add, TypeKind::Int, TypeKind::Int) 
-            | (BinaryOp::Minus, TypeKind::Int, TypeKind::Int) 
-            | (BinaryOp::Times, TypeKind::Int, TypeKind::Int) => {
-                TypeKind::Int
+    // the line and file of a token, for messages about names that could be in any file
+    fn location(&self, token: &Token) -> String {
+        match self.files.get(token.span.file) {
+            Some((_, file)) => format!("at line {} of {}", token.line, file),
+            None => format!("at line {}", token.line),
+        }
+    }
+
+    // the names of a generic declaration's type parameters, reporting repeated ones
+    fn declare_type_params(&mut self, tokens: &[&Token], declaration: &str) -> Vec<String> {
+        let mut type_params: Vec<String> = Vec::new();
+        for token in tokens {
+            if type_params.iter().any(|param| param == token.lexeme) {
+                self.errors.push(TypeError {message:
+                    format!("Type parameter {} of {} is declared more than once, at line {}", token.lexeme, declaration, token.line),
+                    span: token.span,
+                });
             }
-            (BinaryOp::Greater, TypeKind::Int, TypeKind::Int) 
-            | (BinaryOp::GreaterEqual, TypeKind::Int, TypeKind::Int) 
-            | (BinaryOp::Less, TypeKind::Int, TypeKind::Int) 
-            | (BinaryOp::LessEqual, TypeKind::Int, TypeKind::Int) => {
-                TypeKind::Bool
+            type_params.push(token.lexeme.to_string());
+        }
+        type_params
+    }
+
+    // fresh variables standing for a generic declaration's type parameters at one of its uses
+    fn fresh_arguments(&mut self, type_params: &[String], declaration: &str, token: &Token) -> Vec<TypeKind> {
+        type_params.iter()
+            .map(|param| self.fresh_variable(format!("type parameter {} of {} at line {}, column {}",
+                param, declaration, token.line, token.column), token.span))
+            .collect()
+    }
+
+    // true if a value of type_kind holds the target struct or enum by value, somewhere inside it.
+    // The payloads of enums are only looked inside when through_enums is set
+    fn contains_type(&self, target: &str, type_kind: &TypeKind, through_enums: bool, visited: &mut Vec<String>) -> bool {
+        match type_kind {
+            TypeKind::Array(element_type, _) => self.contains_type(target, element_type, through_enums, visited),
+            TypeKind::Named(name, arguments) => {
+                if name == target {
+                    return true;
+                }
+                if visited.contains(name) {
+                    return false;
+                }
+                visited.push(name.clone());
+                // Box<A> holds an A when Box holds its type parameter
+                let components: Vec<TypeKind> = match (self.structs.get(name), self.enums.get(name)) {
+                    (Some(definition), _) => definition.fields.iter()
+                        .map(|(_, field_type)| field_type.substitute_params(&param_mapping(&definition.type_params, arguments)))
+                        .collect(),
+                    (_, Some(definition)) if through_enums => (0..definition.variants.len())
+                        .flat_map(|variant| definition.payload(variant, arguments))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                components.iter().any(|component| self.contains_type(target, component, through_enums, visited))
             }
-            (BinaryOp::BangEqual, _, _) 
-            | (BinaryOp::EqualEqual, _, _) => {
-                match (left_kind, right_kind) {
-                    (TypeKind::Error, _) => {
-                        TypeKind::Error
-                    }
-                    (_, TypeKind::Error) => {
-                        TypeKind::Error
-                    }
-                    _ => {
-                        if mem::discriminant(&left_kind) == mem::discriminant(&right_kind) {
-                            TypeKind::Bool
-                        } else {
-                            self.errors.push(TypeError {message: 
-                                format!("Type error for {:?}: Expected LHS ({:?}) to match RHS ({:?}) for {:?}",
-                                binary.token, left_kind, right_kind, binary.operation
-                            )});
-                            TypeKind::Error
+            _ => false,
+        }
+    }
+
+    fn fresh_variable(&mut self, origin: String, span: Span) -> TypeKind {
+        self.substitution.push(None);
+        self.origins.push((origin, span));
+        TypeKind::Var(self.substitution.len() as u32 - 1)
+    }
+
+    // follow bound variables until reaching a type that isn't one
+    fn shallow_resolve(&self, type_kind: &TypeKind) -> TypeKind {
+        match type_kind {
+            TypeKind::Var(variable) => match &self.substitution[*variable as usize] {
+                Some(bound) => self.shallow_resolve(bound),
+                None => type_kind.clone(),
+            },
+            _ => type_kind.clone(),
+        }
+    }
+
+    // the type with every bound variable inside it replaced by what it is bound to
+    fn resolve(&self, type_kind: &TypeKind) -> TypeKind {
+        match self.shallow_resolve(type_kind) {
+            TypeKind::Array(element_type, length) => TypeKind::Array(Box::new(self.resolve(&element_type)), length),
+            TypeKind::Function(params, return_type) => TypeKind::Function(
+                params.iter().map(|param| self.resolve(param)).collect(),
+                Box::new(self.resolve(&return_type)),
+            ),
+            TypeKind::Named(name, arguments) => TypeKind::Named(
+                name,
+                arguments.iter().map(|argument| self.resolve(argument)).collect(),
+            ),
+            type_kind => type_kind,
+        }
+    }
+
+    fn occurs(&self, variable: u32, type_kind: &TypeKind) -> bool {
+        match self.shallow_resolve(type_kind) {
+            TypeKind::Var(other) => other == variable,
+            TypeKind::Array(element_type, _) => self.occurs(variable, &element_type),
+            TypeKind::Function(params, return_type) => {
+                params.iter().any(|param| self.occurs(variable, param)) || self.occurs(variable, &return_type)
+            }
+            TypeKind::Named(_, arguments) => arguments.iter().any(|argument| self.occurs(variable, argument)),
+            _ => false,
+        }
+    }
+
+    // make two types equal by binding the variables in them. Error unifies with anything,
+    // so a mistake is only reported where it is made
+    fn unify(&mut self, left: &TypeKind, right: &TypeKind) -> Result<(), Mismatch> {
+        let left = self.shallow_resolve(left);
+        let right = self.shallow_resolve(right);
+        match (&left, &right) {
+            (TypeKind::Error, _) | (_, TypeKind::Error) => Ok(()),
+            (TypeKind::Var(a), TypeKind::Var(b)) if a == b => Ok(()),
+            (TypeKind::Var(variable), other) | (other, TypeKind::Var(variable)) => {
+                if self.occurs(*variable, other) {
+                    return Err(Mismatch::Infinite);
+                }
+                if self.integers.contains(variable) {
+                    match other {
+                        TypeKind::Var(other) => {
+                            self.integers.insert(*other);
                         }
+                        TypeKind::Int(_) => {}
+                        _ => return Err(Mismatch::Conflict),
                     }
-                }    
+                }
+                self.substitution[*variable as usize] = Some(other.clone());
+                Ok(())
+            }
+            (TypeKind::Array(a, a_length), TypeKind::Array(b, b_length)) if a_length == b_length => self.unify(a, b),
+            (TypeKind::Function(a_params, a_return), TypeKind::Function(b_params, b_return))
+                if a_params.len() == b_params.len() => {
+                for (a, b) in a_params.iter().zip(b_params.iter()) {
+                    self.unify(a, b)?;
+                }
+                self.unify(a_return, b_return)
+            }
+            (TypeKind::Named(a, a_arguments), TypeKind::Named(b, b_arguments))
+                if a == b && a_arguments.len() == b_arguments.len() => {
+                for (a, b) in a_arguments.iter().zip(b_arguments.iter()) {
+                    self.unify(a, b)?;
+                }
+                Ok(())
+            }
+            _ if left == right => Ok(()),
+            _ => Err(Mismatch::Conflict),
+        }
+    }
+
+    // unify the type something was found to have with the type where it is used expects,
+    // reporting both types and where each of them came from at span, the found code, when they conflict
+    fn expect_type(&mut self, found: &TypeKind, found_origin: &str, expected: &TypeKind, expected_origin: &str, span: Span) -> bool {
+        let mismatch = match self.unify(found, expected) {
+            Ok(()) => return true,
+            Err(mismatch) => mismatch,
+        };
+        let found = self.show(found);
+        let expected = self.show(expected);
+        let message = match mismatch {
+            Mismatch::Conflict => format!("Type mismatch between {} from {} and {} from {}",
+                found, found_origin, expected, expected_origin),
+            Mismatch::Infinite => format!("Infinite type: {} from {} would have to contain itself to match {} from {}",
+                found, found_origin, expected, expected_origin),
+        };
+        self.errors.push(TypeError {message, span});
+        false
+    }
+
+    // a type for error messages, an integer variable is known to be some integer type
+    fn show(&self, type_kind: &TypeKind) -> String {
+        match self.resolve(type_kind) {
+            TypeKind::Var(variable) if self.integers.contains(&variable) => String::from("an integer type"),
+            type_kind => type_kind.to_string(),
+        }
+    }
+
+    // the variables of a let-bound lambda's type that nothing outside it constrains,
+    // so every use of the name can pick its own types for them
+    fn generalize(&self, type_kind: &TypeKind) -> Vec<u32> {
+        let mut variables = Vec::new();
+        free_variables(&self.resolve(type_kind), &mut variables);
+
+        let mut fixed = Vec::new();
+        for binding in self.scopes.iter().flat_map(|scope| scope.values()) {
+            let mut binding_variables = Vec::new();
+            free_variables(&self.resolve(&binding.type_kind), &mut binding_variables);
+            fixed.extend(binding_variables.into_iter().filter(|variable| !binding.quantified.contains(variable)));
+        }
+        // print(x) in the body decides x's type from how the lambda is called, so it stays monomorphic
+        for deferred in &self.deferred {
+            free_variables(&self.resolve(&deferred.type_kind), &mut fixed);
+        }
+        // integer variables stay monomorphic too, they default to int if nothing else decides them
+        variables.retain(|variable| !fixed.contains(variable) && !self.integers.contains(variable));
+        variables
+    }
+
+    // the binding's type with fresh variables for its quantified ones, and those fresh variables
+    fn instantiate(&mut self, binding: &Binding, origin: &str, span: Span) -> (TypeKind, Vec<TypeKind>) {
+        let instance: Vec<TypeKind> = binding.quantified.iter()
+            .map(|_| self.fresh_variable(String::from(origin), span))
+            .collect();
+        let mapping: HashMap<u32, TypeKind> = binding.quantified.iter().copied().zip(instance.iter().cloned()).collect();
+        (self.resolve(&binding.type_kind).substitute(&mapping), instance)
+    }
+
+    // mark a type as one of the integer types, reporting it when it can't be one
+    fn expect_integer(&mut self, found: &TypeKind, found_origin: &str, expected_origin: &str, span: Span) -> bool {
+        match self.shallow_resolve(found) {
+            TypeKind::Var(variable) => {
+                self.integers.insert(variable);
+                true
+            }
+            TypeKind::Int(_) | TypeKind::Error => true,
+            found => {
+                self.errors.push(TypeError {message:
+                    format!("Type mismatch between {} from {} and an integer type from {}", found, found_origin, expected_origin),
+                    span,
+                });
+                false
+            }
+        }
+    }
+
+    fn is_float(&self, type_kind: &TypeKind) -> bool {
+        self.shallow_resolve(type_kind) == TypeKind::Float
+    }
+
+    // the type of an integer literal is the one its suffix names, or an integer variable
+    fn integer_literal_type(&mut self, token: &Token, suffix: Option<IntType>) -> TypeKind {
+        match suffix {
+            Some(int_type) => TypeKind::Int(int_type),
+            None => {
+                let variable = self.fresh_variable(format!("the literal {} at line {}, column {}", token.lexeme, token.line, token.column), token.span);
+                if let TypeKind::Var(variable) = variable {
+                    self.integers.insert(variable);
+                }
+                variable
+            }
+        }
+    }
+
+    // integer variables nothing decided a type for are int, like they were before integers had widths
+    fn default_integers(&mut self) {
+        let mut integers: Vec<u32> = self.integers.iter().copied().collect();
+        integers.sort();
+        for variable in integers {
+            if let TypeKind::Var(_) = self.shallow_resolve(&TypeKind::Var(variable)) {
+                let _ = self.unify(&TypeKind::Var(variable), &TypeKind::Int(IntType::I64));
+            }
+        }
+    }
+
+    fn type_binary(&mut self, binary: &mut Binary) -> TypeKind {
+        let left_kind = self.type_expr(binary.left.as_mut());
+        let right_kind = self.type_expr(binary.right.as_mut());
+        let location = format!("{} at line {}, column {}", binary.token.lexeme, binary.token.line, binary.token.column);
+
+        let type_kind = match binary.operation {
+            BinaryOp::BangEqual | BinaryOp::EqualEqual => {
+                self.expect_type(&right_kind, &describe(&binary.right), &left_kind, &describe(&binary.left), binary.right.span());
+                self.deferred.push(Deferred {requirement: Requirement::Comparable, type_kind: left_kind, location, span: binary.token.span});
+                TypeKind::Bool
+            }
+            // float arithmetic when either operand is known to be a float, % and the wrapping
+            // operators are only for integers
+            BinaryOp::Add | BinaryOp::Minus | BinaryOp::Times | BinaryOp::Divide
+            | BinaryOp::Greater | BinaryOp::GreaterEqual | BinaryOp::Less | BinaryOp::LessEqual
+                if self.is_float(&left_kind) || self.is_float(&right_kind) => {
+                self.expect_type(&right_kind, &describe(&binary.right), &left_kind, &describe(&binary.left), binary.right.span());
+                match binary.operation {
+                    BinaryOp::Greater | BinaryOp::GreaterEqual | BinaryOp::Less | BinaryOp::LessEqual => TypeKind::Bool,
+                    _ => left_kind,
+                }
             }
             _ => {
-                match (left_kind, right_kind) {
-                    (TypeKind::Error, _) => {},
-                    (_, TypeKind::Error) => {},
-                    _ => {
-                        // report error if this is new error and not propogated from child type
-                        self.errors.push(TypeError {message: 
-                            format!("Type error for {:?}: illegal operation {:?} on {:?} and {:?}",
-                            binary.token, binary.operation, left_kind, right_kind
-                        )});
-                    }
+                // both operands have the same integer type, nothing is converted implicitly
+                let operands = format!("the operands of {}", location);
+                if self.expect_integer(&left_kind, &describe(&binary.left), &operands, binary.left.span()) {
+                    self.expect_type(&right_kind, &describe(&binary.right), &left_kind, &describe(&binary.left), binary.right.span());
+                } else {
+                    self.expect_integer(&right_kind, &describe(&binary.right), &operands, binary.right.span());
+                }
+                match binary.operation {
+                    BinaryOp::Greater | BinaryOp::GreaterEqual | BinaryOp::Less | BinaryOp::LessEqual => TypeKind::Bool,
+                    _ => left_kind,
                 }
-                TypeKind::Error
             }
         };
-        binary.type_kind = Some(type_kind);
+        binary.type_kind = Some(type_kind.clone());
         type_kind
+    }
 
+    fn type_logical(&mut self, logical: &mut Logical) -> TypeKind {
+        let left_kind = self.type_expr(logical.left.as_mut());
+        let right_kind = self.type_expr(logical.right.as_mut());
+
+        let operands = format!("the operands of {} at line {}, column {}",
+            logical.token.lexeme, logical.token.line, logical.token.column);
+        self.expect_type(&left_kind, &describe(&logical.left), &TypeKind::Bool, &operands, logical.left.span());
+        self.expect_type(&right_kind, &describe(&logical.right), &TypeKind::Bool, &operands, logical.right.span());
+
+        let type_kind = TypeKind::Bool;
+        logical.type_kind = Some(type_kind.clone());
+        type_kind
     }
 
-    fn type_unary<'t>(&mut self, unary: &'t mut Unary) -> TypeKind {
+    fn type_unary(&mut self, unary: &mut Unary) -> TypeKind {
         let right_kind = self.type_expr(unary.right.as_mut());
-        let type_kind = match (&unary.operation, right_kind) {
-            (UnaryOp::Minus, TypeKind::Int) => {
-                TypeKind::Int
-            }
-            _ => {
-                match right_kind {
-                    TypeKind::Error => {},
-                    _ => {
-                        // report error if this is new error and not propogated from child type
-                        self.errors.push(TypeError {message: 
-                            format!("Type error for {:?}: illegal operation {:?} on {:?}",
-                            unary.token, unary.operation, right_kind 
-                        )});
-                    }
+        let operand = format!("the operand of {} at line {}, column {}", unary.token.lexeme, unary.token.line, unary.token.column);
+        let type_kind = match unary.operation {
+            UnaryOp::Minus if self.is_float(&right_kind) => right_kind,
+            UnaryOp::Minus => {
+                if self.expect_integer(&right_kind, &describe(&unary.right), &operand, unary.right.span()) {
+                    self.deferred.push(Deferred {requirement: Requirement::Signed, type_kind: right_kind.clone(),
+                        location: format!("- at line {}, column {}", unary.token.line, unary.token.column), span: unary.token.span});
                 }
-                TypeKind::Error
+                right_kind
+            }
+            // negating an unsigned value wraps around like 0 -% value does
+            UnaryOp::WrappingMinus => {
+                self.expect_integer(&right_kind, &describe(&unary.right), &operand, unary.right.span());
+                right_kind
+            }
+            UnaryOp::Not => {
+                self.expect_type(&right_kind, &describe(&unary.right), &TypeKind::Bool, &operand, unary.right.span());
+                TypeKind::Bool
             }
         };
-        unary.type_kind = Some(type_kind);
+        unary.type_kind = Some(type_kind.clone());
         type_kind
     }
 
-    fn type_literal<'t>(&mut self, literal: &'t mut Literal) -> TypeKind {
+    // integers convert to every integer type, keeping the low bits and extending them by
+    // the target's signedness. bool converts to 0 or 1. Integers and floats convert to each
+    // other, a float is truncated toward zero
+    fn type_cast(&mut self, cast: &mut Cast) -> TypeKind {
+        let value_type = self.type_expr(cast.expr.as_mut());
+        let location = format!("as at line {}, column {}", cast.token.line, cast.token.column);
+        match &cast.target {
+            TypeKind::Int(_) => {
+                if !matches!(self.shallow_resolve(&value_type), TypeKind::Bool | TypeKind::Float) {
+                    self.expect_integer(&value_type, &describe(&cast.expr), &format!("the value converted by {}", location), cast.expr.span());
+                }
+            }
+            TypeKind::Float => {
+                if !self.is_float(&value_type) {
+                    self.expect_integer(&value_type, &describe(&cast.expr), &format!("the value converted by {}", location), cast.expr.span());
+                }
+            }
+            target => {
+                self.errors.push(TypeError {message:
+                    format!("Type error: {} can only convert to integer types and float, not {}", location, target),
+                    span: cast.token.span,
+                });
+            }
+        }
+        let type_kind = cast.target.clone();
+        cast.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_literal(&mut self, literal: &mut Literal) -> TypeKind {
         let type_kind = match literal.literal_type {
-            LiteralType::Int => TypeKind::Int,
+            LiteralType::Int(_, suffix) => self.integer_literal_type(literal.token, suffix),
+            LiteralType::Float => TypeKind::Float,
+            LiteralType::Bool => TypeKind::Bool,
+            LiteralType::Str => TypeKind::Str,
+            LiteralType::Error => TypeKind::Error,
         };
-        literal.type_kind = Some(type_kind);
+        literal.type_kind = Some(type_kind.clone());
         type_kind
     }
 
-    fn type_grouping<'t>(&mut self, grouping: &'t mut Grouping) -> TypeKind {
+    fn type_grouping(&mut self, grouping: &mut Grouping) -> TypeKind {
         let type_kind = self.type_expr(grouping.expr.as_mut());
-        grouping.type_kind = Some(type_kind);
+        grouping.type_kind = Some(type_kind.clone());
         type_kind
     }
 
-    fn type_if<'t>(&mut self, if_expr: &'t mut If) -> TypeKind {
+    fn type_if(&mut self, if_expr: &mut If) -> TypeKind {
+        let condition_type = self.type_expr(if_expr.condition.as_mut());
+        self.expect_type(&condition_type, &describe(&if_expr.condition), &TypeKind::Bool,
+            &format!("the condition of the if at line {}, column {}", if_expr.token.line, if_expr.token.column), if_expr.condition.span());
+
         let then_type = self.type_expr(if_expr.then_branch.as_mut());
 
         let type_kind = if let Some(else_branch) = &mut if_expr.else_branch {
             let else_type = self.type_expr(else_branch.as_mut());
-            let type_kind = if std::mem::discriminant(&then_type) == std::mem::discriminant(&else_type) {
-                then_type
-            } else {
-                match (then_type, else_type) {
-                    (TypeKind::Error, _) | (_, TypeKind::Error) => {}
-                    _ => {
-                        // report error if this is new error and not propogated from child type
-                        self.errors.push(TypeError {message: 
-                            format!("Type error for {:?}: then branch returns {:?} and else branch returns {:?}",
-                            if_expr.token, then_type, else_type
-                        )});
+            self.expect_type(&else_type, &format!("the else branch at {}", location(else_branch)),
+                &then_type, &format!("the then branch at {}", location(&if_expr.then_branch)), else_branch.span());
+            then_type
+        } else {
+            // without an else branch there is no value when the condition is false
+            TypeKind::Unit
+        };
+
+        if_expr.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_function(&mut self, function: &mut Function) {
+        let mut scope = HashMap::new();
+        for param in &function.params {
+            let binding = Binding {type_kind: param.type_kind.clone(), mutable: false, quantified: Vec::new()};
+            if scope.insert(param.name.lexeme.to_string(), binding).is_some() {
+                self.errors.push(TypeError {message:
+                    format!("Parameter {} of function {} is declared more than once",
+                    param.name.lexeme, function.name.lexeme
+                ), span: param.name.span});
+            }
+        }
+        // function bodies only see their own parameters, never the caller's locals
+        let outer_scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+        self.type_params = function.type_params.iter().map(|token| token.lexeme.to_string()).collect();
+        if !function.type_params.is_empty() {
+            self.generic_function = Some(function.name.lexeme.to_string());
+        }
+        let body_type = self.type_expr(&mut function.body);
+        self.type_params.clear();
+        self.generic_function = None;
+        self.scopes = outer_scopes;
+
+        self.expect_type(&body_type, &format!("the body of function {}", function.name.lexeme), &function.return_type,
+            &format!("the return type of {} declared at line {}", function.name.lexeme, function.name.line), function.body.span());
+    }
+
+    // a function's signature with fresh variables for its type parameters, and those variables
+    fn instantiate_signature(&mut self, name: &str, token: &Token) -> (FunctionSignature, Vec<TypeKind>) {
+        let signature = self.functions[name].clone();
+        let instance = self.fresh_arguments(&signature.type_params, &format!("function {}", name), token);
+        if let (Some(caller), false) = (&self.generic_function, signature.type_params.is_empty()) {
+            self.instantiations.entry(caller.clone()).or_default().push((String::from(name), instance.clone()));
+        }
+        let mapping = param_mapping(&signature.type_params, &instance);
+        let params = signature.params.iter().map(|param| param.substitute_params(&mapping)).collect();
+        let return_type = signature.return_type.substitute_params(&mapping);
+        (FunctionSignature {type_params: Vec::new(), params, return_type, line: signature.line, span: signature.span}, instance)
+    }
+
+    fn lookup_binding(&self, name: &str) -> Option<Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned()
+    }
+
+    // like lookup_binding, but a variable from outside the lambdas being checked is recorded
+    // as a capture of each of them, so every closure in between can pass it on
+    fn use_variable(&mut self, name: &str) -> Option<Binding> {
+        let (index, binding) = self.scopes.iter().enumerate().rev()
+            .find_map(|(index, scope)| scope.get(name).map(|binding| (index, binding.clone())))?;
+        for lambda in &mut self.lambdas {
+            if index < lambda.boundary && !lambda.captures.iter().any(|(captured, _)| captured == name) {
+                lambda.captures.push((String::from(name), binding.type_kind.clone()));
+            }
+        }
+        Some(binding)
+    }
+
+    // true if the variable is declared outside the innermost lambda being checked
+    fn is_captured(&self, name: &str) -> bool {
+        match self.lambdas.last() {
+            Some(lambda) => !self.scopes[lambda.boundary..].iter().any(|scope| scope.contains_key(name)),
+            None => false,
+        }
+    }
+
+    fn type_variable(&mut self, variable: &mut Variable) -> TypeKind {
+        let name = variable.token.lexeme;
+        let type_kind = match self.use_variable(name) {
+            Some(binding) => {
+                let origin = format!("the use of {} at line {}, column {}", name, variable.token.line, variable.token.column);
+                let (type_kind, instance) = self.instantiate(&binding, &origin, variable.token.span);
+                variable.instance = instance;
+                type_kind
+            }
+            // naming a function without calling it makes a function value
+            None if self.functions.contains_key(name) => {
+                let (signature, instance) = self.instantiate_signature(name, variable.token);
+                variable.instance = instance;
+                TypeKind::Function(signature.params, Box::new(signature.return_type))
+            }
+            None => {
+                self.errors.push(TypeError {message:
+                    format!("Undefined variable {} at line {}, column {}",
+                    name, variable.token.line, variable.token.column
+                ), span: variable.token.span});
+                TypeKind::Error
+            }
+        };
+        variable.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_block(&mut self, block: &mut Block) -> TypeKind {
+        self.scopes.push(HashMap::new());
+        for statement in &mut block.statements {
+            match statement {
+                Stmt::Let(let_stmt) => {
+                    // type the initializer before the name is bound, so `let x = x + 1;` refers to the previous x
+                    let init_type = self.type_expr(&mut let_stmt.initializer);
+                    if let Some(annotation) = &let_stmt.type_kind {
+                        let annotation = bind_params(annotation, &self.type_params);
+                        self.check_type_exists(&annotation, let_stmt.name);
+                        self.expect_type(&init_type, &describe(&let_stmt.initializer), &annotation,
+                            &format!("the annotation of {} at line {}, column {}",
+                            let_stmt.name.lexeme, let_stmt.name.line, let_stmt.name.column), let_stmt.initializer.span());
+                        let_stmt.type_kind = Some(annotation);
                     }
+                    // only lambdas are generic, and only when bound once. A mutable variable
+                    // could be assigned a lambda that works for fewer types
+                    let quantified = match &let_stmt.initializer {
+                        Expr::Lambda(_) if !let_stmt.mutable => self.generalize(&init_type),
+                        _ => Vec::new(),
+                    };
+                    self.generalized.extend(quantified.iter().copied());
+                    let_stmt.quantified = quantified.clone();
+                    let binding = Binding {type_kind: init_type, mutable: let_stmt.mutable, quantified};
+                    self.scopes.last_mut().unwrap().insert(let_stmt.name.lexeme.to_string(), binding);
+                }
+                Stmt::Expr(expr) => {
+                    self.type_expr(expr);
+                }
+            }
+        }
+        let type_kind = match &mut block.expr {
+            Some(expr) => self.type_expr(expr.as_mut()),
+            None => TypeKind::Unit,
+        };
+        self.scopes.pop();
+
+        block.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_while(&mut self, while_expr: &mut While) -> TypeKind {
+        let condition_type = self.type_expr(while_expr.condition.as_mut());
+        self.expect_type(&condition_type, &describe(&while_expr.condition), &TypeKind::Bool,
+            &format!("the condition of the while at line {}, column {}", while_expr.token.line, while_expr.token.column), while_expr.condition.span());
+        // the body's value is thrown away after every iteration
+        self.type_expr(while_expr.body.as_mut());
+
+        let type_kind = TypeKind::Unit;
+        while_expr.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_assign(&mut self, assign: &mut Assign) -> TypeKind {
+        let value_type = self.type_expr(assign.value.as_mut());
+        let target_type = self.type_expr(assign.target.as_mut());
+
+        // assigning to an element changes the array, so the variable holding it has to be mutable
+        if let Some(variable) = place_root(assign.target.as_ref()) {
+            if let Some(binding) = self.lookup_binding(variable.token.lexeme) {
+                if self.is_captured(variable.token.lexeme) {
+                    self.errors.push(TypeError {message:
+                        format!("Cannot assign to {} at line {}, column {}, lambdas capture variables by value",
+                        variable.token.lexeme, variable.token.line, variable.token.column
+                    ), span: variable.token.span});
+                } else if !binding.mutable {
+                    self.errors.push(TypeError {message:
+                        format!("Cannot assign twice to immutable variable {} at line {}, column {}, declare it with let mut",
+                        variable.token.lexeme, variable.token.line, variable.token.column
+                    ), span: variable.token.span});
                 }
+            }
+        }
+
+        self.expect_type(&value_type, &describe(&assign.value), &target_type,
+            &format!("the target of the assignment at line {}, column {}", assign.token.line, assign.token.column), assign.value.span());
+
+        let type_kind = TypeKind::Unit;
+        assign.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_array(&mut self, array: &mut Array) -> TypeKind {
+        let element_types: Vec<TypeKind> = array.elements.iter_mut()
+            .map(|element| self.type_expr(element))
+            .collect();
+
+        // every element has the first one's type
+        let first_type = &element_types[0];
+        let first = format!("the first element at {}", location(&array.elements[0]));
+        for (index, element_type) in element_types.iter().enumerate().skip(1) {
+            self.expect_type(element_type, &format!("element {} at {}", index + 1, location(&array.elements[index])),
+                first_type, &first, array.elements[index].span());
+        }
+
+        let type_kind = TypeKind::Array(Box::new(first_type.clone()), element_types.len());
+        array.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_index(&mut self, index: &mut Index) -> TypeKind {
+        let array_type = self.type_expr(index.array.as_mut());
+        let index_type = self.type_expr(index.index.as_mut());
+
+        self.expect_integer(&index_type, &describe(&index.index),
+            &format!("the index at line {}, column {}", index.token.line, index.token.column), index.index.span());
+
+        let type_kind = match self.shallow_resolve(&array_type) {
+            TypeKind::Array(element_type, _) => *element_type,
+            TypeKind::Error => TypeKind::Error,
+            TypeKind::Var(_) => {
+                self.errors.push(TypeError {message:
+                    format!("The type of {} has to be known to index it, add a type annotation", describe(&index.array)),
+                    span: index.array.span(),
+                });
                 TypeKind::Error
-                
-            };
-            type_kind
+            }
+            array_type => {
+                self.errors.push(TypeError {message:
+                    format!("Type error for {:?}: only arrays can be indexed, found {}", index.token, array_type),
+                    span: index.array.span(),
+                });
+                TypeKind::Error
+            }
+        };
+        index.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_struct_literal(&mut self, struct_literal: &mut StructLiteral) -> TypeKind {
+        let value_types: Vec<TypeKind> = struct_literal.fields.iter_mut()
+            .map(|(_, value)| self.type_expr(value))
+            .collect();
+
+        let name = struct_literal.name.lexeme;
+        let definition = match self.structs.get(name) {
+            Some(definition) => definition.clone(),
+            None => {
+                self.errors.push(TypeError {message:
+                    format!("Unknown struct {} at line {}, column {}",
+                    name, struct_literal.name.line, struct_literal.name.column
+                ), span: struct_literal.name.span});
+                struct_literal.type_kind = Some(TypeKind::Error);
+                return TypeKind::Error;
+            }
+        };
+
+        let arguments = self.fresh_arguments(&definition.type_params, &format!("struct {}", name), struct_literal.name);
+        let mut valid = true;
+        for (index, ((field, value), value_type)) in struct_literal.fields.iter().zip(value_types.iter()).enumerate() {
+            if struct_literal.fields[..index].iter().any(|(previous, _)| previous.lexeme == field.lexeme) {
+                self.errors.push(TypeError {message:
+                    format!("Field {} is given more than once at line {}, column {}", field.lexeme, field.line, field.column),
+                    span: field.span,
+                });
+                valid = false;
+                continue;
+            }
+            match definition.field_type(field.lexeme, &arguments) {
+                Some(field_type) => {
+                    valid &= self.expect_type(value_type, &format!("field {} at {}", field.lexeme, location(value)),
+                        &field_type, &format!("field {} declared in struct {}", field.lexeme, name), value.span());
+                }
+                None => {
+                    self.errors.push(TypeError {message:
+                        format!("Struct {} has no field named {}, at line {}, column {}",
+                        name, field.lexeme, field.line, field.column
+                    ), span: field.span});
+                    valid = false;
+                }
+            }
+        }
+        for (field_name, _) in &definition.fields {
+            if !struct_literal.fields.iter().any(|(field, _)| field.lexeme == *field_name) {
+                self.errors.push(TypeError {message:
+                    format!("Missing field {} in {} literal at line {}, column {}",
+                    field_name, name, struct_literal.name.line, struct_literal.name.column
+                ), span: struct_literal.name.span});
+                valid = false;
+            }
+        }
+
+        let type_kind = if valid {
+            TypeKind::Named(name.to_string(), arguments)
         } else {
-            then_type
+            TypeKind::Error
         };
+        struct_literal.type_kind = Some(type_kind.clone());
+        type_kind
+    }
 
-        if_expr.type_kind = Some(type_kind);
+    fn type_field(&mut self, field: &mut Field) -> TypeKind {
+        let object_type = self.type_expr(field.object.as_mut());
+        let type_kind = match self.shallow_resolve(&object_type) {
+            TypeKind::Named(name, arguments) if self.structs.contains_key(&name) => {
+                match self.structs[&name].field_type(field.name.lexeme, &arguments) {
+                    Some(field_type) => field_type,
+                    None => {
+                        self.errors.push(TypeError {message:
+                            format!("Struct {} has no field named {}, at line {}, column {}",
+                            name, field.name.lexeme, field.name.line, field.name.column
+                        ), span: field.name.span});
+                        TypeKind::Error
+                    }
+                }
+            }
+            TypeKind::Error => TypeKind::Error,
+            TypeKind::Var(_) => {
+                self.errors.push(TypeError {message:
+                    format!("The type of {} has to be known to access its field {}, add a type annotation",
+                    describe(&field.object), field.name.lexeme
+                ), span: field.object.span()});
+                TypeKind::Error
+            }
+            object_type => {
+                self.errors.push(TypeError {message:
+                    format!("Type error for {:?}: only structs have fields, found {}", field.token, object_type),
+                    span: field.object.span(),
+                });
+                TypeKind::Error
+            }
+        };
+        field.type_kind = Some(type_kind.clone());
         type_kind
     }
-}
\ No newline at end of file
+
+    // the enum and variant index a path names, reporting an error if there is no such variant
+    fn lookup_variant(&mut self, path: &Path) -> Option<(String, usize)> {
+        let enum_name = path.qualifier.lexeme;
+        let definition = match self.enums.get(enum_name) {
+            Some(definition) => definition,
+            None => {
+                // module::name is one name once the module is imported, so a path is what's left of
+                // a name whose module the file doesn't import
+                let message = if self.files.iter().any(|(module, _)| module.as_deref() == Some(enum_name)) {
+                    format!("Module {} is used {} but not imported there, that file needs import {};",
+                    enum_name, self.location(path.qualifier), enum_name)
+                } else {
+                    format!("Unknown enum {} at line {}, column {}, and no module {} is imported",
+                    enum_name, path.qualifier.line, path.qualifier.column, enum_name)
+                };
+                self.errors.push(TypeError {message, span: path.qualifier.span});
+                return None;
+            }
+        };
+        match definition.variants.iter().position(|(variant, _)| *variant == path.name.lexeme) {
+            Some(index) => Some((enum_name.to_string(), index)),
+            None => {
+                self.errors.push(TypeError {message:
+                    format!("Enum {} has no variant named {}, at line {}, column {}",
+                    enum_name, path.name.lexeme, path.name.line, path.name.column
+                ), span: path.name.span});
+                None
+            }
+        }
+    }
+
+    // a variant without a payload is a value on its own
+    fn type_path(&mut self, path: &mut Path) -> TypeKind {
+        let type_kind = match self.lookup_variant(path) {
+            Some((enum_name, index)) => {
+                let definition = self.enums[&enum_name].clone();
+                let payload = &definition.variants[index].1;
+                if payload.is_empty() {
+                    let arguments = self.fresh_arguments(&definition.type_params, &format!("enum {}", enum_name), path.qualifier);
+                    TypeKind::Named(enum_name, arguments)
+                } else {
+                    self.errors.push(TypeError {message:
+                        format!("Variant {}::{} at line {} holds {} values, construct it like a function call",
+                        enum_name, path.name.lexeme, path.name.line, payload.len()
+                    ), span: path.name.span});
+                    TypeKind::Error
+                }
+            }
+            None => TypeKind::Error,
+        };
+        path.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_variant_call(&mut self, path: &mut Path, arguments: &[Expr], argument_types: &[TypeKind]) -> TypeKind {
+        let type_kind = match self.lookup_variant(path) {
+            Some((enum_name, index)) => {
+                let definition = self.enums[&enum_name].clone();
+                let type_arguments = self.fresh_arguments(&definition.type_params, &format!("enum {}", enum_name), path.qualifier);
+                let payload = definition.payload(index, &type_arguments);
+                if payload.len() != argument_types.len() {
+                    self.errors.push(TypeError {message:
+                        format!("Variant {}::{} at line {} holds {} values but got {}",
+                        enum_name, path.name.lexeme, path.name.line, payload.len(), argument_types.len()
+                    ), span: path.name.span});
+                }
+                for (index, (payload_type, argument_type)) in payload.iter().zip(argument_types.iter()).enumerate() {
+                    self.expect_type(argument_type,
+                        &format!("value {} of {}::{} at {}", index + 1, enum_name, path.name.lexeme, location(&arguments[index])),
+                        payload_type, &format!("value {} declared in enum {}", index + 1, enum_name), arguments[index].span());
+                }
+                TypeKind::Named(enum_name, type_arguments)
+            }
+            None => TypeKind::Error,
+        };
+        path.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_match(&mut self, match_expr: &mut Match) -> TypeKind {
+        let scrutinee_type = self.type_expr(match_expr.scrutinee.as_mut());
+
+        let mut patterns = Vec::new();
+        let mut arm_types: Vec<TypeKind> = Vec::new();
+        for arm in &mut match_expr.arms {
+            let mut bindings = HashMap::new();
+            let pattern = self.type_pattern(&arm.pattern, &scrutinee_type, &mut bindings);
+            patterns.push(pattern);
+            self.scopes.push(bindings);
+            arm_types.push(self.type_expr(&mut arm.body));
+            self.scopes.pop();
+        }
+
+        // every arm has the first one's type
+        let type_kind = arm_types[0].clone();
+        let first = format!("the first arm at {}", location(&match_expr.arms[0].body));
+        for (index, arm_type) in arm_types.iter().enumerate().skip(1) {
+            self.expect_type(arm_type, &format!("arm {} at {}", index + 1, location(&match_expr.arms[index].body)),
+                &type_kind, &first, match_expr.arms[index].body.span());
+        }
+
+        // patterns that didn't typecheck can't be checked for exhaustiveness
+        let scrutinee_type = self.resolve(&scrutinee_type);
+        if scrutinee_type != TypeKind::Error && patterns.iter().all(|pattern| pattern.is_some()) {
+            let patterns: Vec<exhaustiveness::Pattern> = patterns.into_iter().flatten().collect();
+            let checker = exhaustiveness::Checker::new(&self.enums);
+            for (index, arm) in match_expr.arms.iter().enumerate() {
+                if !checker.is_useful(&patterns[..index], &patterns[index], &scrutinee_type) {
+                    let token = pattern_token(&arm.pattern);
+                    self.warnings.push(Warning {message:
+                        format!("Unreachable match arm at line {}, column {}: earlier arms already match everything it does",
+                        token.line, token.column
+                    )});
+                }
+            }
+            if let Some(missing) = checker.missing_pattern(&patterns, &scrutinee_type) {
+                self.errors.push(TypeError {message:
+                    format!("Match at line {}, column {} is not exhaustive: {} is not covered",
+                    match_expr.token.line, match_expr.token.column, missing
+                ), span: match_expr.token.span});
+            }
+        }
+
+        match_expr.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    // check a pattern against the type it is matched with and collect the names it binds.
+    // Returns None if the pattern doesn't fit the type
+    fn type_pattern(&mut self, pattern: &Pattern, expected: &TypeKind, bindings: &mut HashMap<String, Binding>)
+        -> Option<exhaustiveness::Pattern> {
+        match pattern {
+            Pattern::Wildcard(_) => Some(exhaustiveness::Pattern::Wildcard),
+            Pattern::Binding(name) => {
+                let binding = Binding {type_kind: expected.clone(), mutable: false, quantified: Vec::new()};
+                if bindings.insert(name.lexeme.to_string(), binding).is_some() {
+                    self.errors.push(TypeError {message:
+                        format!("{} is bound more than once in the same pattern, at line {}, column {}",
+                        name.lexeme, name.line, name.column
+                    ), span: name.span});
+                    return None;
+                }
+                Some(exhaustiveness::Pattern::Wildcard)
+            }
+            Pattern::Literal(token, literal_type) => {
+                if self.shallow_resolve(expected) == TypeKind::Error {
+                    return None;
+                }
+                let (literal_kind, constructor) = match literal_type {
+                    // the pattern's value is checked against the scrutinee's type once it is known
+                    LiteralType::Int(value, suffix) => {
+                        (self.integer_literal_type(token, *suffix), exhaustiveness::Constructor::Int(*value as i64))
+                    }
+                    LiteralType::Bool => (TypeKind::Bool, exhaustiveness::Constructor::Bool(token.lexeme == "true")),
+                    LiteralType::Float | LiteralType::Str | LiteralType::Error => unreachable!("parser doesn't allow float or string patterns"),
+                };
+                if self.unify(expected, &literal_kind).is_err() {
+                    self.errors.push(TypeError {message:
+                        format!("Type error for {:?}: pattern can't match a value of type {}", token, self.resolve(expected)),
+                        span: token.span,
+                    });
+                    return None;
+                }
+                Some(exhaustiveness::Pattern::Constructor(constructor, Vec::new()))
+            }
+            Pattern::Variant(variant) => {
+                let enum_name = variant.enum_name.lexeme;
+                let type_params = self.enums.get(enum_name).map(|definition| definition.type_params.clone()).unwrap_or_default();
+                let arguments = self.fresh_arguments(&type_params, &format!("enum {}", enum_name), variant.enum_name);
+                let enum_type = TypeKind::Named(enum_name.to_string(), arguments.clone());
+                let definition = match self.enums.get(enum_name).cloned() {
+                    _ if self.shallow_resolve(expected) == TypeKind::Error => return None,
+                    Some(definition) if self.unify(expected, &enum_type).is_ok() => definition,
+                    _ => {
+                        self.errors.push(TypeError {message:
+                            format!("Type error for {:?}: pattern for enum {} can't match a value of type {}",
+                            variant.variant, enum_name, self.resolve(expected)
+                        ), span: variant.variant.span});
+                        return None;
+                    }
+                };
+                let index = match definition.variants.iter().position(|(name, _)| *name == variant.variant.lexeme) {
+                    Some(index) => index,
+                    None => {
+                        self.errors.push(TypeError {message:
+                            format!("Enum {} has no variant named {}, at line {}, column {}",
+                            enum_name, variant.variant.lexeme, variant.variant.line, variant.variant.column
+                        ), span: variant.variant.span});
+                        return None;
+                    }
+                };
+                let payload = definition.payload(index, &arguments);
+                if payload.len() != variant.fields.len() {
+                    self.errors.push(TypeError {message:
+                        format!("Pattern for {}::{} at line {} has {} fields but the variant holds {} values",
+                        enum_name, variant.variant.lexeme, variant.variant.line, variant.fields.len(), payload.len()
+                    ), span: variant.variant.span});
+                    return None;
+                }
+                // check every field even after a bad one, so all their bindings are in scope for the body
+                let fields: Vec<Option<exhaustiveness::Pattern>> = variant.fields.iter().zip(payload.iter())
+                    .map(|(field, field_type)| self.type_pattern(field, field_type, bindings))
+                    .collect();
+                let fields: Option<Vec<exhaustiveness::Pattern>> = fields.into_iter().collect();
+                fields.map(|fields| exhaustiveness::Pattern::Constructor(exhaustiveness::Constructor::Variant(index), fields))
+            }
+        }
+    }
+
+    fn type_call(&mut self, call: &mut Call) -> TypeKind {
+        if let Expr::Variable(variable) = call.callee.as_ref() {
+            let name = variable.token.lexeme;
+            if BUILTINS.contains(&name) {
+                let argument_types = self.type_arguments(&mut call.arguments);
+                let type_kind = match name {
+                    "print" => self.type_print(call, &argument_types),
+                    _ => self.type_len(call, &argument_types),
+                };
+                call.type_kind = Some(type_kind.clone());
+                return type_kind;
+            }
+        }
+
+        if let Expr::Path(path) = call.callee.as_mut() {
+            let argument_types = self.type_arguments(&mut call.arguments);
+            let type_kind = self.type_variant_call(path, &call.arguments, &argument_types);
+            call.type_kind = Some(type_kind.clone());
+            return type_kind;
+        }
+
+        let call_location = format!("line {}, column {}", call.token.line, call.token.column);
+        // named functions are called directly, anything else has to evaluate to a function value
+        let (callee_name, signature) = match call.callee.as_mut() {
+            Expr::Variable(variable) if self.lookup_binding(variable.token.lexeme).is_none()
+                && self.functions.contains_key(variable.token.lexeme) => {
+                let (signature, instance) = self.instantiate_signature(variable.token.lexeme, variable.token);
+                variable.instance = instance;
+                (format!("function {} declared at line {}", variable.token.lexeme, signature.line), Some(signature))
+            }
+            callee => {
+                let callee_type = self.type_expr(callee);
+                match self.shallow_resolve(&callee_type) {
+                    TypeKind::Function(params, return_type) => {
+                        let signature = FunctionSignature {type_params: Vec::new(), params, return_type: *return_type, line: call.token.line, span: call.token.span};
+                        (describe(callee), Some(signature))
+                    }
+                    TypeKind::Error => (describe(callee), None),
+                    // nothing is known about the callee yet, so the call decides its type
+                    TypeKind::Var(_) => {
+                        let argument_types = self.type_arguments(&mut call.arguments);
+                        let return_type = self.fresh_variable(format!("the result of the call at {}", call_location), call.token.span);
+                        let function_type = TypeKind::Function(argument_types, Box::new(return_type.clone()));
+                        self.expect_type(&callee_type, &describe(callee), &function_type, &format!("the call at {}", call_location), callee.span());
+                        call.type_kind = Some(return_type.clone());
+                        return return_type;
+                    }
+                    callee_type => {
+                        self.errors.push(TypeError {message:
+                            format!("Type error for {:?}: only functions can be called, found {}", call.token, callee_type),
+                            span: callee.span(),
+                        });
+                        (describe(callee), None)
+                    }
+                }
+            }
+        };
+
+        let type_kind = match signature {
+            Some(signature) => {
+                if signature.params.len() != call.arguments.len() {
+                    self.type_arguments(&mut call.arguments);
+                    self.errors.push(TypeError {message:
+                        format!("Type error for {:?}: expected {} arguments but got {}",
+                        call.token, signature.params.len(), call.arguments.len()
+                    ), span: call.token.span});
+                } else {
+                    // each argument is unified with its parameter before the next one is typed, so a
+                    // lambda argument gets parameter types the arguments before it worked out
+                    for (index, param_type) in signature.params.iter().enumerate() {
+                        let argument_type = self.type_argument(&mut call.arguments[index], Some(param_type));
+                        self.expect_type(&argument_type, &format!("argument {} at {}", index + 1, location(&call.arguments[index])),
+                            param_type, &format!("parameter {} of {}", index + 1, callee_name), call.arguments[index].span());
+                    }
+                }
+                // the declared return type is known even when the arguments are wrong
+                signature.return_type
+            }
+            None => {
+                self.type_arguments(&mut call.arguments);
+                TypeKind::Error
+            }
+        };
+        call.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    fn type_arguments(&mut self, arguments: &mut [Expr]) -> Vec<TypeKind> {
+        arguments.iter_mut().map(|argument| self.type_expr(argument)).collect()
+    }
+
+    // lambdas passed where a function type is expected take their parameter types from it
+    fn type_argument(&mut self, argument: &mut Expr, param: Option<&TypeKind>) -> TypeKind {
+        match argument {
+            Expr::Lambda(lambda) => self.type_lambda(lambda, param),
+            argument => self.type_expr(argument),
+        }
+    }
+
+    fn type_lambda(&mut self, lambda: &mut Lambda, expected: Option<&TypeKind>) -> TypeKind {
+        let expected_params = match expected.map(|expected| self.shallow_resolve(expected)) {
+            Some(TypeKind::Function(params, _)) if params.len() == lambda.params.len() => params,
+            _ => Vec::new(),
+        };
+
+        let mut scope = HashMap::new();
+        let mut param_types = Vec::new();
+        for (index, param) in lambda.params.iter_mut().enumerate() {
+            let type_kind = match (&param.type_kind, expected_params.get(index)) {
+                (Some(type_kind), _) => {
+                    let type_kind = bind_params(type_kind, &self.type_params);
+                    self.check_type_exists(&type_kind, param.name);
+                    param.type_kind = Some(type_kind.clone());
+                    type_kind
+                }
+                (None, Some(type_kind)) => type_kind.clone(),
+                // the body and the calls work out what it is
+                (None, None) => self.fresh_variable(format!("parameter {} at line {}, column {}",
+                    param.name.lexeme, param.name.line, param.name.column), param.name.span),
+            };
+            let binding = Binding {type_kind: type_kind.clone(), mutable: false, quantified: Vec::new()};
+            if scope.insert(param.name.lexeme.to_string(), binding).is_some() {
+                self.errors.push(TypeError {message:
+                    format!("Parameter {} of the lambda at line {} is declared more than once",
+                    param.name.lexeme, lambda.token.line
+                ), span: param.name.span});
+            }
+            param_types.push(type_kind);
+        }
+
+        self.lambdas.push(LambdaScope {boundary: self.scopes.len(), captures: Vec::new()});
+        self.scopes.push(scope);
+        let body_type = self.type_expr(lambda.body.as_mut());
+        self.scopes.pop();
+        lambda.captures = self.lambdas.pop().unwrap().captures;
+
+        let type_kind = TypeKind::Function(param_types, Box::new(body_type));
+        lambda.type_kind = Some(type_kind.clone());
+        type_kind
+    }
+
+    // print(value) writes an Int, Bool or Str to stdout followed by a newline
+    fn type_print(&mut self, call: &Call, argument_types: &[TypeKind]) -> TypeKind {
+        match argument_types {
+            [argument_type] => {
+                self.deferred.push(Deferred {requirement: Requirement::Printable, type_kind: argument_type.clone(),
+                    location: format!("print at line {}, column {}", call.token.line, call.token.column), span: call.token.span});
+            }
+            _ => {
+                self.errors.push(TypeError {message:
+                    format!("Type error for {:?}: print expects 1 argument but got {}",
+                    call.token, argument_types.len()
+                ), span: call.token.span});
+            }
+        }
+        TypeKind::Unit
+    }
+
+    // len(string) is the string's length in bytes, len(array) its number of elements
+    fn type_len(&mut self, call: &Call, argument_types: &[TypeKind]) -> TypeKind {
+        match argument_types {
+            [argument_type] => {
+                self.deferred.push(Deferred {requirement: Requirement::HasLength, type_kind: argument_type.clone(),
+                    location: format!("len at line {}, column {}", call.token.line, call.token.column), span: call.token.span});
+                TypeKind::Int(IntType::I64)
+            }
+            _ => {
+                self.errors.push(TypeError {message:
+                    format!("Type error for {:?}: len expects 1 argument but got {}",
+                    call.token, argument_types.len()
+                ), span: call.token.span});
+                TypeKind::Error
+            }
+        }
+    }
+
+    // the checks that had to wait until every type was inferred
+    fn check_deferred(&mut self) {
+        for deferred in std::mem::take(&mut self.deferred) {
+            let type_kind = self.resolve(&deferred.type_kind);
+            let allowed = matches!((&deferred.requirement, &type_kind),
+                (_, TypeKind::Error)
+                | (Requirement::Printable, TypeKind::Int(_) | TypeKind::Float | TypeKind::Bool | TypeKind::Str)
+                | (Requirement::HasLength, TypeKind::Str | TypeKind::Array(..))
+                | (Requirement::Comparable, TypeKind::Int(_) | TypeKind::Float | TypeKind::Bool | TypeKind::Str | TypeKind::Unit));
+            let allowed = allowed || matches!((&deferred.requirement, &type_kind), (Requirement::Signed, TypeKind::Int(int_type)) if int_type.signed());
+            if allowed {
+                continue;
+            }
+            let message = match (&deferred.requirement, &type_kind) {
+                (_, TypeKind::Var(variable)) => {
+                    self.reported.insert(*variable);
+                    format!("Cannot infer the type of the value given to {}, add a type annotation", deferred.location)
+                }
+                (Requirement::Printable, _) => format!("Type error: {} expects int, float, bool or str but got {}",
+                    deferred.location, type_kind),
+                (Requirement::HasLength, _) => format!("Type error: {} expects str or an array but got {}",
+                    deferred.location, type_kind),
+                (Requirement::Comparable, _) => format!("Type error: {} can't compare values of type {}, \
+                    arrays, structs, enums and functions can't be compared", deferred.location, type_kind),
+                (Requirement::Signed, _) => format!("Type error: {} can't negate values of type {}, only signed integers",
+                    deferred.location, type_kind),
+            };
+            self.errors.push(TypeError {message, span: deferred.span});
+        }
+    }
+
+    // code is generated for every combination of types a generic function is used at, so one
+    // that reaches itself with bigger types, like f<T> using f<[T; 2]>, would never be done
+    fn check_instantiation_cycles(&mut self) {
+        let mut names: Vec<String> = self.instantiations.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let params: Vec<TypeKind> = self.functions[&name].type_params.iter().map(|param| TypeKind::Param(param.clone())).collect();
+            if let Some((param, argument)) = self.growing_instantiation(&name, &name, &params, &mut Vec::new()) {
+                self.errors.push(TypeError {message:
+                    format!("Function {} declared at line {} uses itself with {} for its type parameter {}, \
+                    so it would need a copy for ever larger types", name, self.functions[&name].line, argument, param
+                ), span: self.functions[&name].span});
+            }
+        }
+    }
+
+    // follow the uses of generic functions from function, whose type parameters stand for arguments,
+    // along paths that don't visit a function twice. Returns the type parameter of start that grows
+    // and what it grows to when one of them gets back to start
+    fn growing_instantiation(&self, start: &str, function: &str, arguments: &[TypeKind], path: &mut Vec<String>)
+        -> Option<(String, TypeKind)> {
+        let mapping = param_mapping(&self.functions[function].type_params, arguments);
+        path.push(String::from(function));
+        let mut growing = None;
+        for (callee, instance) in self.instantiations.get(function).into_iter().flatten() {
+            let instance: Vec<TypeKind> = instance.iter().map(|type_kind| self.resolve(type_kind).substitute_params(&mapping)).collect();
+            if callee == start {
+                growing = self.functions[start].type_params.iter().zip(instance)
+                    .find(|(_, argument)| !matches!(argument, TypeKind::Param(_)) && contains_param(argument))
+                    .map(|(param, argument)| (param.clone(), argument));
+            } else if !path.contains(callee) {
+                growing = self.growing_instantiation(start, callee, &instance, path);
+            }
+            if growing.is_some() {
+                break;
+            }
+        }
+        path.pop();
+        growing
+    }
+
+    // replace the types recorded in the tree with what inference worked out for them,
+    // reporting every variable that is still unknown outside a generic lambda
+    fn finish_type(&mut self, type_kind: &mut TypeKind) {
+        *type_kind = self.resolve(type_kind);
+        let mut variables = Vec::new();
+        free_variables(type_kind, &mut variables);
+        for variable in variables {
+            if !self.generalized.contains(&variable) && self.reported.insert(variable) {
+                self.errors.push(TypeError {message:
+                    format!("Cannot infer a type for {}, add a type annotation", self.origins[variable as usize].0),
+                    span: self.origins[variable as usize].1,
+                });
+            }
+        }
+    }
+
+    // integer literals have to fit the type inference settled on for them
+    fn check_literal_range(&mut self, literal: &Literal, negated: bool) {
+        let int_type = match literal.type_kind.as_ref().map(|type_kind| self.resolve(type_kind)) {
+            Some(TypeKind::Int(int_type)) => int_type,
+            _ => return,
+        };
+        // -5u8 is reported as negating an unsigned value, and -%5u8 is 251
+        let negated = negated && int_type.signed();
+        let LiteralType::Int(value, _) = literal.literal_type else { return };
+        let fits = if negated { -(value as i128) >= int_type.min() } else { value as i128 <= int_type.max() };
+        if !fits {
+            self.errors.push(TypeError {message:
+                format!("Integer literal {}{} at line {}, column {} doesn't fit in type {}",
+                if negated { "-" } else { "" }, literal.token.lexeme, literal.token.line, literal.token.column, int_type
+            ), span: literal.token.span});
+        }
+    }
+
+    fn check_pattern_range(&mut self, pattern: &Pattern, type_kind: &TypeKind) {
+        match (pattern, self.resolve(type_kind)) {
+            (Pattern::Literal(token, LiteralType::Int(value, _)), TypeKind::Int(int_type)) => {
+                let fits = *value as i128 <= int_type.max();
+                if !fits {
+                    self.errors.push(TypeError {message:
+                        format!("Integer pattern {} at line {}, column {} doesn't fit in type {}",
+                        token.lexeme, token.line, token.column, int_type
+                    ), span: token.span});
+                }
+            }
+            (Pattern::Variant(variant), TypeKind::Named(name, arguments)) => {
+                let payload = self.enums.get(&name).and_then(|definition| {
+                    let index = definition.variants.iter().position(|(variant_name, _)| *variant_name == variant.variant.lexeme)?;
+                    Some(definition.payload(index, &arguments))
+                });
+                for (field, field_type) in variant.fields.iter().zip(payload.unwrap_or_default()) {
+                    self.check_pattern_range(field, &field_type);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Binary(binary) => {
+                self.finish_expr(binary.left.as_mut());
+                self.finish_expr(binary.right.as_mut());
+            }
+            Expr::Logical(logical) => {
+                self.finish_expr(logical.left.as_mut());
+                self.finish_expr(logical.right.as_mut());
+            }
+            Expr::Unary(unary) => match (&unary.operation, unary.right.as_mut()) {
+                // -128i8 is in range even though 128i8 isn't
+                (UnaryOp::Minus | UnaryOp::WrappingMinus, Expr::Literal(literal)) if matches!(literal.literal_type, LiteralType::Int(..)) => {
+                    self.check_literal_range(literal, true);
+                    if let Some(type_kind) = &mut literal.type_kind {
+                        self.finish_type(type_kind);
+                    }
+                }
+                (_, right) => self.finish_expr(right),
+            },
+            Expr::Literal(literal) => {
+                if let LiteralType::Int(..) = literal.literal_type {
+                    self.check_literal_range(literal, false);
+                }
+            }
+            Expr::Path(_) => {}
+            Expr::Grouping(grouping) => self.finish_expr(grouping.expr.as_mut()),
+            Expr::If(if_expr) => {
+                self.finish_expr(if_expr.condition.as_mut());
+                self.finish_expr(if_expr.then_branch.as_mut());
+                if let Some(else_branch) = &mut if_expr.else_branch {
+                    self.finish_expr(else_branch.as_mut());
+                }
+            }
+            Expr::Variable(variable) => {
+                for type_kind in &mut variable.instance {
+                    self.finish_type(type_kind);
+                }
+            }
+            Expr::Block(block) => {
+                for statement in &mut block.statements {
+                    match statement {
+                        Stmt::Let(let_stmt) => self.finish_expr(&mut let_stmt.initializer),
+                        Stmt::Expr(expr) => self.finish_expr(expr),
+                    }
+                }
+                if let Some(expr) = &mut block.expr {
+                    self.finish_expr(expr.as_mut());
+                }
+            }
+            Expr::Call(call) => {
+                self.finish_expr(call.callee.as_mut());
+                for argument in &mut call.arguments {
+                    self.finish_expr(argument);
+                }
+            }
+            Expr::While(while_expr) => {
+                self.finish_expr(while_expr.condition.as_mut());
+                self.finish_expr(while_expr.body.as_mut());
+            }
+            Expr::Assign(assign) => {
+                self.finish_expr(assign.target.as_mut());
+                self.finish_expr(assign.value.as_mut());
+            }
+            Expr::Array(array) => {
+                for element in &mut array.elements {
+                    self.finish_expr(element);
+                }
+            }
+            Expr::Index(index) => {
+                self.finish_expr(index.array.as_mut());
+                self.finish_expr(index.index.as_mut());
+            }
+            Expr::StructLiteral(struct_literal) => {
+                for (_, value) in &mut struct_literal.fields {
+                    self.finish_expr(value);
+                }
+            }
+            Expr::Field(field) => self.finish_expr(field.object.as_mut()),
+            Expr::Match(match_expr) => {
+                self.finish_expr(match_expr.scrutinee.as_mut());
+                let scrutinee_type = match_expr.scrutinee.type_kind().unwrap_or(TypeKind::Error);
+                for arm in &mut match_expr.arms {
+                    self.check_pattern_range(&arm.pattern, &scrutinee_type);
+                    self.finish_expr(&mut arm.body);
+                }
+            }
+            Expr::Lambda(lambda) => {
+                for (_, type_kind) in &mut lambda.captures {
+                    *type_kind = self.resolve(type_kind);
+                }
+                self.finish_expr(lambda.body.as_mut());
+            }
+            Expr::Cast(cast) => self.finish_expr(cast.expr.as_mut()),
+        }
+        let type_kind = match expr {
+            Expr::Binary(n) => &mut n.type_kind,
+            Expr::Unary(n) => &mut n.type_kind,
+            Expr::Literal(n) => &mut n.type_kind,
+            Expr::Grouping(n) => &mut n.type_kind,
+            Expr::If(n) => &mut n.type_kind,
+            Expr::Variable(n) => &mut n.type_kind,
+            Expr::Block(n) => &mut n.type_kind,
+            Expr::Call(n) => &mut n.type_kind,
+            Expr::Logical(n) => &mut n.type_kind,
+            Expr::While(n) => &mut n.type_kind,
+            Expr::Assign(n) => &mut n.type_kind,
+            Expr::Array(n) => &mut n.type_kind,
+            Expr::Index(n) => &mut n.type_kind,
+            Expr::StructLiteral(n) => &mut n.type_kind,
+            Expr::Field(n) => &mut n.type_kind,
+            Expr::Path(n) => &mut n.type_kind,
+            Expr::Match(n) => &mut n.type_kind,
+            Expr::Lambda(n) => &mut n.type_kind,
+            Expr::Cast(n) => &mut n.type_kind,
+        };
+        if let Some(type_kind) = type_kind {
+            self.finish_type(type_kind);
+        }
+    }
+}
+
+fn free_variables(type_kind: &TypeKind, variables: &mut Vec<u32>) {
+    match type_kind {
+        TypeKind::Var(variable) if !variables.contains(variable) => variables.push(*variable),
+        TypeKind::Array(element_type, _) => free_variables(element_type, variables),
+        TypeKind::Function(params, return_type) => {
+            for param in params {
+                free_variables(param, variables);
+            }
+            free_variables(return_type, variables);
+        }
+        TypeKind::Named(_, arguments) => {
+            for argument in arguments {
+                free_variables(argument, variables);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn contains_param(type_kind: &TypeKind) -> bool {
+    match type_kind {
+        TypeKind::Param(_) => true,
+        TypeKind::Array(element_type, _) => contains_param(element_type),
+        TypeKind::Function(params, return_type) => params.iter().any(contains_param) || contains_param(return_type),
+        TypeKind::Named(_, arguments) => arguments.iter().any(contains_param),
+        _ => false,
+    }
+}
+
+// a declared type with the names of the declaration's type parameters turned into Params
+fn bind_params(type_kind: &TypeKind, type_params: &[String]) -> TypeKind {
+    match type_kind {
+        TypeKind::Named(name, arguments) if arguments.is_empty() && type_params.contains(name) => TypeKind::Param(name.clone()),
+        TypeKind::Named(name, arguments) => TypeKind::Named(
+            name.clone(),
+            arguments.iter().map(|argument| bind_params(argument, type_params)).collect(),
+        ),
+        TypeKind::Array(element_type, length) => TypeKind::Array(Box::new(bind_params(element_type, type_params)), *length),
+        TypeKind::Function(params, return_type) => TypeKind::Function(
+            params.iter().map(|param| bind_params(param, type_params)).collect(),
+            Box::new(bind_params(return_type, type_params)),
+        ),
+        _ => type_kind.clone(),
+    }
+}
+
+// the first token of an expression, None for an empty block
+fn first_token<'t>(expr: &Expr<'t>) -> Option<&'t Token<'t>> {
+    match expr {
+        Expr::Binary(binary) => first_token(binary.left.as_ref()),
+        Expr::Logical(logical) => first_token(logical.left.as_ref()),
+        Expr::Unary(unary) => Some(unary.token),
+        Expr::Literal(literal) => Some(literal.token),
+        Expr::Grouping(grouping) => first_token(grouping.expr.as_ref()),
+        Expr::If(if_expr) => Some(if_expr.token),
+        Expr::Variable(variable) => Some(variable.token),
+        Expr::Block(block) => match (block.statements.first(), &block.expr) {
+            (Some(Stmt::Let(let_stmt)), _) => Some(let_stmt.name),
+            (Some(Stmt::Expr(expr)), _) => first_token(expr),
+            (None, Some(expr)) => first_token(expr.as_ref()),
+            (None, None) => None,
+        },
+        Expr::Call(call) => first_token(call.callee.as_ref()),
+        Expr::While(while_expr) => Some(while_expr.token),
+        Expr::Assign(assign) => first_token(assign.target.as_ref()),
+        Expr::Array(array) => Some(array.token),
+        Expr::Index(index) => first_token(index.array.as_ref()),
+        Expr::StructLiteral(struct_literal) => Some(struct_literal.name),
+        Expr::Field(field) => first_token(field.object.as_ref()),
+        Expr::Path(path) => Some(path.qualifier),
+        Expr::Match(match_expr) => Some(match_expr.token),
+        Expr::Lambda(lambda) => Some(lambda.token),
+        Expr::Cast(cast) => first_token(cast.expr.as_ref()),
+    }
+}
+
+fn location(expr: &Expr) -> String {
+    match first_token(expr) {
+        Some(token) => format!("line {}, column {}", token.line, token.column),
+        None => String::from("an empty block"),
+    }
+}
+
+// names an expression in errors about its type
+fn describe(expr: &Expr) -> String {
+    match expr {
+        Expr::Variable(variable) => format!("{} at {}", variable.token.lexeme, location(expr)),
+        Expr::Literal(literal) => format!("the literal {} at {}", literal.token.lexeme, location(expr)),
+        _ => format!("the expression at {}", location(expr)),
+    }
+}
+
+fn pattern_token<'t>(pattern: &Pattern<'t>) -> &'t Token<'t> {
+    match pattern {
+        Pattern::Wildcard(token) | Pattern::Binding(token) | Pattern::Literal(token, _) => token,
+        Pattern::Variant(variant) => variant.enum_name,
+    }
+}
+
+// the variable an assignment target is rooted in, `a` for both `a = ...` and `a[i].x = ...`
+fn place_root<'a, 't>(expr: &'a Expr<'t>) -> Option<&'a Variable<'t>> {
+    match expr {
+        Expr::Variable(variable) => Some(variable),
+        Expr::Index(index) => place_root(index.array.as_ref()),
+        Expr::Field(field) => place_root(field.object.as_ref()),
+        _ => None,
+    }
+}
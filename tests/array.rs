@@ -0,0 +1,185 @@
+mod common;
+
+#[test]
+fn test_index_literal() {
+    assert!(matches!(common::run_test("[10, 20, 30][1]").result, common::TestResult::Execution(20)));
+    assert!(matches!(common::run_test("[10, 20, 30,][2]").result, common::TestResult::Execution(30)));
+}
+
+#[test]
+fn test_index_variable() {
+    let input = "let a = [3, 1, 4, 1, 5]; a[0] + a[2] + a[4]";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(12)));
+}
+
+#[test]
+fn test_index_expression() {
+    let input = "let a = [3, 1, 4, 1, 5]; let i = 1; a[i + 1] * a[4 - i]";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(4)));
+}
+
+#[test]
+fn test_element_assignment() {
+    let input = "
+    let mut a = [0, 0, 0];
+    a[0] = 1;
+    a[2] = 3;
+    a[0] * 100 + a[1] * 10 + a[2]
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(103)));
+}
+
+#[test]
+fn test_whole_array_assignment() {
+    let input = "let mut a = [1, 2]; let b = a; a = [5, 6]; a[0] + a[1] * 10 + b[0] * 100";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(165)));
+}
+
+#[test]
+fn test_nested_arrays() {
+    let input = "
+    let mut grid = [[1, 2, 3], [4, 5, 6]];
+    grid[1][2] = 60;
+    let row = grid[1];
+    grid[0][1] + row[2] + [[7, 8], [9, 10]][1][0]
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(71)));
+}
+
+#[test]
+fn test_bool_array() {
+    let input = "let flags = [true, false]; if flags[1] { 1 } else { 2 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(2)));
+}
+
+#[test]
+fn test_len() {
+    assert!(matches!(common::run_test("len([1, 2, 3, 4])").result, common::TestResult::Execution(4)));
+    assert!(matches!(common::run_test("let a = [[1, 2], [3, 4], [5, 6]]; len(a) * 10 + len(a[0])").result,
+        common::TestResult::Execution(32)));
+}
+
+#[test]
+fn test_sum_loop() {
+    let input = "
+    let a = [5, 10, 15, 20];
+    let mut sum = 0;
+    let mut i = 0;
+    while i < len(a) {
+        sum = sum + a[i];
+        i = i + 1;
+    }
+    sum
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(50)));
+}
+
+#[test]
+fn test_bubble_sort() {
+    let input = "
+    let mut a = [5, 2, 9, 1, 7];
+    let mut i = 0;
+    while i < len(a) {
+        let mut j = 0;
+        while j < len(a) - 1 - i {
+            if a[j] > a[j + 1] {
+                let tmp = a[j];
+                a[j] = a[j + 1];
+                a[j + 1] = tmp;
+            }
+            j = j + 1;
+        }
+        i = i + 1;
+    }
+    print(a[0]); print(a[1]); print(a[2]); print(a[3]); print(a[4]);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "1\n2\n5\n7\n9\n");
+}
+
+#[test]
+fn test_pass_and_return_arrays() {
+    // 301 + 12 wraps around to 57 as an exit status
+    let input = "
+    fn reverse(a: [int; 3]) -> [int; 3] {
+        [a[2], a[1], a[0]]
+    }
+    fn sum(a: [int; 3], scale: int) -> int {
+        (a[0] + a[1] + a[2]) * scale
+    }
+    let r = reverse([1, 2, 3]);
+    r[0] * 100 + r[2] + sum(r, 2)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(57)));
+}
+
+#[test]
+fn test_arguments_spill_to_stack() {
+    // the second array only partly fits in registers
+    let input = "
+    fn pick(a: [int; 4], b: [int; 4], i: int) -> int {
+        a[i] * 10 + b[i]
+    }
+    pick([1, 2, 3, 4], [5, 6, 7, 8], 3)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(48)));
+}
+
+#[test]
+fn test_return_array_from_main() {
+    let input = "
+    fn make() -> [int; 2] { [4, 5] }
+    fn main() -> int { make()[1] }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(5)));
+}
+
+#[test]
+fn test_array_in_block() {
+    let input = "let a = { let x = 1; let y = 2; [x, y, x + y] }; a[2]";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(3)));
+}
+
+#[test]
+fn test_out_of_bounds() {
+    let input = "let a = [1, 2, 3]; let i = 3; a[i]";
+    let common::Outcome {result, stderr, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(202)));
+    assert_eq!(stderr, "error: index out of bounds: the len is 3 but the index is 3\n");
+}
+
+#[test]
+fn test_negative_index() {
+    let input = "let i = -1; [1, 2, 3][i]";
+    let common::Outcome {result, stderr, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(202)));
+    assert_eq!(stderr, "error: index out of bounds: the len is 3 but the index is -1\n");
+}
+
+#[test]
+fn test_out_of_bounds_assignment() {
+    let input = "let mut a = [1, 2]; a[5] = 0; 0";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(202)));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(common::run_test("[]").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("[1, 2").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("fn f(a: [int; 0]) -> int { 0 } 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("fn f(a: [int]) -> int { 0 } 0").result, common::TestResult::ParseError));
+}
+
+#[test]
+fn test_type_errors() {
+    assert!(matches!(common::run_test("[1, true]").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("[1, 2][true]").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("let x = 5; x[0]").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("let a = [1, 2]; a[0] = 5; 0").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("let mut a = [1, 2]; a = [1, 2, 3]; 0").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("[1, 2] == [1, 2]").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("fn f(a: [int; 2]) -> int { a[0] } f([1, 2, 3])").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("print([1])").result, common::TestResult::TypeError));
+}
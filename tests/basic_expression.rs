@@ -3,7 +3,7 @@ mod common;
 #[test]
 fn test_basic_expression() {
     let input = "(4 + 5 + -1) * 2";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::Execution(status_code) => status_code == 16,
         _ => false,
     });
@@ -12,7 +12,7 @@ fn test_basic_expression() {
 #[test]
 fn type_error() {
     let input = "5 < (5 < 5)";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::TypeError => true,
         _ => false,
     });
@@ -21,7 +21,7 @@ fn type_error() {
 #[test]
 fn type_error2() {
     let input = "5 == (5 < 5)";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::TypeError => true,
         _ => false,
     });
@@ -30,7 +30,7 @@ fn type_error2() {
 #[test]
 fn test_less_than() {
     let input = "5 < 6";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::Execution(status_code) => status_code == 1,
         _ => false,
     });
@@ -40,7 +40,7 @@ fn test_less_than() {
 #[test]
 fn test_equal_fail() {
     let input = "5 == 7";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::Execution(status_code) => status_code == 0,
         _ => false,
     });
@@ -49,7 +49,7 @@ fn test_equal_fail() {
 #[test]
 fn test_if() {
     let input = "if 2 > 1 { 1} else { 0 }";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::Execution(status_code) => status_code == 1,
         _ => false,
     });
@@ -58,7 +58,7 @@ fn test_if() {
 #[test]
 fn test_if_type_error() {
     let input = "if 2 > 1 { 1 } else { 0 > 1 }";
-    assert!(match common::run_test(input) {
+    assert!(match common::run_test(input).result {
         common::TestResult::TypeError => true,
         _ => false,
     });
@@ -0,0 +1,107 @@
+mod common;
+
+#[test]
+fn test_block_value() {
+    let input = "let x = { let a = 2; let b = 3; a * b }; x + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(7)));
+}
+
+#[test]
+fn test_statements_discard_values() {
+    // every intermediate value is popped, so the stack stays balanced around the loop
+    let input = "
+    let mut i = 0;
+    let mut sum = 0;
+    while i < 100 {
+        1 + 2;
+        sum = sum + i;
+        i = i + 1;
+    }
+    sum / 100
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(49)));
+}
+
+#[test]
+fn test_block_without_trailing_expression_is_unit() {
+    let input = "let x = { 1; }; x + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_block_scope() {
+    let input = "let x = 1; let y = { let x = 10; x + 1 }; x + y";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(12)));
+}
+
+#[test]
+fn test_block_scope_ends() {
+    let input = "let y = { let inner = 10; inner }; inner";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_shadowing_in_same_block() {
+    let input = "let x = 5; let x = x * 2; let x = x + 1; x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(11)));
+}
+
+#[test]
+fn test_function_body_block() {
+    let input = "
+    fn collatz_steps(start: int) -> int {
+        let mut n = start;
+        let mut steps = 0;
+        while n != 1 {
+            if n % 2 == 0 {
+                n = n / 2;
+            } else {
+                n = 3 * n + 1;
+            }
+            steps = steps + 1;
+        }
+        steps
+    }
+    collatz_steps(27)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(111)));
+}
+
+#[test]
+fn test_else_if() {
+    let input = "
+    fn sign(n: int) -> int {
+        if n < 0 { 0 - 1 } else if n == 0 { 0 } else { 1 }
+    }
+    sign(-5) + sign(0) * 10 + sign(7) * 100
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(99)));
+}
+
+#[test]
+fn test_nested_blocks_in_expression() {
+    let input = "{ let a = 4; a } * { let b = 5; { let c = b; c + 1 } }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(24)));
+}
+
+#[test]
+fn test_unit_function() {
+    let input = "
+    fn nothing(x: int) -> int { x; 3 }
+    let unused = { nothing(1); };
+    nothing(2)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(3)));
+}
+
+#[test]
+fn test_missing_semicolon() {
+    let input = "let x = 1; x + 1 x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::ParseError));
+}
+
+#[test]
+fn test_let_is_not_an_expression() {
+    let input = "(let x = 1; x)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::ParseError));
+}
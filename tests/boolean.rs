@@ -0,0 +1,87 @@
+mod common;
+
+#[test]
+fn test_true_literal() {
+    let input = "if true { 3 } else { 4 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(3)));
+}
+
+#[test]
+fn test_false_literal() {
+    let input = "false";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(0)));
+}
+
+#[test]
+fn test_not() {
+    let input = "let b = 1 > 2; !b";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_double_not() {
+    let input = "!!true == true";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_and() {
+    let input = "if 1 < 2 && 3 < 4 { 10 } else { 20 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(10)));
+}
+
+#[test]
+fn test_or() {
+    let input = "if 1 > 2 || 3 > 4 { 10 } else { 20 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(20)));
+}
+
+#[test]
+fn test_precedence() {
+    // && binds tighter than ||
+    let input = "true || false && false";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_and_short_circuits() {
+    // evaluating forever() would recurse until the stack overflows
+    let input = "fn forever(n: int) -> bool { forever(n) } false && forever(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(0)));
+}
+
+#[test]
+fn test_or_short_circuits() {
+    let input = "fn forever(n: int) -> bool { forever(n) } true || forever(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_not_type_error() {
+    let input = "!5";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_and_type_error() {
+    let input = "true && 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_or_type_error() {
+    let input = "1 || false";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_condition_type_error() {
+    let input = "if 1 { 2 } else { 3 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_single_ampersand() {
+    let input = "true & false";
+    assert!(matches!(common::run_test(input).result, common::TestResult::ScanError));
+}
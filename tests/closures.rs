@@ -0,0 +1,231 @@
+mod common;
+
+#[test]
+fn test_call_lambda() {
+    let input = "let inc = |x: int| x + 1; inc(41)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_call_lambda_directly() {
+    let input = "(|a: int, b: int| a * b)(6, 7)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_captured_variables() {
+    let input = "
+    let base = 30;
+    let scale = 2;
+    let f = |x: int| base + x * scale;
+    f(6)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_captures_by_value() {
+    let input = "
+    let mut n = 1;
+    let f = || n * 10;
+    n = 5;
+    f() + n
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(15)));
+}
+
+#[test]
+fn test_lambda_argument_types_from_parameter() {
+    let input = "
+    fn apply(f: fn(int, bool) -> int, x: int) -> int { f(x, true) }
+    let k = 3;
+    apply(|x, flag| if flag { x * k } else { 0 }, 14)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_named_function_as_value() {
+    let input = "
+    fn double(x: int) -> int { x * 2 }
+    fn twice(f: fn(int) -> int, x: int) -> int { f(f(x)) }
+    let g = double;
+    twice(double, 5) + g(1)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(22)));
+}
+
+#[test]
+fn test_return_closure() {
+    let input = "
+    fn make_adder(n: int) -> fn(int) -> int { |x: int| x + n }
+    let add_two = make_adder(2);
+    let add_ten = make_adder(10);
+    add_two(1) * 10 + add_ten(-9)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(31)));
+}
+
+#[test]
+fn test_nested_lambdas() {
+    // the inner lambda captures a through the outer one
+    let input = "
+    let a = 100;
+    let outer = |b: int| {
+        let inner = |c: int| a + b + c;
+        inner(1)
+    };
+    outer(20)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(121)));
+}
+
+#[test]
+fn test_wide_captures_and_results() {
+    let input = "
+    struct Point { x: int, y: int }
+    let offset = Point { x: 10, y: 20 };
+    let weights = [1, 2, 3];
+    let shift = |p: Point| Point { x: p.x + offset.x * weights[2], y: p.y + offset.y };
+    let moved = shift(Point { x: 1, y: 2 });
+    moved.x + moved.y
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(53)));
+}
+
+#[test]
+fn test_stack_arguments() {
+    let input = "
+    let k = 1000;
+    let f = |a: int, b: int, c: int, d: int, e: int, f: int, g: int, h: int| k + a + b * 2 + h * 100;
+    f(1, 2, 0, 0, 0, 0, 0, 2) - k
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(205)));
+}
+
+#[test]
+fn test_closures_in_arrays_and_structs() {
+    let input = "
+    struct Op { name: str, run: fn(int) -> int }
+    let n = 4;
+    let ops = [|x: int| x + n, |x: int| x * n, |x: int| x - n];
+    let op = Op { name: \"square\", run: |x: int| x * x };
+    let mut total = 0;
+    let mut i = 0;
+    while i < len(ops) {
+        total = total + ops[i](10);
+        i = i + 1;
+    }
+    print(op.name);
+    total + op.run(2)
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(64)));
+    assert_eq!(stdout, "square\n");
+}
+
+#[test]
+fn test_sort_with_callback() {
+    let input = "
+    fn sort(values: [int; 5], before: fn(int, int) -> bool) -> [int; 5] {
+        let mut sorted = values;
+        let mut i = 1;
+        while i < len(sorted) {
+            let mut j = i;
+            while j > 0 && before(sorted[j], sorted[j - 1]) {
+                let swap = sorted[j];
+                sorted[j] = sorted[j - 1];
+                sorted[j - 1] = swap;
+                j = j - 1;
+            }
+            i = i + 1;
+        }
+        sorted
+    }
+    fn map(values: [int; 5], f: fn(int) -> int) -> [int; 5] {
+        let mut mapped = values;
+        let mut i = 0;
+        while i < len(mapped) {
+            mapped[i] = f(mapped[i]);
+            i = i + 1;
+        }
+        mapped
+    }
+    let descending = sort([3, 1, 4, 1, 5], |a, b| a > b);
+    let doubled = map(descending, |x| x * 2);
+    let mut i = 0;
+    while i < len(doubled) {
+        print(doubled[i]);
+        i = i + 1;
+    }
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "10\n8\n6\n2\n2\n");
+}
+
+#[test]
+fn test_unit_lambda() {
+    let input = r#"
+    let greeting = "hello";
+    let say = |times: int| {
+        let mut i = 0;
+        while i < times {
+            print(greeting);
+            i = i + 1;
+        }
+    };
+    say(2);
+    0
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "hello\nhello\n");
+}
+
+#[test]
+fn test_many_closures() {
+    // enough allocations to need more than one chunk of heap memory
+    let input = "
+    let mut i = 0;
+    let mut total = 0;
+    while i < 100000 {
+        let a = i; let b = i; let c = i; let d = i; let e = i; let f = i; let g = i; let h = i;
+        let sum = || a + b + c + d + e + f + g + h;
+        total = sum() / 8 - i;
+        i = i + 1;
+    }
+    total
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(0)));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(common::run_test("let f = |x x + 1; 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("let f = |x: int x; 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("fn f(g: fn(int) int) -> int { 0 } 0").result, common::TestResult::ParseError));
+}
+
+#[test]
+fn test_type_errors() {
+    let cases = [
+        // nothing says what x is
+        "let mut f = |x| x; 0",
+        "let f = |x: int| x; f(true)",
+        "let f = |x: int| x; f(1, 2)",
+        "let n = 5; n(1)",
+        "let mut n = 1; let f = || { n = 2; 0 }; 0",
+        "let f = |x: int| x; f == f",
+        "let f = |x: int, x: int| x; 0",
+        "fn apply(f: fn(int) -> int) -> int { f(1) } apply(|x: bool| 1)",
+        "fn apply(f: fn(int) -> int) -> int { f(1) } apply(|x| x == 1)",
+        "fn apply(f: fn(int) -> int) -> int { f(1) } apply(|x, y| x)",
+        "fn f(g: fn(Missing) -> int) -> int { 0 } 0",
+        "let f = |x: int| y; 0",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::TypeError), "{}", case);
+    }
+}
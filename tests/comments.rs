@@ -0,0 +1,47 @@
+mod common;
+
+#[test]
+fn test_line_comments() {
+    let input = "
+    // a comment on its own line
+    let x = 6; // after a statement
+    print(x / 2); //no space, and / in the code before it
+    x //
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(6)));
+    assert_eq!(stdout, "3\n");
+}
+
+#[test]
+fn test_block_comments_nest() {
+    let input = "
+    /* one line */ let x = 4;
+    /*
+    print(x); /* nested
+    over lines */ print(x * 2);
+    */
+    x /* in the middle */ * 10
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(40)));
+    assert_eq!(stdout, "");
+}
+
+#[test]
+fn test_positions_after_comments() {
+    let input = "/* a
+    comment over
+    two more lines */ let x: bool = /* c */ 1; x";
+    let errors = common::run_test(input).errors;
+    assert_eq!(errors, ["Type mismatch between an integer type from the literal 1 at line 3, column 45 \
+        and bool from the annotation of x at line 3, column 27"]);
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let input = "let x = 1;\n  x /* open /* nested */\n still open";
+    let common::Outcome {errors: scan_errors, parse_errors, ..} = common::run_test(input);
+    assert_eq!(scan_errors, ["Unterminated block comment at line 2, column 5"]);
+    assert!(parse_errors.is_empty());
+}
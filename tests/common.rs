@@ -1,64 +1,137 @@
-use std::fmt::format;
 use std::process::Command;
-use std::{fs::File, fs::remove_file, io::Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fs, path::{Path, PathBuf}};
 
-
-
-use rcheer_lib::CompileResult;
-
-pub struct Test {
-    pub input: &'static str,
-    pub output: &'static str,
-}
+use rcheer_lib::{CompileResult, Options};
 
 pub enum TestResult {
     Execution(i32),
     TypeError,
     ScanError,
     ParseError,
+    ImportError,
 }
 
-pub fn run_test(input: &'static str) -> TestResult {
+// a test program and how to compile it, a &str is the program on its own with the default options
+#[derive(Default)]
+pub struct Test<'a> {
+    pub input: &'a str,
+    // files written into a directory of their own, pairs of a path and its contents. When there
+    // are any the program starts in the first one and input isn't used
+    pub files: &'a [(&'a str, &'a str)],
+    // where imports are looked for after the importing file's directory, relative to the files' directory
+    pub search_path: &'a [&'a str],
+    pub options: Options,
+}
 
-    let output = format!("test.s");
-    match rcheer_lib::compile(input) {
-        CompileResult::Program(asm) => {
-            let mut file = File::create(output.as_str())
-                .expect(format!("Unable to create output file: {}", output.as_str()).as_str());
-            file.write_all(asm.as_bytes())
-                .expect("Failed to write to output file: {:?}")
-        }   
-        CompileResult::ParseError(p) => {
-            println!("Error in parsing: {}", p.message);
-            return TestResult::ParseError;
+impl<'a> From<&'a str> for Test<'a> {
+    fn from(input: &'a str) -> Test<'a> {
+        Test {input, ..Test::default()}
+    }
+}
+
+// what compiling the program, and running it if it compiled, did
+pub struct Outcome {
+    pub result: TestResult,
+    pub stdout: String,
+    pub stderr: String,
+    pub asm: String,
+    pub warnings: Vec<String>,
+    pub notes: Vec<String>,
+    // the messages of the errors that stopped the program compiling, all of the kind result is
+    pub errors: Vec<String>,
+    // when the scanner found errors, the messages of the errors parsing what it recovered
+    pub parse_errors: Vec<String>,
+}
+
+// cargo runs tests in parallel, so each test gets its own directory for its files
+static TEST_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub fn run_test<'a>(test: impl Into<Test<'a>>) -> Outcome {
+    let test = test.into();
+    let test_id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let directory = std::env::temp_dir().join(format!("rcheer_test_{}_{}", std::process::id(), test_id));
+    fs::create_dir_all(&directory).expect("Unable to create test directory");
+    let compiled = match test.files.first() {
+        Some((main, _)) => {
+            for (path, contents) in test.files {
+                let path = directory.join(path);
+                fs::create_dir_all(path.parent().unwrap()).expect("Unable to create test directory");
+                fs::write(path, contents).expect("Unable to write test file");
+            }
+            let search_path: Vec<PathBuf> = test.search_path.iter().map(|path| directory.join(path)).collect();
+            rcheer_lib::compile_file(&directory.join(main), &search_path, &test.options)
         }
-        CompileResult::ScanError(s) => {
-            println!("Error in scanning: {:?}", s);
-            return TestResult::ScanError;
+        None => rcheer_lib::compile_with_options(test.input, &test.options),
+    };
+
+    let mut outcome = Outcome {result: TestResult::Execution(0), stdout: String::new(), stderr: String::new(),
+        asm: String::new(), warnings: Vec::new(), notes: Vec::new(), errors: Vec::new(), parse_errors: Vec::new()};
+    match compiled {
+        CompileResult::Program {asm, warnings, notes} => {
+            outcome.asm = asm;
+            outcome.warnings = warnings.into_iter().map(|warning| warning.message).collect();
+            outcome.notes = notes;
+            outcome.execute(&directory);
+        }
+        CompileResult::ScanError(scan_errors, parse_errors) => {
+            outcome.result = TestResult::ScanError;
+            outcome.errors = scan_errors.into_iter().map(|error| error.message).collect();
+            outcome.parse_errors = parse_errors.into_iter().map(|error| error.message).collect();
+        }
+        CompileResult::ParseError(errors) => {
+            outcome.result = TestResult::ParseError;
+            outcome.errors = errors.into_iter().map(|error| error.message).collect();
         }
         CompileResult::TypeError(errors) => {
-            println!("Error in type checking");
-            return TestResult::TypeError;
+            outcome.result = TestResult::TypeError;
+            outcome.errors = errors.into_iter().map(|error| error.message).collect();
         }
-    };
-    let gcc_status = Command::new("gcc")
-        .arg(output)
-        .status();
-    match gcc_status {
-        Ok(s) => {
-            println!("GCC status: {}", s);
-        },
-        Err(e) => {
-            panic!("GCC failed to compile: {}", e)
+        CompileResult::ImportError(error) => {
+            outcome.result = TestResult::ImportError;
+            outcome.errors = vec![error.message];
+        }
+    }
+    fs::remove_dir_all(&directory).ok();
+    outcome.print();
+    outcome
+}
+
+impl Outcome {
+    fn execute(&mut self, directory: &Path) {
+        let output = directory.join("test.s");
+        let executable = directory.join("test.out");
+        fs::write(&output, &self.asm).expect("Unable to write the assembly");
+        match Command::new("gcc").arg(&output).arg("-o").arg(&executable).status() {
+            Ok(status) => println!("GCC status: {}", status),
+            Err(error) => panic!("GCC failed to compile: {}", error),
         }
+        let execution = Command::new(&executable).output()
+            .unwrap_or_else(|error| panic!("running executable error: {}", error));
+        self.stdout = String::from_utf8(execution.stdout).expect("program output should be utf-8");
+        self.stderr = String::from_utf8(execution.stderr).expect("program output should be utf-8");
+        self.result = TestResult::Execution(execution.status.code().unwrap());
     }
-    let status = Command::new("./a.out").status();
-    match status {
-        Ok(s) => {
-            println!("status {}", s.code().unwrap());
-            TestResult::Execution(s.code().unwrap())}
-        Err(e) => {
-            panic!("running executable error: {}", e)
+
+    // cargo shows what a failing test printed
+    fn print(&self) {
+        match self.result {
+            TestResult::Execution(status) => println!("status {}", status),
+            TestResult::TypeError => println!("Error in type checking"),
+            TestResult::ScanError => println!("Error in scanning"),
+            TestResult::ParseError => println!("Error in parsing"),
+            TestResult::ImportError => println!("Error in importing"),
+        }
+        for message in self.errors.iter().chain(&self.parse_errors) {
+            println!("{}", message);
         }
+        for warning in &self.warnings {
+            println!("warning: {}", warning);
+        }
+        for note in &self.notes {
+            println!("note: {}", note);
+        }
+        println!("stdout {:?}", self.stdout);
+        println!("stderr {:?}", self.stderr);
     }
-}
\ No newline at end of file
+}
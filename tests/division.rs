@@ -0,0 +1,68 @@
+mod common;
+
+#[test]
+fn test_divide() {
+    let input = "84 / 2";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_divide_truncates_toward_zero() {
+    let input = "0 - 7 / 2 == -3 && -7 / 2 == -3";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_modulo() {
+    let input = "47 % 10";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(7)));
+}
+
+#[test]
+fn test_negative_modulo() {
+    // the remainder takes the sign of the dividend
+    let input = "-7 % 3 == -1 && 7 % -3 == 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_precedence() {
+    let input = "2 + 12 / 4 * 3 % 5";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(6)));
+}
+
+#[test]
+fn test_divide_by_zero() {
+    let input = "let zero = 0; 10 / zero";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(200)));
+}
+
+#[test]
+fn test_modulo_by_zero() {
+    let input = "10 % (5 - 5)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(200)));
+}
+
+#[test]
+fn test_divide_overflow() {
+    let input = "let min = -9223372036854775808; min / -1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(201)));
+}
+
+#[test]
+fn test_modulo_overflow() {
+    let input = "let min = -9223372036854775808; min % -1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(201)));
+}
+
+#[test]
+fn test_min_divided_by_other_values() {
+    let input = "let min = -9223372036854775808; min / 2 == -4611686018427387904";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_divide_type_error() {
+    let input = "10 / true";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
@@ -0,0 +1,211 @@
+mod common;
+
+#[test]
+fn test_match_on_variants() {
+    let input = "
+    enum Shape { Circle(int), Rect(int, int), Empty }
+    fn area(shape: Shape) -> int {
+        match shape {
+            Shape::Circle(r) => 3 * r * r,
+            Shape::Rect(w, h) => w * h,
+            Shape::Empty => 0,
+        }
+    }
+    area(Shape::Circle(2)) + area(Shape::Rect(3, 5)) + area(Shape::Empty)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(27)));
+}
+
+#[test]
+fn test_unit_variants() {
+    let input = "
+    enum Color { Red, Green, Blue }
+    let c = Color::Green;
+    match c { Color::Red => 1, Color::Green => 2, Color::Blue => 3 }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(2)));
+}
+
+#[test]
+fn test_nested_patterns() {
+    let input = "
+    enum Option { Some(int), None }
+    enum Pair { Both(Option, Option) }
+    fn score(pair: Pair) -> int {
+        match pair {
+            Pair::Both(Option::Some(0), _) => 100,
+            Pair::Both(Option::Some(a), Option::Some(b)) => a * 10 + b,
+            Pair::Both(Option::Some(a), Option::None) => a,
+            Pair::Both(Option::None, _) => 7,
+        }
+    }
+    score(Pair::Both(Option::Some(0), Option::None))
+        + score(Pair::Both(Option::Some(4), Option::Some(2)))
+        + score(Pair::Both(Option::Some(5), Option::None))
+        + score(Pair::Both(Option::None, Option::Some(9)))
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(154)));
+}
+
+#[test]
+fn test_literal_patterns() {
+    let input = "
+    fn describe(n: int, flag: bool) -> int {
+        match n {
+            0 => match flag { true => 1, false => 2 },
+            1 => 10,
+            other => other * 10,
+        }
+    }
+    describe(0, true) + describe(0, false) + describe(1, true) + describe(3, false)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(43)));
+}
+
+#[test]
+fn test_wide_payloads() {
+    let input = r#"
+    struct Point { x: int, y: int }
+    enum Value { Text(str), Pos(Point), Many([int; 3], bool) }
+    fn total(value: Value) -> int {
+        match value {
+            Value::Text(s) => { print(s); len(s) },
+            Value::Pos(p) => p.x * 10 + p.y,
+            Value::Many(values, flag) => if flag { values[0] + values[1] + values[2] } else { 0 },
+        }
+    }
+    total(Value::Text("hello")) + total(Value::Pos(Point { x: 3, y: 4 })) + total(Value::Many([1, 2, 3], true))
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(45)));
+    assert_eq!(stdout, "hello\n");
+}
+
+#[test]
+fn test_return_enums() {
+    let input = "
+    enum Result { Ok(int), Err(int) }
+    fn divide(a: int, b: int) -> Result {
+        if b == 0 { Result::Err(1) } else { Result::Ok(a / b) }
+    }
+    fn unwrap_or(result: Result, default: int) -> int {
+        match result { Result::Ok(value) => value, Result::Err(_) => default }
+    }
+    unwrap_or(divide(20, 4), 0) * 10 + unwrap_or(divide(1, 0), 3)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(53)));
+}
+
+#[test]
+fn test_match_with_blocks_and_locals() {
+    let input = "
+    enum Op { Add(int, int), Neg(int) }
+    let ops = [Op::Add(1, 2), Op::Neg(4), Op::Add(10, 20)];
+    let mut sum = 0;
+    let mut i = 0;
+    while i < len(ops) {
+        let value = match ops[i] {
+            Op::Add(a, b) => {
+                let total = a + b;
+                total
+            }
+            Op::Neg(a) => 0 - a,
+        };
+        sum = sum + value;
+        i = i + 1;
+    }
+    sum
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(29)));
+}
+
+#[test]
+fn test_enum_in_struct() {
+    let input = "
+    enum Kind { Small, Big(int) }
+    struct Item { kind: Kind, count: int }
+    let mut item = Item { kind: Kind::Small, count: 2 };
+    item.kind = Kind::Big(5);
+    match item.kind { Kind::Small => item.count, Kind::Big(n) => n * item.count }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(10)));
+}
+
+#[test]
+fn test_recursive_enums() {
+    let input = "
+    enum List { Nil, Cons(int, List) }
+    fn sum(list: List) -> int {
+        match list {
+            List::Nil => 0,
+            List::Cons(head, tail) => head + sum(tail),
+        }
+    }
+    fn second(list: List) -> int {
+        match list {
+            List::Cons(_, List::Cons(x, _)) => x,
+            _ => 0,
+        }
+    }
+    let list = List::Cons(4, List::Cons(5, List::Cons(6, List::Nil)));
+    sum(list) * 10 + second(list)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(155)));
+}
+
+#[test]
+fn test_unreachable_arm_warning() {
+    let input = "
+    enum Color { Red, Green }
+    match Color::Red { _ => 1, Color::Green => 2 }
+    ";
+    let warnings = common::run_test(input).warnings;
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("Unreachable match arm"), "{}", warnings[0]);
+    let exhaustive = common::run_test("match true { true => 1, false => 0 }");
+    assert!(matches!(exhaustive.result, common::TestResult::Execution(1)));
+    assert!(exhaustive.warnings.is_empty());
+}
+
+#[test]
+fn test_non_exhaustive_match() {
+    let cases = [
+        "enum Color { Red, Green } match Color::Red { Color::Red => 1 }",
+        "enum Option { Some(bool), None } match Option::None { Option::Some(true) => 1, Option::None => 0 }",
+        "match 3 { 0 => 1, 1 => 2 }",
+        "match true { true => 1 }",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::TypeError), "{}", case);
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(common::run_test("enum Empty {} 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("enum E { A(int } 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("match 1 { 1 => 2 3 => 4 }").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("match 1 { }").result, common::TestResult::ParseError));
+}
+
+#[test]
+fn test_type_errors() {
+    let color = "enum Color { Red, Green(int) }";
+    let cases = [
+        "Color::Blue; 0",
+        "Color::Green; 0",
+        "Color::Red(1); 0",
+        "Color::Green(true); 0",
+        "let c = Color::Red; c == c",
+        "match Color::Red { Color::Red => 1, Color::Green(x) => true }",
+        "match Color::Red { Color::Red => 1, Color::Green(x, y) => 2 }",
+        "match 1 { true => 1, _ => 2 }",
+        "match Color::Red { Color::Red => 1, Color::Green(x) => x, _ => y }",
+        "enum Color { Blue } 0",
+        "struct Color { x: int } 0",
+    ];
+    for case in cases {
+        let input = format!("{} {}", color, case);
+        assert!(matches!(common::run_test(input.as_str()).result, common::TestResult::TypeError), "{}", case);
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+#[test]
+fn test_float_literals_and_printing() {
+    let input = "
+    print(1.5);
+    print(-2.25);
+    print(3.0);
+    print(0.1 + 0.2);
+    print(1.0 / 3.0);
+    print(2.5e-3);
+    print(1e20);
+    print(1.5E-7);
+    print(0.00001);
+    print(123456789.125);
+    print(1.0 / 0.0);
+    print(-1.0 / 0.0);
+    print(0.0 / 0.0);
+    print(-0.0);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "1.5\n-2.25\n3.0\n0.3\n0.333333333333333\n0.0025\n1e20\n1.5e-7\n0.00001\n\
+        123456789.125\ninf\n-inf\nNaN\n-0.0\n");
+}
+
+#[test]
+fn test_float_arithmetic() {
+    let input = "
+    fn sqrt(x: float) -> float {
+        let mut guess = x / 2.0;
+        let mut i = 0;
+        while i < 30 {
+            guess = (guess + x / guess) / 2.0;
+            i = i + 1;
+        }
+        guess
+    }
+    struct Point { x: float, y: float }
+    let p = Point { x: 3.0, y: 4.0 };
+    let scale = 0.5;
+    let halve = |value: float| value * scale;
+    print(sqrt(p.x * p.x + p.y * p.y));
+    print(halve(-p.x) - 1.0);
+    print([1.5, 2.5][1] * 4.0);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "5.0\n-2.5\n10.0\n");
+}
+
+#[test]
+fn test_float_comparisons() {
+    let input = "
+    let nan = 0.0 / 0.0;
+    print(1.5 < 2.5);
+    print(2.5 <= 2.5);
+    print(-1.0 > 1.0);
+    print(0.1 + 0.2 == 0.3);
+    print(nan == nan);
+    print(nan != nan);
+    print(nan < 1.0);
+    print(nan >= 1.0);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "true\ntrue\nfalse\nfalse\nfalse\ntrue\nfalse\nfalse\n");
+}
+
+#[test]
+fn test_casts_between_integers_and_floats() {
+    let input = "
+    print(7 as float / 2 as float);
+    print(255u8 as float);
+    print(18446744073709551615u64 as float);
+    print(3.9 as int);
+    print(-3.9 as int);
+    print(300.5 as u8);
+    print(-5.0 as u8);
+    print(1e30 as i64);
+    print((0.0 / 0.0) as i32);
+    print(1.8e19 as u64);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "3.5\n255.0\n1.84467440737096e19\n3\n-3\n255\n0\n9223372036854775807\n0\n\
+        18000000000000000000\n");
+}
+
+#[test]
+fn test_floats_are_not_integers() {
+    for input in ["1 + 1.0", "let x: float = 1; 0", "5.0 % 2.0", "1.0 +% 2.0", "true as float", "(1.5 as float) as bool"] {
+        assert!(matches!(common::run_test(input).result, common::TestResult::TypeError), "{}", input);
+    }
+    for input in ["1.5u8", "1e400"] {
+        assert!(matches!(common::run_test(input).result, common::TestResult::ScanError), "{}", input);
+    }
+}
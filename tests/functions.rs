@@ -0,0 +1,107 @@
+mod common;
+
+#[test]
+fn test_call() {
+    let input = "fn add(a: int, b: int) -> int { a + b } add(3, 4) * 2";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(14)));
+}
+
+#[test]
+fn test_main_function() {
+    let input = "fn main() -> int { 42 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_recursion() {
+    let input = "
+    fn fact(n: int) -> int { if n < 2 { 1 } else { n * fact(n - 1) } }
+    fn main() -> int { fact(5) }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(120)));
+}
+
+#[test]
+fn test_call_before_definition() {
+    let input = "
+    fn main() -> int { double(21) }
+    fn double(x: int) -> int { x * 2 }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_stack_arguments() {
+    // the seventh and eighth arguments are passed on the stack
+    let input = "
+    fn pick(a: int, b: int, c: int, d: int, e: int, f: int, g: int, h: int) -> int { a - b + g * 10 - h }
+    pick(9, 1, 0, 0, 0, 0, 5, 2)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(56)));
+}
+
+#[test]
+fn test_odd_stack_arguments() {
+    let input = "
+    fn last(a: int, b: int, c: int, d: int, e: int, f: int, g: int) -> int { g - a }
+    let x = 1; last(x, 2, 3, 4, 5, 6, 10)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(9)));
+}
+
+#[test]
+fn test_nested_calls() {
+    let input = "
+    fn sub(a: int, b: int) -> int { a - b }
+    let x = 50; sub(x, sub(20, 5)) + sub(1, 1)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(35)));
+}
+
+#[test]
+fn test_bool_function() {
+    let input = "fn is_big(n: int) -> bool { n > 100 } if is_big(200) { 7 } else { 3 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(7)));
+}
+
+#[test]
+fn test_arity_error() {
+    let input = "fn add(a: int, b: int) -> int { a + b } add(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_argument_type_error() {
+    let input = "fn add(a: int, b: int) -> int { a + b } add(1, 2 < 3)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_return_type_error() {
+    let input = "fn f(a: int) -> bool { a } f(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_function_does_not_see_caller_locals() {
+    let input = "fn f() -> int { x } let x = 1; f()";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_undefined_function() {
+    let input = "missing(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_missing_main() {
+    let input = "fn f() -> int { 1 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_missing_return_type() {
+    let input = "fn f() { 1 } f()";
+    assert!(matches!(common::run_test(input).result, common::TestResult::ParseError));
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use rcheer_lib::Options;
+
+fn with_stats() -> Options {
+    Options {gc_stats: true, ..Options::default()}
+}
+
+// the numbers in the statistics line: collections, allocated, freed, in use and heap bytes
+fn stats(stderr: &str) -> Vec<u64> {
+    let line = stderr.lines().find(|line| line.starts_with("gc: ")).expect("expected gc statistics");
+    line.split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_garbage_is_collected() {
+    let input = "
+    let mut i = 0;
+    let mut total = 0;
+    while i < 200000 {
+        let k = i;
+        let add = |x: int| x + k;
+        total = total + add(1) - i;
+        i = i + 1;
+    }
+    total / 1000
+    ";
+    let common::Outcome {result, stderr, ..} = common::run_test(common::Test {input, options: with_stats(), ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(200)));
+    let stats = stats(&stderr);
+    assert!(stats[0] > 0, "{}", stderr);
+    assert!(stats[1] >= 200000 * 32, "{}", stderr);
+    assert_eq!(stats[1] - stats[2], stats[3], "{}", stderr);
+    assert!(stats[4] <= 2 << 20, "{}", stderr);
+}
+
+#[test]
+fn test_reachable_closures_survive() {
+    let input = "
+    let mut chain = |x: int| x;
+    let mut i = 0;
+    while i < 20000 {
+        let next = chain;
+        chain = |x: int| next(x) + 1;
+        let k = i;
+        let garbage = |x: int| x * k;
+        garbage(i);
+        i = i + 1;
+    }
+    chain(0) / 100
+    ";
+    let common::Outcome {result, stderr, ..} = common::run_test(common::Test {input, options: with_stats(), ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(200)));
+    assert!(stats(&stderr)[0] > 0, "{}", stderr);
+}
+
+#[test]
+fn test_pointers_inside_aggregates_are_found() {
+    let captured: Vec<String> = (0..70).map(|i| format!("|x: int| x + {}", i)).collect();
+    let input = format!("
+    enum Maybe {{ Nothing, Just(int, fn(int) -> int) }}
+    struct Pair {{ count: int, f: fn(int) -> int }}
+    fn keep(many: [fn(int) -> int; 70], maybe: Maybe, pair: Pair) -> fn(int) -> int {{
+        |x: int| match maybe {{
+            Maybe::Just(n, f) => f(x) + n + many[69](0) + pair.f(pair.count),
+            Maybe::Nothing => 0,
+        }}
+    }}
+    let k = 2;
+    let f = keep([{}], Maybe::Just(1, |x: int| x * k), Pair {{ count: 3, f: |x: int| x + k }});
+    let mut i = 0;
+    while i < 100000 {{
+        let garbage = |x: int| x + i;
+        garbage(0);
+        i = i + 1;
+    }}
+    f(10)
+    ", captured.join(", "));
+    let common::Outcome {result, stderr, ..} = common::run_test(common::Test {input: &input, options: with_stats(), ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(95)));
+    assert!(stats(&stderr)[0] > 0, "{}", stderr);
+}
+
+#[test]
+fn test_objects_bigger_than_a_chunk() {
+    let numbers = vec!["7"; 140000].join(", ");
+    let input = format!("
+    let numbers = [{}];
+    let mut i = 0;
+    let mut total = 0;
+    while i < 20 {{
+        let last = || numbers[139999] + numbers[0];
+        total = total + last();
+        i = i + 1;
+    }}
+    total / 10
+    ", numbers);
+    let common::Outcome {result, stderr, ..} = common::run_test(common::Test {input: &input, options: with_stats(), ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(28)), "{}", stderr);
+    assert!(stats(&stderr)[4] > 1 << 20, "{}", stderr);
+}
+
+#[test]
+fn test_stats_only_when_asked_for() {
+    let input = "let k = 1; let f = |x: int| x + k; f(1)";
+    let common::Outcome {result, stderr, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(2)));
+    assert_eq!(stderr, "");
+    let stderr = common::run_test(common::Test {input, options: with_stats(), ..Default::default()}).stderr;
+    assert_eq!(stderr, "gc: 0 collections, 32 bytes allocated, 0 bytes freed, 32 bytes in use, 1048576 bytes of heap\n");
+}
+
+#[test]
+fn test_recursive_enums_and_structs_live_on_the_heap() {
+    let input = "
+    enum List<T> { Nil, Cons(T, List<T>) }
+    struct Tree { value: int, children: List<Tree> }
+    fn range(n: int) -> List<int> {
+        let mut list = List::Nil;
+        let mut i = 0;
+        while i < n {
+            list = List::Cons(i, list);
+            i = i + 1;
+        }
+        list
+    }
+    fn sum(list: List<int>) -> int {
+        match list {
+            List::Nil => 0,
+            List::Cons(head, tail) => head + sum(tail),
+        }
+    }
+    fn total(tree: Tree) -> int {
+        tree.value + forest(tree.children)
+    }
+    fn forest(trees: List<Tree>) -> int {
+        match trees {
+            List::Nil => 0,
+            List::Cons(tree, rest) => total(tree) + forest(rest),
+        }
+    }
+    let kept = range(1000);
+    let leaf = Tree { value: 1, children: List::Nil };
+    let tree = Tree { value: 2, children: List::Cons(leaf, List::Cons(Tree { value: 3, children: List::Cons(leaf, List::Nil) }, List::Nil)) };
+    let mut round = 0;
+    while round < 300 {
+        let garbage = range(1000);
+        round = round + 1;
+    }
+    sum(kept) - 499500 + total(tree)
+    ";
+    let common::Outcome {result, stderr, ..} = common::run_test(common::Test {input, options: with_stats(), ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(7)), "{}", stderr);
+    let stats = stats(&stderr);
+    assert!(stats[0] > 0, "{}", stderr);
+    assert!(stats[2] > 0, "{}", stderr);
+    assert!(stats[4] <= 2 << 20, "{}", stderr);
+}
+
+#[test]
+fn test_roots_in_every_frame_are_found() {
+    let input = "
+    enum List { Nil, Cons(int, List) }
+    fn sum(list: List) -> int {
+        match list {
+            List::Nil => 0,
+            List::Cons(head, tail) => head + sum(tail),
+        }
+    }
+    fn churn(n: int) -> int {
+        let mut i = 0;
+        while i < n {
+            let garbage = List::Cons(i, List::Nil);
+            i = i + 1;
+        }
+        0
+    }
+    fn deep(depth: int, a: int, b: int, c: int, d: int, e: int, kept: List) -> int {
+        let mine = List::Cons(depth, kept);
+        if depth == 0 {
+            churn(100000) + sum(mine)
+        } else {
+            let f = deep;
+            1 + f(depth - 1, a, b, c, d, e, mine) - 1
+        }
+    }
+    deep(50, 0, 0, 0, 0, 0, List::Nil) / 5
+    ";
+    let common::Outcome {result, stderr, ..} = common::run_test(common::Test {input, options: with_stats(), ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(255)), "{}", stderr);
+    assert!(stats(&stderr)[0] > 0, "{}", stderr);
+}
@@ -0,0 +1,134 @@
+mod common;
+
+#[test]
+fn test_generic_functions() {
+    let input = r#"
+    fn id<T>(x: T) -> T { x }
+    fn pick<T>(flag: bool, a: T, b: T) -> T { if flag { a } else { b } }
+    print(id("hello"));
+    if id(true) { pick(false, 1, id(42)) } else { 0 }
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(42)));
+    assert_eq!(stdout, "hello\n");
+}
+
+#[test]
+fn test_generic_structs() {
+    let input = r#"
+    struct Pair<A, B> { first: A, second: B }
+    fn swap<A, B>(p: Pair<A, B>) -> Pair<B, A> { Pair { first: p.second, second: p.first } }
+    let p = swap(Pair { first: 1, second: "two" });
+    let nested = swap(swap(Pair { first: true, second: Pair { first: 5, second: 6 } }));
+    let mut q: Pair<int, [int; 2]> = Pair { second: [10, 20], first: 3 };
+    q.second[1] = 30;
+    print(p.first);
+    if nested.first { p.second + nested.second.first + q.first + q.second[1] } else { 0 }
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(39)));
+    assert_eq!(stdout, "two\n");
+}
+
+#[test]
+fn test_generic_enums() {
+    let input = "
+    struct Point { x: int, y: int }
+    enum Option<T> { Some(T), None }
+    fn unwrap_or<T>(option: Option<T>, default: T) -> T {
+        match option { Option::Some(value) => value, Option::None => default }
+    }
+    fn map<T, U>(option: Option<T>, f: fn(T) -> U) -> Option<U> {
+        match option { Option::Some(value) => Option::Some(f(value)), Option::None => Option::None }
+    }
+    let none: Option<Point> = Option::None;
+    let point = unwrap_or(none, Point { x: 3, y: 4 });
+    let doubled = map(Option::Some(point), |p| p.x * p.y);
+    let big = unwrap_or(map(Option::Some(5), |n| n > 1), false);
+    if big { unwrap_or(doubled, 0) + 30 } else { 0 }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_generic_function_values() {
+    let input = "
+    fn apply<T, U>(f: fn(T) -> U, x: T) -> U { f(x) }
+    fn first<T>(values: [T; 3]) -> T { let value: T = values[0]; value }
+    let get = first;
+    let twice = |n| n * 2;
+    apply(twice, get([20, 0, 0])) + len(apply(first, [\"ab\", \"c\", \"d\"]))
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_generic_functions_calling_each_other() {
+    let input = "
+    struct Pair<A, B> { first: A, second: B }
+    fn make<A, B>(a: A, b: B) -> Pair<A, B> { Pair { first: a, second: b } }
+    fn dup<T>(x: T) -> Pair<T, T> { make(x, x) }
+    fn count<T>(x: T, n: int) -> int { if n == 0 { 0 } else { 1 + count(x, n - 1) } }
+    let p = dup(dup(20));
+    p.second.first + p.first.second + count(p, 2)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_instances_have_mangled_names() {
+    let input = "
+    struct Pair<A, B> { first: A, second: B }
+    fn id<T>(x: T) -> T { x }
+    id(Pair { first: [1, 2], second: true });
+    id(1) + id(2)
+    ";
+    let asm = common::run_test(input).asm;
+    assert_eq!(asm.matches("\tcheer_id.i:").count(), 1);
+    assert_eq!(asm.matches("\tcheer_id.4PairIA2_ibE:").count(), 1);
+    assert!(!asm.contains("\tcheer_id:"));
+}
+
+#[test]
+fn test_parse_errors() {
+    let cases = [
+        "fn id<>(x: int) -> int { x } 0",
+        "fn id<T(x: T) -> T { x } 0",
+        "struct Pair<A, B { a: A } 0",
+        "struct Box<T> { v: T } let b: Box<int = Box { v: 1 }; 0",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::ParseError), "{}", case);
+    }
+}
+
+#[test]
+fn test_type_errors() {
+    let cases = [
+        "fn bad<T>(x: T) -> int { x + 1 } 0",
+        "fn bad<T, U>(x: T) -> U { x } 0",
+        "fn f<T, T>(x: T) -> T { x } 0",
+        "fn show<T>(x: T) -> int { print(x); 0 } show(1)",
+        "fn same<T>(a: T, b: T) -> bool { a == b } same(1, 2)",
+        "fn pick<T>(a: T, b: T) -> T { a } pick(1, true)",
+        "enum Option<T> { Some(T), None } fn none<T>() -> Option<T> { Option::None } let x = none(); 0",
+        "struct Box<T> { v: T } let b: Box<int> = Box { v: true }; 0",
+        "struct Box<T> { v: T } let b: Box<int, int> = Box { v: 1 }; 0",
+        "struct Box<T> { v: T } let b: Box = Box { v: 1 }; 0",
+        "struct Box<T> { v: T } fn f<T>(b: Box<T>) -> int { b.v } 0",
+        "struct Box<T> { v: T } struct Loop { b: Box<Loop> } 0",
+        "fn main<T>() -> int { 0 }",
+        "fn f<T>(x: T) -> int { let y: U = x; 0 } 0",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::TypeError), "{}", case);
+    }
+}
+
+#[test]
+fn test_growing_instantiation_is_rejected() {
+    let input = "fn f<T>(x: T, n: int) -> int { if n == 0 { 0 } else { f([x], n - 1) } } f(1, 3)";
+    let errors = common::run_test(input).errors;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("uses itself with [T; 1] for its type parameter T"), "{}", errors[0]);
+}
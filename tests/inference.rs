@@ -0,0 +1,121 @@
+mod common;
+
+#[test]
+fn test_unannotated_lambdas() {
+    let input = "
+    let inc = |x| x + 1;
+    let pick = |flag, a, b| if flag { a } else { b };
+    pick(true, inc(40), 0) + pick(false, 0, 1)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_annotated_locals() {
+    let input = "
+    let n: int = 40;
+    let f: fn(int) -> int = |x| x + 2;
+    let values: [bool; 2] = [true, false];
+    if values[0] { f(n) } else { 0 }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_let_polymorphism() {
+    let input = r#"
+    let id = |x| x;
+    let twice = |f, x| f(f(x));
+    let s = id("hello");
+    print(s);
+    if id(true) { twice(|n| n * 3, id(4)) + len(twice(|t| t, s)) } else { 0 }
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(41)));
+    assert_eq!(stdout, "hello\n");
+}
+
+#[test]
+fn test_generic_helpers_with_wide_values() {
+    let input = "
+    struct Point { x: int, y: int }
+    let pair = |a, b| [a, b];
+    let second = |a, b| b;
+    let points = pair(Point { x: 1, y: 2 }, Point { x: 3, y: 4 });
+    let numbers = pair(10, 20);
+    let p = second(0, points[1]);
+    p.x * 10 + p.y + numbers[0] + numbers[1]
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(64)));
+}
+
+#[test]
+fn test_generic_helpers_capture_variables() {
+    let input = "
+    let base = 100;
+    let const_base = |x| base;
+    let apply = |f, x| f(x);
+    apply(|n| n - 58, const_base(true)) + const_base(0) - 100
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_nested_generic_helpers() {
+    let input = "
+    let id = |x| x;
+    let wrap = |y| id(y);
+    let compose = |f, g| |x| f(g(x));
+    let add_one = compose(|n| n + 1, wrap);
+    if wrap(true) { add_one(wrap(41)) } else { 0 }
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_generic_helpers_in_functions() {
+    let input = "
+    enum Choice { Left, Right }
+    fn choose(choice: Choice, a: int, b: int) -> int {
+        let select = |c, l, r| match c { Choice::Left => l, Choice::Right => r };
+        if select(choice, true, false) { select(Choice::Left, a, b) } else { b }
+    }
+    choose(Choice::Left, 42, 0) + choose(Choice::Right, 1, 0)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(common::run_test("let x: = 1; x").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("let x int = 1; x").result, common::TestResult::ParseError));
+}
+
+#[test]
+fn test_type_errors() {
+    let cases = [
+        "let x: bool = 1; 0",
+        "let f = |x| x(x); 0",
+        "let id = |x| x; let y = id; 0",
+        "let show = |x| print(x); show(1); show(true); 0",
+        "let f = |p| p.x; 0",
+        "let first = |a| a[0]; 0",
+        "let id = |x| x; id(1) == id(true)",
+        "let mut f = |x| x; f(1); f(true); 0",
+        "let eq = |a, b| a == b; eq([1], [2])",
+        "let f = |g| g(1) + g(true); 0",
+        "let x: Missing = 1; 0",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::TypeError), "{}", case);
+    }
+}
+
+#[test]
+fn test_mismatch_names_both_types_and_origins() {
+    let input = "fn add(a: int, b: int) -> int { a + b } add(1, true)";
+    let errors = common::run_test(input).errors;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("bool from argument 2 at line 1"), "{}", errors[0]);
+    assert!(errors[0].contains("int from parameter 2 of function add"), "{}", errors[0]);
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use rcheer_lib::scan::Scanner;
+
+#[test]
+fn test_sized_arithmetic_wraps_at_its_width() {
+    let input = "
+    let a: u8 = 200;
+    let b: i8 = 100;
+    let c = 65535u16 +% 2u16;
+    print(a +% 100);
+    print(b +% b);
+    print(c);
+    print(2147483647i32 +% 1i32);
+    print(4294967295u32 *% 2u32);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "44\n-56\n1\n-2147483648\n4294967294\n");
+}
+
+#[test]
+fn test_unsigned_division_and_comparison() {
+    let input = "
+    let big = 18446744073709551615u64;
+    print(big);
+    print(big / 10u64);
+    print(big % 10u64);
+    print(200u8 / 3u8);
+    print(big > 1u64);
+    print(255u8 >= 128u8);
+    print(-1i8 < 1i8);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "18446744073709551615\n1844674407370955161\n5\n66\ntrue\ntrue\ntrue\n");
+}
+
+#[test]
+fn test_casts_extend_by_the_target_type() {
+    let input = "
+    let x = -1i8;
+    print(x as u8);
+    print(x as i64);
+    print(x as u64);
+    print(300 as u8);
+    print(200u8 as i8);
+    print(65537i32 as i16);
+    print(true as u8 + false as u8);
+    print(-(128 as i32) as u16 as u64 as i8);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "255\n-1\n18446744073709551615\n44\n-56\n1\n1\n-128\n");
+}
+
+#[test]
+fn test_literal_types_are_inferred() {
+    let input = "
+    struct Header { tag: u8, length: u32 }
+    fn checksum(header: Header) -> u32 { header.tag as u32 + header.length }
+    let header = Header { tag: 250, length: 4000000000 };
+    let values: [i16; 3] = [1, -2, 30000];
+    let total = values[0] + values[1] + values[2];
+    print(checksum(header));
+    print(total);
+    let small = 7;
+    small + 0i8
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(7)));
+    assert_eq!(stdout, "4000000250\n29999\n");
+}
+
+#[test]
+fn test_extreme_literals() {
+    let input = "
+    print(-9223372036854775808);
+    print(9223372036854775807);
+    print(-128i8);
+    print(-2147483648i32 / 2i32);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "-9223372036854775808\n9223372036854775807\n-128\n-1073741824\n");
+}
+
+#[test]
+fn test_sized_divide_overflow() {
+    let input = "let min = -128i8; let minus_one = -1i8; (min / minus_one) as int";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(201)));
+}
+
+#[test]
+fn test_match_on_sized_integers() {
+    let input = "
+    fn describe(byte: u8) -> int {
+        match byte {
+            0 => 1,
+            255 => 2,
+            _ => 3,
+        }
+    }
+    let big = match 18446744073709551615u64 { 18446744073709551615 => 10, _ => 0 };
+    describe(0) + describe(255) * 10 + big * 10
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(121)));
+}
+
+#[test]
+fn test_instructions_match_the_width() {
+    let asm = common::run_test("
+    let a: u8 = 7;
+    let b: i32 = 7;
+    print(a < 8); print(b < 8);
+    print(a * a); print(b / 2)
+    ").asm;
+    assert!(asm.contains("\tjb CMP_TRUE"));
+    assert!(asm.contains("\tjl CMP_TRUE"));
+    assert!(asm.contains("\tmulb %dl\n"));
+    assert!(asm.contains("\tidivl %ecx\n\t\tmovslq %eax, %rax"));
+}
+
+#[test]
+fn test_prefixes_and_separators() {
+    let input = "
+    print(0xff);
+    print(0o17);
+    print(0b1010_0101);
+    print(1_000_000);
+    print(0xFFFF_FFFF_FFFF_FFFFu64);
+    print(-0x8000_0000_0000_0000);
+    print(0x7fu8 +% 0x81u8);
+    print(1_000.5);
+    let a: [int; 0x3] = [1, 2, 3];
+    match 0b11 { 0x3 => a[2], _ => 0 }
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(3)));
+    assert_eq!(stdout, "255\n15\n165\n1000000\n18446744073709551615\n-9223372036854775808\n0\n1000.5\n");
+}
+
+#[test]
+fn test_literals_that_need_64_bit_immediates() {
+    let asm = common::run_test("print(0x1_0000_0000); 0").asm;
+    assert!(asm.contains("\tmovabsq $4294967296, %rax\n"));
+}
+
+#[test]
+fn test_scan_errors() {
+    assert!(matches!(common::run_test("5u7").result, common::TestResult::ScanError));
+    assert!(matches!(common::run_test("5int").result, common::TestResult::ScanError));
+    assert!(matches!(common::run_test("0x").result, common::TestResult::ScanError));
+    assert!(matches!(common::run_test("18446744073709551616u64; 0").result, common::TestResult::ScanError));
+    let errors = common::run_test("0b102; 0o8; 0x_; 18446744073709551616; 0x1_0000_0000_0000_0000; 012").errors;
+    assert_eq!(errors, [
+        "Invalid digit 2 in binary literal 0b10 at line 1, column 1",
+        "Invalid digit 8 in octal literal 0o at line 1, column 8",
+        "The hex literal 0x_ has no digits at line 1, column 13",
+        "Integer literal 18446744073709551616 doesn't fit in 64 bits at line 1, column 18",
+        "Integer literal 0x1_0000_0000_0000_0000 doesn't fit in 64 bits at line 1, column 40",
+        "Leading zeros in integer literals are not permitted at line 1, column 65",
+    ]);
+}
+
+#[test]
+fn test_type_errors() {
+    let cases = [
+        "1u8 + 1i8; 0",
+        "let x: u8 = 1; let y: int = x; 0",
+        "256u8; 0",
+        "let x: i8 = 128; 0",
+        "-129i8; 0",
+        "-1u32; 0",
+        "let x: u64 = 5; -x; 0",
+        "9223372036854775808; 0",
+        "5 as bool; 0",
+        "\"five\" as int; 0",
+        "true + 1; 0",
+        "match 1u8 { 256 => 0, _ => 1 }",
+        "fn f(x: u16) -> u16 { x } f(1i16); 0",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::TypeError), "{}", case);
+    }
+}
+
+#[test]
+fn test_out_of_range_literal_names_the_type() {
+    let errors = common::run_test("let x: u8 = 300; 0").errors;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("Integer literal 300 at line 1, column 13 doesn't fit in type u8"), "{}", errors[0]);
+}
+
+#[test]
+fn test_integer_literals_carry_their_value() {
+    let tokens: Vec<_> = Scanner::new(0, "0x_ffu8 1_000 0b101 x").map(|result| result.unwrap()).collect();
+    let values: Vec<Option<u64>> = tokens.iter().map(|token| token.int.map(|(value, _)| value)).collect();
+    assert_eq!(values, [Some(255), Some(1000), Some(5), None]);
+    let suffixed: Vec<bool> = tokens.iter().map(|token| matches!(token.int, Some((_, Some(_))))).collect();
+    assert_eq!(suffixed, [true, false, false, false]);
+}
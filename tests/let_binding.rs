@@ -0,0 +1,56 @@
+mod common;
+
+#[test]
+fn test_let() {
+    let input = "let x = 5; x + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(6)));
+}
+
+#[test]
+fn test_let_chain() {
+    let input = "let x = 3; let y = x * 2; y + x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(9)));
+}
+
+#[test]
+fn test_shadowing() {
+    let input = "let x = 2; let x = x * 10; x + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(21)));
+}
+
+#[test]
+fn test_lexical_scope() {
+    // the inner x is only visible in the block
+    let input = "let x = 1; { let x = 40; x } + x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(41)));
+}
+
+#[test]
+fn test_variable_in_condition() {
+    let input = "let limit = 10; let n = 12; if n > limit { n - limit } else { 0 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(2)));
+}
+
+#[test]
+fn test_undefined_variable() {
+    let input = "let x = 1; y";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_out_of_scope() {
+    let input = "{ let x = 1; x } + x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_let_type_error() {
+    let input = "let b = 1 < 2; b + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_let_missing_semicolon() {
+    let input = "let x = 1 x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::ParseError));
+}
@@ -0,0 +1,40 @@
+use rcheer_lib::{scan::{self, Scanner}, token::TokenType};
+
+#[test]
+fn test_lexemes_are_slices_of_the_source() {
+    let source = String::from("let total = 0x_ff + 1_000; // done\nprint(\"sum\" )");
+    let tokens: Vec<_> = Scanner::new(0, &source).map(|result| result.unwrap()).collect();
+    let lexemes: Vec<&str> = tokens.iter().map(|token| token.lexeme).collect();
+    assert_eq!(lexemes, ["let", "total", "=", "0x_ff", "+", "1_000", ";", "print", "(", "\"sum\"", ")"]);
+    for token in &tokens {
+        assert_eq!(token.lexeme, &source[token.span.start_byte..token.span.end_byte]);
+        assert!(std::ptr::eq(token.lexeme.as_ptr(), source[token.span.start_byte..].as_ptr()));
+    }
+}
+
+#[test]
+fn test_scanning_lazily() {
+    let source = "import shapes; $ 1u9 \"unterminated";
+    let mut scanner = Scanner::new(0, source);
+    let import = scanner.next().unwrap().unwrap();
+    assert_eq!(import.token_type, TokenType::Import);
+    assert_eq!(scanner.next().unwrap().unwrap().lexeme, "shapes");
+    assert_eq!(scanner.next().unwrap().unwrap().token_type, TokenType::Semicolon);
+    let error = scanner.next().unwrap().unwrap_err();
+    let start = error.span.start(source);
+    assert_eq!((error.message.as_str(), start.line, start.column), ("Unrecognized input $", 1, 16));
+    assert_eq!(scanner.error_token().map(|token| token.lexeme), Some("$"));
+    assert!(scanner.next().unwrap().is_err());
+    assert_eq!(scanner.error_token().map(|token| token.lexeme), Some("1u9"));
+    assert!(scanner.next().unwrap().is_err());
+    assert!(scanner.next().is_none());
+}
+
+#[test]
+fn test_scan_recovers_with_error_tokens() {
+    let result = scan::scan(0, "1 & 2 /* open");
+    let types: Vec<TokenType> = result.tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(types, [TokenType::IntLiteral, TokenType::Error, TokenType::IntLiteral]);
+    let messages: Vec<&str> = result.errors.iter().map(|error| error.message.as_str()).collect();
+    assert_eq!(messages, ["Unrecognized input &, did you mean &&", "Unterminated block comment"]);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+#[test]
+fn test_qualified_names() {
+    let files = [
+        ("main.ch", "
+        import geometry;
+        let corner = geometry::Point { x: 3, y: 4 };
+        let shape: geometry::Shape = geometry::Shape::Rect(geometry::origin(), corner);
+        print(geometry::area(shape));
+        match geometry::Shape::Square(5) {
+            geometry::Shape::Square(side) => side,
+            _ => 0,
+        }
+        "),
+        ("geometry.ch", "
+        struct Point { x: int, y: int }
+        enum Shape { Rect(Point, Point), Square(int) }
+        fn origin() -> Point { Point { x: 0, y: 0 } }
+        fn area(shape: Shape) -> int {
+            match shape {
+                Shape::Rect(low, high) => width(low, high) * (high.y - low.y),
+                Shape::Square(side) => side * side,
+            }
+        }
+        fn width(low: Point, high: Point) -> int { high.x - low.x }
+        "),
+    ];
+    let common::Outcome {result, stdout, ..} = common::run_test(common::Test {files: &files, ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(5)));
+    assert_eq!(stdout, "12\n");
+}
+
+#[test]
+fn test_modules_have_their_own_names() {
+    let files = [
+        ("main.ch", "
+        import counter;
+        fn next(n: int) -> int { n + 100 }
+        next(counter::next(1))
+        "),
+        ("counter.ch", "
+        struct next { next: int }
+        fn next(next: int) -> int { let step = |next: int| next + 1; step(next) }
+        fn wrap(n: int) -> next { next { next: next(n) } }
+        "),
+    ];
+    let result = common::run_test(common::Test {files: &files, ..Default::default()}).result;
+    assert!(matches!(result, common::TestResult::Execution(102)));
+}
+
+#[test]
+fn test_imports_are_found_next_to_the_importer_or_on_the_search_path() {
+    let files = [
+        ("src/main.ch", "import shapes; import text; shapes::sides(text::length(\"abc\"))"),
+        ("src/shapes.ch", "import text; fn sides(n: int) -> int { n * 2 + text::length(\"\") }"),
+        ("lib/text.ch", "fn length(s: str) -> int { len(s) }"),
+    ];
+    let result = common::run_test(common::Test {files: &files, search_path: &["lib"], ..Default::default()}).result;
+    assert!(matches!(result, common::TestResult::Execution(6)));
+}
+
+#[test]
+fn test_generic_functions_from_modules() {
+    let files = [
+        ("main.ch", "import pair; let p = pair::Pair { first: 1, second: true }; print(pair::swap(p).first); 0"),
+        ("pair.ch", "
+        struct Pair<A, B> { first: A, second: B }
+        fn swap<A, B>(p: Pair<A, B>) -> Pair<B, A> { Pair { first: p.second, second: p.first } }
+        "),
+    ];
+    let common::Outcome {result, stdout, ..} = common::run_test(common::Test {files: &files, ..Default::default()});
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "true\n");
+}
+
+#[test]
+fn test_import_cycles() {
+    let files = [
+        ("main.ch", "import a; a::f()"),
+        ("a.ch", "import b; fn f() -> int { 1 }"),
+        ("b.ch", "import a; fn g() -> int { 2 }"),
+    ];
+    assert_eq!(common::run_test(common::Test {files: &files, ..Default::default()}).errors, ["Import cycle a -> b -> a, b imports a at line 1"]);
+    let files = [
+        ("main.ch", "import helper; 0"),
+        ("helper.ch", "import main; fn f() -> int { 1 }"),
+    ];
+    assert_eq!(common::run_test(common::Test {files: &files, ..Default::default()}).errors, ["Import cycle main -> helper -> main, helper imports main at line 1"]);
+}
+
+#[test]
+fn test_import_errors() {
+    let missing = common::run_test(common::Test {files: &[("main.ch", "import nowhere; 0")], ..Default::default()}).errors.remove(0);
+    assert!(missing.starts_with("Can't find module nowhere imported at line 1, looked for nowhere.ch in "), "{}", missing);
+    let statements = common::run_test(common::Test {files: &[("main.ch", "import side; 0"), ("side.ch", "print(1);")], ..Default::default()}).errors.remove(0);
+    assert!(statements.ends_with("side.ch: Module side can only declare functions, structs and enums, \
+        it has statements at the top level"), "{}", statements);
+}
+
+#[test]
+fn test_names_need_their_module() {
+    let files = [
+        ("main.ch", "import geometry; area(2)"),
+        ("geometry.ch", "fn area(side: int) -> int { side * side }"),
+    ];
+    let result = common::run_test(common::Test {files: &files, ..Default::default()}).result;
+    assert!(matches!(result, common::TestResult::TypeError));
+    let files = [
+        ("main.ch", "import geometry; 0"),
+        ("geometry.ch", "fn area(side: int) -> int { side * side } fn bad() -> int { area(true) }"),
+    ];
+    let result = common::run_test(common::Test {files: &files, ..Default::default()}).result;
+    assert!(matches!(result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_only_a_module_declares_its_names() {
+    let files = [
+        ("r.ch", "import q; 0"),
+        ("q.ch", "import geometry; fn geometry::foo() -> int { 1 }"),
+        ("geometry.ch", "fn area() -> int { 1 }"),
+    ];
+    let error = common::run_test(common::Test {files: &files, ..Default::default()}).errors.remove(0);
+    assert!(error.ends_with("q.ch: Can't declare geometry::foo at line 1, only module geometry can declare names in it"), "{}", error);
+    let files = [
+        ("main.ch", "import geometry; fn geometry::extra() -> int { 7 } print(geometry::extra()); 0"),
+        ("geometry.ch", "fn area() -> int { 1 }"),
+    ];
+    assert_eq!(common::run_test(common::Test {files: &files, ..Default::default()}).errors, ["Can't declare geometry::extra at line 1, only module geometry can declare names in it"]);
+}
+
+#[test]
+fn test_duplicate_definitions_name_their_file() {
+    let files = [
+        ("main.ch", "import shapes; 0"),
+        ("shapes.ch", "fn area() -> int { 1 }\nstruct Point { x: int }\nfn area() -> int { 2 }\nenum Point { Origin }"),
+    ];
+    let errors = common::run_test(common::Test {files: &files, ..Default::default()}).errors;
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].starts_with("Type shapes::Point is defined more than once, at line 2 of "), "{}", errors[0]);
+    assert!(errors[0].contains("shapes.ch and at line 4 of ") && errors[0].ends_with("shapes.ch"), "{}", errors[0]);
+    assert!(errors[1].starts_with("Function shapes::area is defined more than once, at line 1 of "), "{}", errors[1]);
+    assert!(errors[1].ends_with("shapes.ch"), "{}", errors[1]);
+}
+
+#[test]
+fn test_names_of_modules_that_are_not_imported() {
+    let files = [
+        ("main.ch", "import util; import other; other::twice(1)"),
+        ("other.ch", "fn twice(x: int) -> int { util::double(x) }"),
+        ("util.ch", "fn double(x: int) -> int { x * 2 }"),
+    ];
+    let errors = common::run_test(common::Test {files: &files, ..Default::default()}).errors;
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].starts_with("Module util is used at line 1 of "), "{}", errors[0]);
+    assert!(errors[0].ends_with("other.ch but not imported there, that file needs import util;"), "{}", errors[0]);
+    let files = [("main.ch", "util::double(1)")];
+    assert_eq!(common::run_test(common::Test {files: &files, ..Default::default()}).errors, ["Unknown enum util at line 1, column 1, and no module util is imported"]);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use rcheer_lib::Options;
+
+#[test]
+fn test_overflow_reports_operator_and_line() {
+    let input = "
+    let big = 9223372036854775807;
+    let one = 1;
+    big + one
+    ";
+    let common::Outcome {result, stderr, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(204)));
+    assert_eq!(stderr, "error: arithmetic overflow in + at line 4\n");
+}
+
+#[test]
+fn test_every_checked_operator_traps() {
+    let cases = [
+        ("let x = -9223372036854775807; x - 2", "-"),
+        ("let x = 4294967296; x * x", "*"),
+        ("let x = -9223372036854775807 - 1; -x", "-"),
+        ("let x: i8 = 100; (x + x) as int", "+"),
+        ("let x: i16 = 200; (x * x) as int", "*"),
+        ("let x: i8 = -128; (x * -1) as int", "*"),
+        ("let x: u8 = 0; (x - 1) as int", "-"),
+        ("let x: u32 = 65536; (x * x) as int", "*"),
+        ("let x = 18446744073709551615u64; (x + 1) as int", "+"),
+    ];
+    for (input, operator) in cases {
+        let common::Outcome {result, stderr, ..} = common::run_test(input);
+        assert!(matches!(result, common::TestResult::Execution(204)), "{}", input);
+        assert_eq!(stderr, format!("error: arithmetic overflow in {} at line 1\n", operator), "{}", input);
+    }
+}
+
+#[test]
+fn test_results_that_fit_do_not_trap() {
+    let input = "
+    let a: i8 = -64;
+    let b: u8 = 255;
+    let c: i16 = 181;
+    print(a * 2);
+    print(b - 255);
+    print(c * c);
+    print(-9223372036854775807 - 1);
+    print(-128i8);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "-128\n0\n32761\n-9223372036854775808\n-128\n");
+}
+
+#[test]
+fn test_wrapping_operators_never_trap() {
+    let input = "
+    let max = 9223372036854775807;
+    let byte: u8 = 255;
+    let min = max +% 1;
+    print(min);
+    print(min -% 1);
+    print(max *% 2);
+    print(-%min);
+    print(byte +% 1);
+    print(-%1u8);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "-9223372036854775808\n9223372036854775807\n-2\n-9223372036854775808\n0\n255\n");
+}
+
+#[test]
+fn test_release_wraps_instead_of_trapping() {
+    let input = "let big = 9223372036854775807; let x: u8 = 0; print(x - 1); big + 43";
+    let result = common::run_test(common::Test {input, options: Options::release(), ..Default::default()}).result;
+    assert!(matches!(result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_checks_are_only_emitted_when_asked_for() {
+    let input = "let x = 1; let y: u8 = 2; print(y * y); x + x";
+    let debug = common::run_test(input).asm;
+    assert!(debug.contains("\tjo OVERFLOW"));
+    assert!(debug.contains("\tjc OVERFLOW"));
+    let release = common::run_test(common::Test {input, options: Options::release(), ..Default::default()});
+    assert!(matches!(release.result, common::TestResult::Execution(2)));
+    assert!(!release.asm.contains("OVERFLOW"));
+    let wrapping = common::run_test("let x = 1; x +% x *% -%x");
+    assert!(matches!(wrapping.result, common::TestResult::Execution(0)));
+    assert!(!wrapping.asm.contains("OVERFLOW"));
+}
+
+#[test]
+fn test_wrapping_operator_precedence() {
+    let input = "2 +% 3 *% 4 -% 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(13)));
+}
+
+#[test]
+fn test_wrapping_operators_need_integers() {
+    let cases = [
+        "true +% 1",
+        "-%false; 0",
+        "1u8 *% 1i8; 0",
+    ];
+    for case in cases {
+        assert!(matches!(common::run_test(case).result, common::TestResult::TypeError), "{}", case);
+    }
+}
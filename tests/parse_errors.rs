@@ -0,0 +1,37 @@
+mod common;
+
+#[test]
+fn test_every_statement_with_an_error_is_reported() {
+    let input = "let a = 1 +;\nlet b = (2;\nlet c = 3;\nc * * 2;\nlet d = 4 c";
+    let errors = common::run_test(input).errors;
+    assert_eq!(errors.len(), 4, "{:?}", errors);
+    assert!(errors[0].starts_with("Expected primary expression") && errors[0].contains("line: 1"), "{}", errors[0]);
+    assert!(errors[1].starts_with("Expect ) got") && errors[1].contains("line: 2"), "{}", errors[1]);
+    assert!(errors[2].starts_with("Expected primary expression") && errors[2].contains("line: 4"), "{}", errors[2]);
+    assert!(errors[3].starts_with("Expect ; got") && errors[3].contains("line: 5"), "{}", errors[3]);
+}
+
+#[test]
+fn test_errors_in_every_declaration_are_reported() {
+    let input = "fn f(x: int) -> int { let y = x +; y }\n\
+        struct Empty {}\n\
+        fn g() -> int { if { 1 } }\n\
+        enum E { A(int }\n\
+        f(1)";
+    let errors = common::run_test(input).errors;
+    assert_eq!(errors.len(), 4, "{:?}", errors);
+    for (error, line) in errors.iter().zip(1..) {
+        assert!(error.contains(&format!("line: {}", line)), "{}", error);
+    }
+}
+
+#[test]
+fn test_recovery_skips_whole_blocks() {
+    // the error is inside the while's braces, so parsing starts again after them
+    let errors = common::run_test("let mut i = 0;\nwhile i < 3 { i = i + ; }\ni = ];\ni").errors;
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[1].contains("line: 3"), "{}", errors[1]);
+    // a } with no { is reported once
+    assert_eq!(common::run_test("1; } 2").errors.len(), 1);
+    assert_eq!(common::run_test("let x = {").errors.len(), 1);
+}
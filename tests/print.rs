@@ -0,0 +1,90 @@
+mod common;
+
+#[test]
+fn test_print_int() {
+    let common::Outcome {result, stdout, ..} = common::run_test("print(42); 0");
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "42\n");
+}
+
+#[test]
+fn test_print_negative() {
+    let stdout = common::run_test("print(-1234567); print(0 - 10)").stdout;
+    assert_eq!(stdout, "-1234567\n-10\n");
+}
+
+#[test]
+fn test_print_zero() {
+    let stdout = common::run_test("print(0)").stdout;
+    assert_eq!(stdout, "0\n");
+}
+
+#[test]
+fn test_print_extremes() {
+    // 2^63 wraps around to i64::MIN
+    let input = "let min = 1073741824 *% 1073741824 *% 8; print(min); print(min -% 1)";
+    let stdout = common::run_test(input).stdout;
+    assert_eq!(stdout, "-9223372036854775808\n9223372036854775807\n");
+}
+
+#[test]
+fn test_print_bool() {
+    let stdout = common::run_test("print(true); print(1 > 2)").stdout;
+    assert_eq!(stdout, "true\nfalse\n");
+}
+
+#[test]
+fn test_print_result_larger_than_exit_code() {
+    let input = "
+    fn fib(n: int) -> int { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }
+    print(fib(30))
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "832040\n");
+}
+
+#[test]
+fn test_print_in_loop() {
+    let input = "
+    let mut i = 1;
+    while i <= 3 {
+        print(i * 100);
+        i = i + 1;
+    }
+    ";
+    let stdout = common::run_test(input).stdout;
+    assert_eq!(stdout, "100\n200\n300\n");
+}
+
+#[test]
+fn test_print_at_odd_stack_depth() {
+    let input = "let a = 1; let b = 2; let c = 3; print(a + b + c); c";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(3)));
+    assert_eq!(stdout, "6\n");
+}
+
+#[test]
+fn test_print_is_unit() {
+    let input = "print(1) + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_print_unit_type_error() {
+    let input = "print(print(1))";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_print_arity_error() {
+    let input = "print(1, 2)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_redefine_print() {
+    let input = "fn print(x: int) -> int { x } print(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+#[test]
+fn test_every_scan_error_is_reported() {
+    let input = "let a = 5u7;\nlet b = \"\\q\";\nlet c = [$, &, 007, 1.5e];\nlet d = c[0] +;\n/* open";
+    let common::Outcome {errors: scan_errors, parse_errors, ..} = common::run_test(input);
+    assert_eq!(scan_errors, [
+        "Invalid suffix u7 on integer literal 5, expected one of i8, i16, i32, i64, u8, u16, u32 or u64 at line 1, column 9",
+        "Unknown escape sequence \\q at line 2, column 9",
+        "Unrecognized input $ at line 3, column 10",
+        "Unrecognized input &, did you mean && at line 3, column 13",
+        "Leading zeros in integer literals are not permitted at line 3, column 16",
+        "Float literal 1.5 can't have a suffix at line 3, column 21",
+        "Unterminated block comment at line 5, column 1",
+    ]);
+    assert_eq!(parse_errors.len(), 1);
+    assert!(parse_errors[0].starts_with("Expected primary expression") && parse_errors[0].contains("line: 4"),
+        "{}", parse_errors[0]);
+}
+
+#[test]
+fn test_scan_errors_in_every_file() {
+    let files = [
+        ("main.ch", "import helper; helper::f(#)"),
+        ("helper.ch", "fn f(x: int) -> int { x + 1u9 }"),
+    ];
+    let errors = common::run_test(common::Test {files: &files, ..Default::default()}).errors;
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], "Unrecognized input # at line 1, column 26");
+    assert!(errors[1].ends_with("helper.ch: Invalid suffix u9 on integer literal 1, \
+        expected one of i8, i16, i32, i64, u8, u16, u32 or u64 at line 1, column 27"), "{}", errors[1]);
+}
+
+#[test]
+fn test_error_tokens_where_operators_go() {
+    for input in ["let x = 1 & 2; x", "let x = 1; x $ 2", "fn f(n: int) -> int { n } f(1 # 2)", "let x = [1 @, 2]; 0"] {
+        let common::Outcome {result, errors: scan_errors, parse_errors, ..} = common::run_test(input);
+        assert!(matches!(result, common::TestResult::ScanError), "{}", input);
+        assert_eq!(scan_errors.len(), 1, "{}", input);
+        assert!(parse_errors.is_empty(), "{}: {:?}", input, parse_errors);
+    }
+    let common::Outcome {errors: scan_errors, parse_errors, ..} = common::run_test("let x = 1 & 2;\nlet y = (x;\ny");
+    assert_eq!(scan_errors, ["Unrecognized input &, did you mean && at line 1, column 11"]);
+    assert_eq!(parse_errors.len(), 1);
+    assert!(parse_errors[0].starts_with("Expect ) got") && parse_errors[0].contains("line: 2"), "{}", parse_errors[0]);
+}
@@ -0,0 +1,99 @@
+use rcheer_lib::{ast::Expr, parse::{self, ParseResult}, scan, span::{position, Position, Span}, CompileResult};
+
+#[test]
+fn test_positions_count_lines_and_characters() {
+    let source = "let x = 1;\nlet é = \"ü\";\n\nx";
+    assert_eq!(position(source, 0), Position {line: 1, column: 1});
+    assert_eq!(position(source, 4), Position {line: 1, column: 5});
+    assert_eq!(position(source, 10), Position {line: 1, column: 11});
+    assert_eq!(position(source, 11), Position {line: 2, column: 1});
+    // é and ü take two bytes each but count as one column
+    let string = source.find('"').unwrap();
+    assert_eq!(position(source, string), Position {line: 2, column: 9});
+    assert_eq!(position(source, source.len() - 1), Position {line: 4, column: 1});
+    assert_eq!(position(source, source.len()), Position {line: 4, column: 2});
+}
+
+#[test]
+fn test_span_start_and_end() {
+    let source = "print(\"ü\");\n  f(x)";
+    let string = Span::new(0, 6, 10);
+    assert_eq!(&source[string.start_byte..string.end_byte], "\"ü\"");
+    assert_eq!(string.start(source), Position {line: 1, column: 7});
+    assert_eq!(string.end(source), Position {line: 1, column: 9});
+
+    let call = Span::new(0, 15, 19);
+    let whole = Span::new(0, 0, 1).to(call);
+    assert_eq!(whole, Span::new(0, 0, 19));
+    assert_eq!(&source[call.start_byte..call.end_byte], "f(x)");
+    assert_eq!(whole.end(source), Position {line: 2, column: 6});
+    assert_eq!(call.start_point().end(source), Position {line: 2, column: 3});
+}
+
+// the text of the span of the expression a program ends with
+fn final_expression_text(source: &str) -> String {
+    let tokens = scan::scan(1, source).tokens;
+    let program = match parse::parse(&tokens) {
+        ParseResult::Ast(program) => program,
+        ParseResult::Error(errors) => panic!("{}", errors[0].message),
+    };
+    let span = match program.main {
+        Some(Expr::Block(block)) => block.expr.expect("the program should end with an expression").span(),
+        _ => panic!("expected statements"),
+    };
+    assert_eq!(span.file, 1);
+    String::from(&source[span.start_byte..span.end_byte])
+}
+
+#[test]
+fn test_expression_spans() {
+    let cases = [
+        ("let a = 1;\n  ( a + 2 )  ", "( a + 2 )"),
+        ("f(1, g(2)) ;f(3)", "f(3)"),
+        ("  f(1, g(2)  )", "f(1, g(2)  )"),
+        ("a[1 + b[2]]", "a[1 + b[2]]"),
+        ("make().point .x", "make().point .x"),
+        ("-x as u8 ", "-x as u8"),
+        ("x as [int;  3]\n", "x as [int;  3]"),
+        ("if a { 1 } else if b { 2 } else { 3 } ", "if a { 1 } else if b { 2 } else { 3 }"),
+        ("match x { 1 => 2, _ => { 3 } }", "match x { 1 => 2, _ => { 3 } }"),
+        ("|a: int, b| a + b", "|a: int, b| a + b"),
+        ("|| 1", "|| 1"),
+        ("p.x[2] = y + 1", "p.x[2] = y + 1"),
+        ("{ let z = 1; z }", "{ let z = 1; z }"),
+    ];
+    for (source, text) in cases {
+        assert_eq!(final_expression_text(source), text, "{}", source);
+    }
+}
+
+// the text of the span of the error parsing source, or where the span starts when it's empty
+fn parse_error_text(source: &str) -> (String, Position) {
+    let tokens = scan::scan(0, source).tokens;
+    match parse::parse(&tokens) {
+        ParseResult::Error(errors) => (String::from(&source[errors[0].span.start_byte..errors[0].span.end_byte]), errors[0].span.start(source)),
+        ParseResult::Ast(_) => panic!("expected {} not to parse", source),
+    }
+}
+
+#[test]
+fn test_errors_carry_spans() {
+    let source = "let x = 1;
+let y = 0x_ + 1u9;";
+    let errors = scan::scan(0, source).errors;
+    let texts: Vec<&str> = errors.iter().map(|error| &source[error.span.start_byte..error.span.end_byte]).collect();
+    assert_eq!(texts, ["0x_", "1u9"]);
+    assert_eq!(errors[0].span.start(source), Position {line: 2, column: 9});
+
+    assert_eq!(parse_error_text("let x = 1;
+1 + let"), (String::from("let"), Position {line: 2, column: 5}));
+    assert_eq!(parse_error_text("[1, 2] = 3"), (String::from("[1, 2]"), Position {line: 1, column: 1}));
+    assert_eq!(parse_error_text("let x = (1 +"), (String::new(), Position {line: 1, column: 13}));
+
+    let source = "let flag = true;
+if flag { 1 } else { flag }";
+    let CompileResult::TypeError(errors) = rcheer_lib::compile(source) else { panic!("expected a type error") };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(&source[errors[0].span.start_byte..errors[0].span.end_byte], "{ flag }");
+    assert_eq!(errors[0].span.start(source), Position {line: 2, column: 20});
+}
@@ -0,0 +1,75 @@
+mod common;
+
+#[test]
+fn test_print_string() {
+    let common::Outcome {result, stdout, ..} = common::run_test(r#"print("hello, world"); 0"#);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "hello, world\n");
+}
+
+#[test]
+fn test_print_empty_string() {
+    let stdout = common::run_test(r#"print("")"#).stdout;
+    assert_eq!(stdout, "\n");
+}
+
+#[test]
+fn test_escapes() {
+    let stdout = common::run_test(r#"print("a\tb\n\"c\" \\ \u{e9}\u{1F600}")"#).stdout;
+    assert_eq!(stdout, "a\tb\n\"c\" \\ \u{e9}\u{1F600}\n");
+}
+
+#[test]
+fn test_len() {
+    assert!(matches!(common::run_test(r#"len("hello")"#).result, common::TestResult::Execution(5)));
+    assert!(matches!(common::run_test(r#"len("")"#).result, common::TestResult::Execution(0)));
+    // length is in bytes, not characters
+    assert!(matches!(common::run_test(r#"len("\u{e9}\n")"#).result, common::TestResult::Execution(3)));
+}
+
+#[test]
+fn test_equality() {
+    assert!(matches!(common::run_test(r#"if "abc" == "abc" { 1 } else { 0 }"#).result, common::TestResult::Execution(1)));
+    assert!(matches!(common::run_test(r#"if "abc" == "abd" { 1 } else { 0 }"#).result, common::TestResult::Execution(0)));
+    assert!(matches!(common::run_test(r#"if "abc" == "ab" { 1 } else { 0 }"#).result, common::TestResult::Execution(0)));
+    assert!(matches!(common::run_test(r#"if "" == "" { 1 } else { 0 }"#).result, common::TestResult::Execution(1)));
+    assert!(matches!(common::run_test(r#"if "abc" != "abd" { 1 } else { 0 }"#).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_string_functions() {
+    let input = r#"
+    fn greet(name: str) -> str {
+        if name == "" { "nobody" } else { name }
+    }
+    print(greet("cheer"));
+    print(greet(""));
+    len(greet("abc"))
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(3)));
+    assert_eq!(stdout, "cheer\nnobody\n");
+}
+
+#[test]
+fn test_multiline_string() {
+    let stdout = common::run_test("print(\"one\ntwo\")").stdout;
+    assert_eq!(stdout, "one\ntwo\n");
+}
+
+#[test]
+fn test_scan_errors() {
+    assert!(matches!(common::run_test(r#"print("abc)"#).result, common::TestResult::ScanError));
+    assert!(matches!(common::run_test(r#"print("\q")"#).result, common::TestResult::ScanError));
+    assert!(matches!(common::run_test(r#"print("\u{110000}")"#).result, common::TestResult::ScanError));
+    assert!(matches!(common::run_test(r#"print("\u{}")"#).result, common::TestResult::ScanError));
+}
+
+#[test]
+fn test_type_errors() {
+    assert!(matches!(common::run_test(r#""a" + "b""#).result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test(r#""a" == 1"#).result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test(r#""a" < "b""#).result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test("len(5)").result, common::TestResult::TypeError));
+    assert!(matches!(common::run_test(r#"len("a", "b")"#).result, common::TestResult::TypeError));
+}
@@ -0,0 +1,189 @@
+mod common;
+
+#[test]
+fn test_field_access() {
+    let input = "
+    struct Point { x: int, y: int }
+    let p = Point { x: 3, y: 4 };
+    p.x * 10 + p.y
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(34)));
+}
+
+#[test]
+fn test_literal_field_access() {
+    let input = "struct Point { x: int, y: int } Point { x: 3, y: 4 }.y";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(4)));
+}
+
+#[test]
+fn test_fields_out_of_order() {
+    // fields are evaluated in the order written but laid out in declaration order
+    let input = "
+    struct Point { x: int, y: int, z: int }
+    let p = Point { z: { print(1); 3 }, x: { print(2); 1 }, y: { print(3); 2 } };
+    p.x * 100 + p.y * 10 + p.z
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(123)));
+    assert_eq!(stdout, "1\n2\n3\n");
+}
+
+#[test]
+fn test_mixed_field_types() {
+    let input = r#"
+    struct Person { name: str, age: int, active: bool }
+    let person = Person { name: "Ada", age: 36, active: true };
+    print(person.name);
+    if person.active { person.age } else { 0 }
+    "#;
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(36)));
+    assert_eq!(stdout, "Ada\n");
+}
+
+#[test]
+fn test_field_assignment() {
+    let input = "
+    struct Point { x: int, y: int }
+    let mut p = Point { x: 1, y: 2 };
+    p.y = 20;
+    p.x = p.x + p.y;
+    p.x
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(21)));
+}
+
+#[test]
+fn test_nested_structs() {
+    let input = "
+    struct Point { x: int, y: int }
+    struct Line { start: Point, end: Point }
+    let mut line = Line { start: Point { x: 1, y: 2 }, end: Point { x: 3, y: 4 } };
+    line.end.y = 40;
+    let start = line.start;
+    start.x * 100 + start.y * 10 + line.end.y + line.end.x
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(163)));
+}
+
+#[test]
+fn test_structs_and_arrays() {
+    let input = "
+    struct Point { x: int, y: int }
+    struct Polygon { points: [Point; 3], closed: bool }
+    let mut shape = Polygon {
+        points: [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }, Point { x: 5, y: 6 }],
+        closed: true,
+    };
+    shape.points[1].y = 9;
+    let mut sum = 0;
+    let mut i = 0;
+    while i < len(shape.points) {
+        sum = sum + shape.points[i].x * shape.points[i].y;
+        i = i + 1;
+    }
+    sum
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(59)));
+}
+
+#[test]
+fn test_pass_and_return_structs() {
+    let input = "
+    struct Point { x: int, y: int }
+    fn add(a: Point, b: Point) -> Point {
+        Point { x: a.x + b.x, y: a.y + b.y }
+    }
+    fn swap(p: Point) -> Point { Point { y: p.x, x: p.y } }
+    let p = swap(add(Point { x: 1, y: 2 }, Point { x: 10, y: 20 }));
+    p.x * 10 + p.y
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(231)));
+}
+
+#[test]
+fn test_struct_passed_by_value() {
+    let input = "
+    struct Counter { count: int }
+    fn bump(c: Counter) -> int {
+        let mut local = c;
+        local.count = local.count + 1;
+        local.count
+    }
+    let c = Counter { count: 5 };
+    bump(c) * 10 + c.count
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(65)));
+}
+
+#[test]
+fn test_recursive_structs_are_values() {
+    let input = "
+    struct Node { value: int, items: [int; 2], next: Next }
+    enum Next { End, Link(Node) }
+    struct Holder { node: Node }
+    let mut holder = Holder { node: Node { value: 1, items: [2, 3], next: Next::Link(Node { value: 4, items: [0, 0], next: Next::End }) } };
+    let copy = holder;
+    holder.node.value = 5;
+    holder.node.items[1] = 6;
+    holder.node.next = Next::End;
+    print(holder.node.value);
+    print(holder.node.items[1]);
+    print(copy.node.value);
+    print(copy.node.items[1]);
+    match copy.node.next { Next::Link(node) => node.value, Next::End => 0 }
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(4)));
+    assert_eq!(stdout, "5\n6\n1\n3\n");
+}
+
+#[test]
+fn test_struct_declared_after_use() {
+    let input = "
+    fn origin() -> Point { Point { x: 0, y: 7 } }
+    struct Point { x: int, y: int }
+    origin().y
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(7)));
+}
+
+#[test]
+fn test_if_with_variable_condition() {
+    // an identifier followed by a block is not a struct literal
+    let input = "let flag = true; let x = 5; if flag { x } else { 0 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(5)));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(common::run_test("struct Empty {} 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("struct Point { x: int, y } 0").result, common::TestResult::ParseError));
+    assert!(matches!(common::run_test("struct Point { x: int } let p = Point { x: 1 }; p.").result, common::TestResult::ParseError));
+}
+
+#[test]
+fn test_type_errors() {
+    let point = "struct Point { x: int, y: int }";
+    let cases = [
+        "let p = Point { x: 1 }; 0",
+        "let p = Point { x: 1, y: 2, z: 3 }; 0",
+        "let p = Point { x: 1, x: 2, y: 3 }; 0",
+        "let p = Point { x: true, y: 2 }; 0",
+        "let p = Point { x: 1, y: 2 }; p.z",
+        "let p = Point { x: 1, y: 2 }; p.x = 3; 0",
+        "let p = Point { x: 1, y: 2 }; p == p",
+        "let n = 5; n.x",
+        "let q = Other { x: 1 }; 0",
+        "fn f(a: Missing) -> int { 0 } 0",
+        "struct Point { z: int } 0",
+        "struct Point { x: int, x: int } 0",
+        "struct Node { next: Node } 0",
+        "struct A { b: B } struct B { a: [A; 2] } 0",
+    ];
+    for case in cases {
+        let input = format!("{} {}", point, case);
+        assert!(matches!(common::run_test(input.as_str()).result, common::TestResult::TypeError), "{}", case);
+    }
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use rcheer_lib::Options;
+
+fn reporting() -> Options {
+    Options {report_tail_calls: true, ..Options::default()}
+}
+
+#[test]
+fn test_deep_self_recursion() {
+    let input = "
+    fn count(n: int, total: int) -> int {
+        if n == 0 { total } else { count(n - 1, total + 1) }
+    }
+    count(1000000, 0) / 10000
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(100)));
+}
+
+#[test]
+fn test_deep_mutual_recursion() {
+    let input = "
+    fn is_even(n: int) -> bool { if n == 0 { true } else { is_odd(n - 1) } }
+    fn is_odd(n: int) -> bool {
+        match n {
+            0 => false,
+            _ => is_even(n - 1),
+        }
+    }
+    print(is_even(1000001));
+    print(is_odd(1000001));
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "false\ntrue\n");
+}
+
+#[test]
+fn test_tail_calls_with_stack_arguments_and_wide_results() {
+    let input = "
+    struct Pair { left: int, right: int }
+    fn rotate(n: int, a: int, b: int, c: int, d: int, e: int, f: int, g: int) -> int {
+        match n { 0 => a * 10 + g, _ => rotate(n - 1, g, a, b, c, d, e, f) }
+    }
+    fn swap(n: int, pair: Pair) -> Pair {
+        if n == 0 { pair } else { swap(n - 1, Pair { left: pair.right, right: pair.left + 1 }) }
+    }
+    let pair = swap(1000001, Pair { left: 0, right: 0 });
+    print(rotate(1000001, 1, 2, 3, 4, 5, 6, 7));
+    print(pair.left);
+    print(pair.right);
+    0
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(0)));
+    assert_eq!(stdout, "65\n500000\n500001\n");
+}
+
+#[test]
+fn test_tail_calls_to_functions_with_more_stack_arguments() {
+    let input = "
+    fn a(x: int, y: int, z: int, w: int, v: int, u: int, t: int) -> int {
+        if x == 0 { t } else { b(x - 1) }
+    }
+    fn b(x: int) -> int {
+        if x == 0 { 7 } else { a(x, 0, 0, 0, 0, 0, x) }
+    }
+    let f = b;
+    print(b(100000));
+    f(100001)
+    ";
+    let common::Outcome {result, stdout, ..} = common::run_test(input);
+    assert!(matches!(result, common::TestResult::Execution(7)));
+    assert_eq!(stdout, "7\n");
+    assert!(common::run_test(input).warnings.is_empty());
+}
+
+#[test]
+fn test_tail_position_calls_that_stay_calls_are_warned_about() {
+    let input = "
+    fn seven(a: int, b: int, c: int, d: int, e: int, f: int, g: int) -> int { a + g }
+    let call = |x: int| seven(x, 0, 0, 0, 0, 0, x);
+    call(1)
+    ";
+    assert_eq!(common::run_test(input).warnings, vec![
+        "call to seven at line 3, column 25 in lambda at line 3, column 16 is in tail position but stays an ordinary call: \
+        it needs 1 word of stack argument space but callers of lambda at line 3, column 16 only reserve 0",
+    ]);
+    let input = "
+    fn eight(a: int, b: int, c: int, d: int, e: int, f: int, g: int, h: int) -> int { a + h }
+    let call = |x: int| eight(x, 0, 0, 0, 0, 0, 0, x);
+    call(1)
+    ";
+    assert_eq!(common::run_test(input).warnings, vec![
+        "call to eight at line 3, column 25 in lambda at line 3, column 16 is in tail position but stays an ordinary call: \
+        it needs 2 words of stack argument space but callers of lambda at line 3, column 16 only reserve 0",
+    ]);
+}
+
+#[test]
+fn test_tail_calls_through_closures() {
+    let input = "
+    fn apply(f: fn(int) -> int, x: int) -> int { f(x) }
+    fn repeat(f: fn(int) -> int, n: int, x: int) -> int {
+        if n == 0 { x } else { repeat(f, n - 1, apply(f, x)) }
+    }
+    let step = 3;
+    repeat(|x| x + step, 1000000, 0) / 100000
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(30)));
+}
+
+#[test]
+fn test_calls_that_are_not_tail_calls_still_work() {
+    let input = "
+    fn factorial(n: int) -> int { if n == 0 { 1 } else { n * factorial(n - 1) } }
+    fn sum(n: int) -> int { let rest = if n == 0 { 0 } else { sum(n - 1) }; rest + n }
+    factorial(5) + sum(10)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(175)));
+}
+
+#[test]
+fn test_tail_calls_are_jumps() {
+    let asm = common::run_test("
+    fn count(n: int) -> int { if n == 0 { 0 } else { count(n - 1) } }
+    count(3)
+    ").asm;
+    assert!(asm.contains("\t\tleave\n\t\tjmp cheer_count\n"));
+    assert!(asm.contains("\t\tcall cheer_count\n"));
+}
+
+#[test]
+fn test_report_tail_calls() {
+    let input = "
+    fn count(n: int) -> int { if n == 0 { 0 } else { count(n - 1) } }
+    fn factorial(n: int) -> int { if n == 0 { 1 } else { n * factorial(n - 1) } }
+    fn six(a: int, b: int, c: int, d: int, e: int, f: int) -> int {
+        seven(a, b, c, d, e, f, 0)
+    }
+    fn seven(a: int, b: int, c: int, d: int, e: int, f: int, g: int) -> int { a + g }
+    let twice = |x: int| count(x) * 2;
+    count(factorial(3)) + six(1, 2, 3, 4, 5, 6) + twice(1)
+    ";
+    let notes = common::run_test(common::Test {input, options: reporting(), ..Default::default()}).notes;
+    assert_eq!(notes, vec![
+        "call to count at line 2, column 54 in function count is a tail call",
+        "call to factorial at line 3, column 62 in function factorial is not a tail call: it isn't in tail position",
+        "call to seven at line 5, column 9 in function six is a tail call",
+        "call to count at line 8, column 26 in lambda at line 8, column 17 is not a tail call: it isn't in tail position",
+        "call to factorial at line 9, column 11 in the top level is not a tail call: the top level has no frame to reuse",
+        "call to count at line 9, column 5 in the top level is not a tail call: the top level has no frame to reuse",
+        "call to six at line 9, column 27 in the top level is not a tail call: the top level has no frame to reuse",
+        "call through a closure at line 9, column 56 in the top level is not a tail call: the top level has no frame to reuse",
+    ]);
+    let unreported = common::run_test(input);
+    assert!(matches!(unreported.result, common::TestResult::Execution(_)));
+    assert!(unreported.notes.is_empty());
+}
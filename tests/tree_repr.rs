@@ -0,0 +1,20 @@
+use rcheer_lib::{ast::{tree_repr, Expr}, parse::{self, ParseResult}, scan};
+
+// the tree of the expression a program ends with
+fn final_expression_tree(source: &str) -> String {
+    let tokens = scan::scan(0, source).tokens;
+    let program = match parse::parse(&tokens) {
+        ParseResult::Ast(program) => program,
+        ParseResult::Error(errors) => panic!("{}", errors[0].message),
+    };
+    match program.main {
+        Some(Expr::Block(block)) => tree_repr(&block.expr.expect("the program should end with an expression"), 0),
+        _ => panic!("expected statements"),
+    }
+}
+
+#[test]
+fn test_if_expressions_print_their_branches() {
+    assert_eq!(final_expression_tree("if x < 1 { 2 } else { 3 }"), "if\n<\n x\n 1\n2\nelse\n3");
+    assert_eq!(final_expression_tree("if x { 2 }"), "if\nx\n2");
+}
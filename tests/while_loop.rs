@@ -0,0 +1,107 @@
+mod common;
+
+#[test]
+fn test_assignment() {
+    let input = "let mut x = 1; let ignored = (x = x + 41); x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(42)));
+}
+
+#[test]
+fn test_while() {
+    let input = "
+    let mut i = 0;
+    let mut sum = 0;
+    let done = while i < 5 { let step = (sum = sum + i); i = i + 1 };
+    sum
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(10)));
+}
+
+#[test]
+fn test_while_never_runs() {
+    let input = "let mut x = 7; let done = while false { x = 0 }; x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(7)));
+}
+
+#[test]
+fn test_nested_while() {
+    let input = "
+    let mut count = 0;
+    let mut i = 0;
+    let done = while i < 4 {
+        let mut j = 0;
+        let inner = while j < i { let step = (count = count + 1); j = j + 1 };
+        i = i + 1
+    };
+    count
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(6)));
+}
+
+#[test]
+fn test_while_in_function() {
+    let input = "
+    fn pow(base: int, exponent: int) -> int {
+        let mut result = 1;
+        let mut n = exponent;
+        let done = while n > 0 { let step = (result = result * base); n = n - 1 };
+        result
+    }
+    pow(3, 4)
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(81)));
+}
+
+#[test]
+fn test_if_without_else_in_loop() {
+    let input = "
+    let mut i = 0;
+    let mut evens = 0;
+    let done = while i < 10 { let step = if i % 2 == 0 { evens = evens + 1 }; i = i + 1 };
+    evens
+    ";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(5)));
+}
+
+#[test]
+fn test_assign_to_shadowed_outer() {
+    // assignment updates the innermost binding with that name
+    let input = "let mut x = 1; { let mut x = 5; x = 6; } x";
+    assert!(matches!(common::run_test(input).result, common::TestResult::Execution(1)));
+}
+
+#[test]
+fn test_assign_immutable() {
+    let input = "let x = 1; x = 2";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_assign_parameter() {
+    let input = "fn f(a: int) -> int { let u = (a = 2); a } f(1)";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_assign_type_error() {
+    let input = "let mut x = 1; x = true";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_while_condition_type_error() {
+    let input = "let mut x = 1; while x { x = 0 }";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_while_is_unit() {
+    let input = "let x = while false { 1 }; x + 1";
+    assert!(matches!(common::run_test(input).result, common::TestResult::TypeError));
+}
+
+#[test]
+fn test_invalid_assignment_target() {
+    let input = "let mut x = 1; x + 1 = 2";
+    assert!(matches!(common::run_test(input).result, common::TestResult::ParseError));
+}